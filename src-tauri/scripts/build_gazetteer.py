@@ -0,0 +1,118 @@
+#!/usr/bin/env python3
+"""オフライン逆ジオコーディング用の地名辞書（resources/geonames/cities.tsv）を GeoNames から生成する
+
+使い方:
+    python3 src-tauri/scripts/build_gazetteer.py              # GeoNames からダウンロードして生成
+    python3 src-tauri/scripts/build_gazetteer.py --source DIR # ダウンロード済みのファイルから生成
+
+DIR には cities15000.zip（または cities15000.txt）、admin1CodesASCII.txt、countryInfo.txt を置く。
+出力の列: name, latitude, longitude, country_code, country, region（タブ区切り、UTF-8）
+"""
+
+import argparse
+import datetime
+import io
+import sys
+import urllib.request
+import zipfile
+from pathlib import Path
+
+GEONAMES_URL = "https://download.geonames.org/export/dump/"
+CITIES_FILE = "cities15000"
+ADMIN1_FILE = "admin1CodesASCII.txt"
+COUNTRY_FILE = "countryInfo.txt"
+DEFAULT_OUTPUT = Path(__file__).resolve().parent.parent / "resources" / "geonames" / "cities.tsv"
+
+
+def read_source(source: Path | None, name: str) -> str:
+    """GeoNames のファイルを読む（source が無ければダウンロード）"""
+    if source is None:
+        print(f"Downloading {GEONAMES_URL}{name}", file=sys.stderr)
+        with urllib.request.urlopen(GEONAMES_URL + name) as response:
+            data = response.read()
+    else:
+        data = (source / name).read_bytes()
+    return data.decode("utf-8")
+
+
+def read_cities(source: Path | None) -> str:
+    """cities15000.txt を読む（zip のままでも展開済みでもよい）"""
+    if source is not None and (source / f"{CITIES_FILE}.txt").exists():
+        return (source / f"{CITIES_FILE}.txt").read_text(encoding="utf-8")
+
+    if source is None:
+        print(f"Downloading {GEONAMES_URL}{CITIES_FILE}.zip", file=sys.stderr)
+        with urllib.request.urlopen(f"{GEONAMES_URL}{CITIES_FILE}.zip") as response:
+            data = response.read()
+    else:
+        data = (source / f"{CITIES_FILE}.zip").read_bytes()
+    with zipfile.ZipFile(io.BytesIO(data)) as archive:
+        return archive.read(f"{CITIES_FILE}.txt").decode("utf-8")
+
+
+def data_rows(content: str):
+    """タブ区切りの行（# で始まるコメント行と空行を除く）"""
+    for line in content.splitlines():
+        if line and not line.startswith("#"):
+            yield line.split("\t")
+
+
+def clean(value: str) -> str:
+    """出力の列にタブ・改行が混ざらないようにする"""
+    return " ".join(value.split())
+
+
+def build(source: Path | None) -> list[str]:
+    # countryInfo.txt: ISO, ISO3, ISO-Numeric, fips, Country, ...
+    countries = {row[0]: row[4] for row in data_rows(read_source(source, COUNTRY_FILE)) if len(row) > 4}
+    # admin1CodesASCII.txt: "JP.40", name, asciiname, geonameid
+    regions = {row[0]: row[1] for row in data_rows(read_source(source, ADMIN1_FILE)) if len(row) > 1}
+
+    rows = []
+    # cities15000.txt: geonameid, name, asciiname, alternatenames, latitude, longitude,
+    # feature class, feature code, country code, cc2, admin1 code, ...
+    for row in data_rows(read_cities(source)):
+        if len(row) < 11:
+            continue
+        geoname_id, name, latitude, longitude, country_code, admin1 = (
+            row[0], row[1], row[4], row[5], row[8], row[10],
+        )
+        if not name or not country_code:
+            continue
+        rows.append((
+            int(geoname_id),
+            "\t".join([
+                clean(name),
+                latitude,
+                longitude,
+                country_code,
+                clean(countries.get(country_code, country_code)),
+                clean(regions.get(f"{country_code}.{admin1}", "")),
+            ]),
+        ))
+
+    # geonameid 順にして、再生成したときの差分を小さくする
+    rows.sort()
+    return [line for _, line in rows]
+
+
+def main() -> None:
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--source", type=Path, help="ダウンロード済みの GeoNames ファイルのディレクトリ")
+    parser.add_argument("--output", type=Path, default=DEFAULT_OUTPUT, help=f"出力先（既定: {DEFAULT_OUTPUT}）")
+    args = parser.parse_args()
+
+    lines = build(args.source)
+    header = [
+        "# オフライン逆ジオコーディング用の地名辞書（タブ区切り、UTF-8）",
+        "# 列: name\tlatitude\tlongitude\tcountry_code\tcountry\tregion",
+        f"# GeoNames の {CITIES_FILE} / {ADMIN1_FILE} / {COUNTRY_FILE} から"
+        f" src-tauri/scripts/build_gazetteer.py で生成（{datetime.date.today().isoformat()}）",
+        "# Data: GeoNames (https://www.geonames.org/), licensed under CC BY 4.0",
+    ]
+    args.output.write_text("\n".join(header + lines) + "\n", encoding="utf-8")
+    print(f"Wrote {len(lines)} places to {args.output}", file=sys.stderr)
+
+
+if __name__ == "__main__":
+    main()
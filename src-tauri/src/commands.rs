@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Write;
 use std::path::Path;
@@ -37,6 +38,20 @@ fn validate_group_description(description: &Option<String>) -> Result<Option<Str
     }
 }
 
+/// スマートグループの検索クエリのバリデーション（1〜1000文字で、解析できること）
+fn validate_smart_query(query: &str) -> Result<String, String> {
+    let trimmed = query.trim();
+    if trimmed.is_empty() {
+        return Err("Smart group query cannot be empty".to_string());
+    }
+    if trimmed.chars().count() > 1000 {
+        return Err("Smart group query is too long (max 1000 characters)".to_string());
+    }
+    crate::query_utils::compile_query(trimmed)
+        .map_err(|e| format!("Invalid smart group query: {}", e))?;
+    Ok(trimmed.to_string())
+}
+
 /// カラーコードのバリデーション（HEX形式: #RGB or #RRGGBB）
 fn validate_color(color: &str) -> Result<String, String> {
     let trimmed = color.trim();
@@ -73,6 +88,77 @@ fn validate_comment(comment: &str) -> Result<String, String> {
     Ok(trimmed.to_string())
 }
 
+/// 画像コメントのバリデーション（最大500文字、空文字列はNULL扱い）
+fn validate_image_comment(comment: &str) -> Result<Option<String>, String> {
+    if comment.trim().is_empty() {
+        return Ok(None);
+    }
+    validate_comment(comment).map(Some)
+}
+
+/// コメント投稿者名のバリデーション（最大100文字、空文字列はNULL扱い）
+fn validate_comment_author(author: &str) -> Result<Option<String>, String> {
+    let trimmed = author.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed.chars().count() > 100 {
+        return Err("Author name is too long (max 100 characters)".to_string());
+    }
+    Ok(Some(trimmed.to_string()))
+}
+
+/// キャプションのバリデーション（最大500文字、空文字列はNULL扱い）
+fn validate_caption(caption: &str) -> Result<Option<String>, String> {
+    let trimmed = caption.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed.chars().count() > 500 {
+        return Err("Caption is too long (max 500 characters)".to_string());
+    }
+    Ok(Some(trimmed.to_string()))
+}
+
+/// 評価値のバリデーション（0〜5）
+fn validate_rating(rating: i64) -> Result<i64, String> {
+    if !(0..=5).contains(&rating) {
+        return Err(format!("Invalid rating: {} (must be 0-5)", rating));
+    }
+    Ok(rating)
+}
+
+/// お気に入りフラグのバリデーション（0 または 1）
+fn validate_is_favorite(is_favorite: i64) -> Result<i64, String> {
+    if is_favorite != 0 && is_favorite != 1 {
+        return Err(format!("Invalid is_favorite value: {} (must be 0 or 1)", is_favorite));
+    }
+    Ok(is_favorite)
+}
+
+/// タグ配列のバリデーション（各1〜100文字、前後の空白除去、重複除去）
+/// "/" 区切りは階層タグとして扱い、各階層の前後の空白も除去する
+fn validate_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let normalized = tag.split('/').map(str::trim).collect::<Vec<_>>().join("/");
+        let trimmed = normalized.as_str();
+        if trimmed.is_empty() {
+            return Err("Tag cannot be empty".to_string());
+        }
+        if trimmed.split('/').any(str::is_empty) {
+            return Err(format!("Tag has an empty level: {}", trimmed));
+        }
+        if trimmed.chars().count() > 100 {
+            return Err(format!("Tag is too long (max 100 characters): {}", trimmed));
+        }
+        if !result.iter().any(|t| t == trimmed) {
+            result.push(trimmed.to_string());
+        }
+    }
+    Ok(result)
+}
+
 #[tauri::command]
 pub async fn initialize_database() -> Result<String, String> {
     crate::db::init_db().await?;
@@ -166,44 +252,408 @@ pub async fn select_directory(app: tauri::AppHandle) -> Result<Option<String>, S
 
 /**
  * 指定されたディレクトリ内の画像・動画をスキャンしてファイルパスのリストを返します
+ * 前回スキャン以降に変更のないファイルはDBの既存メタデータを再利用します
  */
 #[tauri::command]
 pub async fn scan_directory(
     path: String,
 ) -> Result<Vec<ImageFileInfo>, String> {
+    use rusqlite::Connection;
+
     // ディレクトリをスキャン
-    let file_paths = crate::fs_utils::scan_images_in_directory(&path)?;
+    let files = crate::fs_utils::scan_images_in_directory(&path)?;
+
+    // 差分スキャン用の基準情報を取得
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    let baseline = ScanBaseline::load(&conn, &path);
 
     // ファイル情報のリストを作成
-    let result: Vec<ImageFileInfo> = file_paths
-        .into_iter()
-        .map(|file_path| {
-            let file_name = crate::fs_utils::get_file_name(&file_path);
-            let file_type = crate::fs_utils::get_file_type(&file_path);
+    let result = build_file_infos(files, &baseline);
 
-            // 動画の場合のみメタデータ抽出
-            let metadata = if file_type == "video" {
-                crate::video_utils::extract_video_metadata(&file_path).ok()
-            } else {
-                None
-            };
+    println!("Scanned {} files (images and videos)", result.len());
+
+    Ok(result)
+}
+
+/// 前回スキャン時にDBへ記録したファイル情報
+struct KnownFile {
+    file_size: Option<i64>,
+    file_mtime: Option<i64>,
+    content_hash: Option<String>,
+    perceptual_hash: Option<String>,
+    duration_seconds: Option<f64>,
+    width: Option<i32>,
+    height: Option<i32>,
+    video_codec: Option<String>,
+    audio_codec: Option<String>,
+    captured_at: Option<String>,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    lens_model: Option<String>,
+    iso: Option<i64>,
+    exposure_time: Option<f64>,
+    f_number: Option<f64>,
+    orientation: Option<i64>,
+    taken_at: Option<String>,
+    taken_at_source: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    altitude: Option<f64>,
+    metadata_version: Option<i64>,
+    country_code: Option<String>,
+    country: Option<String>,
+    region: Option<String>,
+    city: Option<String>,
+    xmp_sidecar_mtime: Option<i64>,
+    phash_failed_mtime: Option<i64>,
+    metadata_failed_mtime: Option<i64>,
+}
+
+/// 差分スキャンの基準情報（登録済みファイルと最終スキャン日時）
+#[derive(Default)]
+struct ScanBaseline {
+    known_files: HashMap<String, KnownFile>,
+    /// directories.last_scanned_at（UNIX秒）
+    last_scanned_at: Option<i64>,
+}
+
+impl ScanBaseline {
+    /**
+     * ディレクトリ配下の登録済みファイル情報と最終スキャン日時を読み込みます
+     * 読み込みに失敗した場合は空の基準情報を返します（フルスキャン扱い）
+     */
+    fn load(conn: &rusqlite::Connection, dir_path: &str) -> Self {
+        match Self::try_load(conn, dir_path) {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                eprintln!("Warning: Failed to load scan baseline for {}: {}", dir_path, e);
+                Self::default()
+            }
+        }
+    }
+
+    fn try_load(conn: &rusqlite::Connection, dir_path: &str) -> Result<Self, rusqlite::Error> {
+        let last_scanned_at: Option<String> = conn
+            .query_row(
+                "SELECT last_scanned_at FROM directories WHERE path = ?",
+                rusqlite::params![dir_path],
+                |row| row.get(0),
+            )
+            .unwrap_or(None);
+
+        // CURRENT_TIMESTAMP は UTC の "YYYY-MM-DD HH:MM:SS" 形式
+        let last_scanned_at = last_scanned_at.and_then(|t| {
+            chrono::NaiveDateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|dt| dt.and_utc().timestamp())
+        });
+
+        let prefix = if dir_path.ends_with('/') {
+            dir_path.to_string()
+        } else {
+            format!("{}/", dir_path)
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT file_path, file_size, file_mtime, content_hash, perceptual_hash, duration_seconds, width, height, video_codec, audio_codec,
+                captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation,
+                taken_at, taken_at_source, latitude, longitude, altitude, metadata_version,
+                country_code, country, region, city, xmp_sidecar_mtime, phash_failed_mtime, metadata_failed_mtime
+            FROM images WHERE file_path LIKE ?",
+        )?;
+
+        let known_files = stmt
+            .query_map(rusqlite::params![format!("{}%", prefix)], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    KnownFile {
+                        file_size: row.get(1)?,
+                        file_mtime: row.get(2)?,
+                        content_hash: row.get(3)?,
+                        perceptual_hash: row.get(4)?,
+                        duration_seconds: row.get(5)?,
+                        width: row.get(6)?,
+                        height: row.get(7)?,
+                        video_codec: row.get(8)?,
+                        audio_codec: row.get(9)?,
+                        captured_at: row.get(10)?,
+                        camera_make: row.get(11)?,
+                        camera_model: row.get(12)?,
+                        lens_model: row.get(13)?,
+                        iso: row.get(14)?,
+                        exposure_time: row.get(15)?,
+                        f_number: row.get(16)?,
+                        orientation: row.get(17)?,
+                        taken_at: row.get(18)?,
+                        taken_at_source: row.get(19)?,
+                        latitude: row.get(20)?,
+                        longitude: row.get(21)?,
+                        altitude: row.get(22)?,
+                        metadata_version: row.get(23)?,
+                        country_code: row.get(24)?,
+                        country: row.get(25)?,
+                        region: row.get(26)?,
+                        city: row.get(27)?,
+                        xmp_sidecar_mtime: row.get(28)?,
+                        phash_failed_mtime: row.get(29)?,
+                        metadata_failed_mtime: row.get(30)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(Self {
+            known_files,
+            last_scanned_at,
+        })
+    }
+
+    /**
+     * 前回スキャン以降に変更されていないファイルの登録済み情報を返します
+     * サイズ・更新日時が記録済みならその一致で判定し、未記録の行（旧バージョンで登録）は
+     * 更新日時が最終スキャン日時より前かどうかで判定します
+     */
+    fn unchanged(&self, file: &crate::fs_utils::ScannedFile) -> Option<&KnownFile> {
+        let known = self.known_files.get(&file.path)?;
+        let is_unchanged = match (known.file_size, known.file_mtime) {
+            (Some(size), Some(mtime)) => file.size == Some(size) && file.mtime == Some(mtime),
+            _ => matches!((file.mtime, self.last_scanned_at), (Some(m), Some(t)) if m < t),
+        };
+        is_unchanged.then_some(known)
+    }
+}
+
+/**
+ * スキャンしたファイルからファイル情報のリストを作成します
+ * 全ファイルの内容ハッシュ・知覚ハッシュを計算し、動画の場合はffprobeでメタデータを抽出しますが、
+ * 前回スキャン以降に変更のないファイルはDBの既存ハッシュ・メタデータを再利用します
+ * ファイルごとの処理は設定された並列数で実行し、結果はスキャン順を保ちます
+ */
+fn build_file_infos(
+    files: Vec<crate::fs_utils::ScannedFile>,
+    baseline: &ScanBaseline,
+) -> Vec<ImageFileInfo> {
+    let never_cancelled = std::sync::atomic::AtomicBool::new(false);
+    let built = crate::jobs::parallel_map(
+        files,
+        crate::video_utils::metadata_worker_count(),
+        &never_cancelled,
+        |file| build_file_info(file, baseline),
+    );
 
-            ImageFileInfo {
-                file_path,
-                file_name,
-                file_type,
-                duration_seconds: metadata.as_ref().map(|m| m.duration_seconds),
-                width: metadata.as_ref().map(|m| m.width),
-                height: metadata.as_ref().map(|m| m.height),
-                video_codec: metadata.as_ref().map(|m| m.video_codec.clone()),
-                audio_codec: metadata.as_ref().and_then(|m| m.audio_codec.clone()),
+    let mut reused = 0usize;
+    let result: Vec<ImageFileInfo> = built
+        .into_iter()
+        .flatten()
+        .map(|(info, was_reused)| {
+            if was_reused {
+                reused += 1;
             }
+            info
         })
         .collect();
 
-    println!("Scanned {} files (images and videos)", result.len());
+    if reused > 0 {
+        println!("Reused metadata for {} unchanged files", reused);
+    }
 
-    Ok(result)
+    result
+}
+
+/// スキャン時に抽出するメタデータの版（抽出項目を増やしたら上げる）
+/// これより古い版で記録した行は、ファイルに変更がなくても次回スキャンで再抽出する
+const SCAN_METADATA_VERSION: i64 = 1;
+
+// taken_at の取得元
+const TAKEN_AT_SOURCE_EXIF: &str = "exif";
+const TAKEN_AT_SOURCE_VIDEO: &str = "video";
+const TAKEN_AT_SOURCE_MTIME: &str = "mtime";
+
+/// UNIX秒をローカル時刻の "YYYY-MM-DD HH:MM:SS" に変換
+fn format_local_timestamp(secs: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs, 0).map(|dt| {
+        dt.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    })
+}
+
+/**
+ * スキャンした1ファイル分のファイル情報を作成します
+ *
+ * @return (ファイル情報, 既存メタデータを再利用したか)
+ */
+fn build_file_info(
+    file: crate::fs_utils::ScannedFile,
+    baseline: &ScanBaseline,
+) -> (ImageFileInfo, bool) {
+    let file_name = crate::fs_utils::get_file_name(&file.path);
+    let file_type = crate::fs_utils::get_file_type(&file.path);
+
+    let unchanged = baseline.unchanged(&file);
+
+    // XMPサイドカー（前回取り込み時から変更があるもののみ）
+    let known_sidecar_mtime = baseline.known_files.get(&file.path).and_then(|k| k.xmp_sidecar_mtime);
+    let xmp_sidecar = crate::xmp_utils::read_sidecar(&file.path, known_sidecar_mtime);
+
+    // 変更がなくハッシュ計算済みなら再利用
+    let content_hash = match unchanged.and_then(|k| k.content_hash.clone()) {
+        Some(hash) => Some(hash),
+        None => crate::hash_utils::compute_file_hash(&file.path)
+            .map_err(|e| eprintln!("Warning: {}", e))
+            .ok(),
+    };
+
+    // 変更がなく、動画ならメタデータ取得済み・静止画ならサイズ取得済みの場合は再利用
+    // 抽出に失敗したファイルも、変更されるまでは失敗した結果を再利用する（ffprobe のタイムアウトを毎回待たない）
+    // （抽出項目が増える前に記録した行は再抽出する）
+    let known = unchanged.filter(|k| {
+        let extracted = match file_type.as_str() {
+            "video" => k.duration_seconds.is_some(),
+            _ => k.width.is_some(),
+        };
+        let failed = k.metadata_failed_mtime.is_some() && k.metadata_failed_mtime == file.mtime;
+        k.metadata_version.is_some_and(|v| v >= SCAN_METADATA_VERSION) && (extracted || failed)
+    });
+
+    let mut info = ImageFileInfo {
+        file_path: file.path,
+        file_name,
+        file_type,
+        file_size: file.size,
+        file_mtime: file.mtime,
+        content_hash,
+        perceptual_hash: None,
+        duration_seconds: None,
+        width: None,
+        height: None,
+        video_codec: None,
+        audio_codec: None,
+        captured_at: None,
+        camera_make: None,
+        camera_model: None,
+        lens_model: None,
+        iso: None,
+        exposure_time: None,
+        f_number: None,
+        orientation: None,
+        taken_at: None,
+        taken_at_source: None,
+        latitude: None,
+        longitude: None,
+        altitude: None,
+        country_code: None,
+        country: None,
+        region: None,
+        city: None,
+        xmp_sidecar,
+        phash_failed_mtime: None,
+        metadata_failed_mtime: None,
+    };
+
+    if let Some(known) = known {
+        info.duration_seconds = known.duration_seconds;
+        info.width = known.width;
+        info.height = known.height;
+        info.video_codec = known.video_codec.clone();
+        info.audio_codec = known.audio_codec.clone();
+        info.captured_at = known.captured_at.clone();
+        info.camera_make = known.camera_make.clone();
+        info.camera_model = known.camera_model.clone();
+        info.lens_model = known.lens_model.clone();
+        info.iso = known.iso;
+        info.exposure_time = known.exposure_time;
+        info.f_number = known.f_number;
+        info.orientation = known.orientation;
+        info.taken_at = known.taken_at.clone();
+        info.taken_at_source = known.taken_at_source.clone();
+        info.latitude = known.latitude;
+        info.longitude = known.longitude;
+        info.altitude = known.altitude;
+        info.country_code = known.country_code.clone();
+        info.country = known.country.clone();
+        info.region = known.region.clone();
+        info.city = known.city.clone();
+        info.metadata_failed_mtime = known.metadata_failed_mtime;
+    } else if info.file_type == "video" {
+        // 動画の場合のみメタデータ抽出
+        match crate::video_utils::extract_video_metadata(&info.file_path) {
+            Ok(metadata) => {
+                info.duration_seconds = Some(metadata.duration_seconds);
+                info.width = Some(metadata.width);
+                info.height = Some(metadata.height);
+                info.video_codec = Some(metadata.video_codec);
+                info.audio_codec = metadata.audio_codec;
+                info.taken_at = metadata.creation_time.clone();
+                info.taken_at_source = metadata.creation_time.map(|_| TAKEN_AT_SOURCE_VIDEO.to_string());
+                info.set_location(metadata.location);
+            }
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                info.metadata_failed_mtime = info.file_mtime;
+            }
+        }
+    } else {
+        // 静止画はサイズとEXIF/XMPを抽出
+        let metadata = crate::exif_utils::extract_image_metadata(&info.file_path);
+        info.width = metadata.width;
+        info.height = metadata.height;
+        info.captured_at = metadata.captured_at;
+        info.camera_make = metadata.camera_make;
+        info.camera_model = metadata.camera_model;
+        info.lens_model = metadata.lens_model;
+        info.iso = metadata.iso;
+        info.exposure_time = metadata.exposure_time;
+        info.f_number = metadata.f_number;
+        info.orientation = metadata.orientation;
+        info.taken_at = info.captured_at.clone();
+        info.taken_at_source = info.captured_at.as_ref().map(|_| TAKEN_AT_SOURCE_EXIF.to_string());
+        info.set_location(metadata.location);
+        if info.width.is_none() {
+            info.metadata_failed_mtime = info.file_mtime;
+        }
+    }
+
+    // 撮影日時が取れない場合はファイルの更新日時で代用
+    if info.taken_at.is_none() {
+        info.taken_at = info.file_mtime.and_then(format_local_timestamp);
+        info.taken_at_source = info.taken_at.as_ref().map(|_| TAKEN_AT_SOURCE_MTIME.to_string());
+    }
+
+    // 撮影位置があれば地名を求める（同梱の地名辞書で判定、ネットワークは使わない）
+    if info.city.is_none() {
+        if let (Some(latitude), Some(longitude)) = (info.latitude, info.longitude) {
+            info.set_place(crate::geocode_utils::reverse_geocode(latitude, longitude));
+        }
+    }
+
+    // 知覚ハッシュ（動画は代表フレームから）。変更がなく計算済みなら再利用
+    // 計算に失敗したファイルは更新日時を記録し、変更されるまで再計算しない
+    let phash_failed = unchanged.is_some_and(|k| k.phash_failed_mtime.is_some() && k.phash_failed_mtime == info.file_mtime);
+    info.perceptual_hash = match unchanged.and_then(|k| k.perceptual_hash.clone()) {
+        Some(hash) => Some(hash),
+        None if phash_failed => {
+            info.phash_failed_mtime = info.file_mtime;
+            None
+        }
+        None => {
+            let computed = if info.file_type == "video" {
+                crate::video_utils::compute_video_phash(&info.file_path, info.duration_seconds)
+            } else {
+                crate::phash_utils::compute_image_phash(&info.file_path)
+            };
+            computed
+                .map_err(|e| {
+                    eprintln!("Warning: {}", e);
+                    info.phash_failed_mtime = info.file_mtime;
+                })
+                .ok()
+        }
+    };
+
+    (info, known.is_some())
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -212,793 +662,4149 @@ pub struct ImageFileInfo {
     pub file_name: String,
     pub file_type: String,
 
+    // 差分スキャン用
+    pub file_size: Option<i64>,
+    pub file_mtime: Option<i64>,
+
+    // 重複検出用（ファイル内容のBLAKE3ハッシュ）
+    pub content_hash: Option<String>,
+    // 類似画像検出用（dHash、16進文字列）
+    pub perceptual_hash: Option<String>,
+
     // Phase 3追加
     pub duration_seconds: Option<f64>,
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub video_codec: Option<String>,
     pub audio_codec: Option<String>,
+
+    // 静止画のEXIF/XMP
+    pub captured_at: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub iso: Option<i64>,
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub orientation: Option<i64>,
+
+    // 撮影日時（タイムライン・日付検索用）と取得元
+    pub taken_at: Option<String>,
+    pub taken_at_source: Option<String>,
+
+    // 撮影位置（GPS）
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>,
+
+    // 撮影位置の地名（逆ジオコーディング）
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+
+    // 前回の取り込みから変更されたXMPサイドカー（同期時に評価・タグ・コメントへ取り込む）
+    #[serde(skip)]
+    pub xmp_sidecar: Option<crate::xmp_utils::XmpSidecar>,
+
+    // 知覚ハッシュの計算に失敗したときのファイル更新日時
+    #[serde(skip)]
+    pub phash_failed_mtime: Option<i64>,
+
+    // メタデータ（動画の ffprobe・静止画のサイズ）の抽出に失敗したときのファイル更新日時
+    #[serde(skip)]
+    pub metadata_failed_mtime: Option<i64>,
+}
+
+impl ImageFileInfo {
+    fn set_location(&mut self, location: Option<crate::geo_utils::GeoLocation>) {
+        if let Some(location) = location {
+            self.latitude = Some(location.latitude);
+            self.longitude = Some(location.longitude);
+            self.altitude = location.altitude;
+        }
+    }
+
+    fn set_place(&mut self, place: Option<crate::geocode_utils::Place>) {
+        if let Some(place) = place {
+            self.country_code = Some(place.country_code);
+            self.country = Some(place.country);
+            self.region = place.region;
+            self.city = Some(place.city);
+        }
+    }
 }
 
 // ============================================================
-// Phase 4: グループ管理機能
+// 画像メタデータAPI
 // ============================================================
 
+/// 画像メタデータ変更イベント名
+pub const IMAGES_UPDATED_EVENT: &str = "images-updated";
+
+/// 画像のタグをJSON配列として取得する式（images を別名なしで参照するクエリ用）
+/// IMAGE_COLUMNS に concat! で埋め込むため、文字列リテラルとして定義する
+macro_rules! image_tags_sql {
+    () => {
+        "(SELECT json_group_array(t.name ORDER BY it.position) FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = images.id)"
+    };
+}
+
+const IMAGE_TAGS_SQL: &str = image_tags_sql!();
+
+/// SELECT対象のカラム（row_to_image_data と順序を合わせること）
+const IMAGE_COLUMNS: &str = concat!("id, file_path, file_name, file_type, comment, ", image_tags_sql!(), ", rating, is_favorite, created_at, updated_at, duration_seconds, width, height, video_codec, audio_codec, thumbnail_path, directory_id, file_size, file_mtime, COALESCE(is_missing, 0), content_hash, perceptual_hash, captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation, taken_at, taken_at_source, latitude, longitude, altitude, country_code, country, region, city");
+
 /**
- * グループ情報を表す構造体
+ * データベースに登録された画像・動画の情報を表す構造体
  */
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GroupData {
+pub struct ImageData {
     pub id: i64,
-    pub name: String,
-    pub description: Option<String>,
-    pub color: String,
-    pub representative_image_id: Option<i64>,
+    pub file_path: String,
+    pub file_name: String,
+    pub file_type: String,
+    pub comment: Option<String>,
+    pub tags: Vec<String>,
+    pub rating: i64,
+    pub is_favorite: i64,
     pub created_at: String,
     pub updated_at: String,
-    pub image_count: i64,
+    pub duration_seconds: Option<f64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub thumbnail_path: Option<String>,
+    pub directory_id: Option<i64>,
+    pub file_size: Option<i64>,
+    pub file_mtime: Option<i64>,
+    pub is_missing: i64,
+    pub content_hash: Option<String>,
+    pub perceptual_hash: Option<String>,
+    pub captured_at: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub iso: Option<i64>,
+    pub exposure_time: Option<f64>,
+    pub f_number: Option<f64>,
+    pub orientation: Option<i64>,
+    pub taken_at: Option<String>,
+    pub taken_at_source: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
 }
 
 /**
- * グループ作成時の入力データ
+ * 画像メタデータ更新時の入力データ
+ * 指定されたフィールドのみ更新します
  */
 #[derive(Debug, Deserialize)]
-pub struct CreateGroupInput {
-    pub name: String,
-    pub description: Option<String>,
-    pub color: Option<String>,
-    pub representative_image_id: Option<i64>,
+pub struct UpdateImageMetadataInput {
+    pub id: i64,
+    pub comment: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub rating: Option<i64>,
+    pub is_favorite: Option<i64>,
+    /// trueの場合、変更内容をaction_logに記録します（Undo/Redo対象）
+    #[serde(default)]
+    pub record_action: bool,
 }
 
 /**
- * グループ更新時の入力データ
+ * 画像メタデータ一括更新時の入力データ
+ * tags は置き換え、add_tags / remove_tags は既存タグへの追加・削除
  */
 #[derive(Debug, Deserialize)]
-pub struct UpdateGroupInput {
-    pub id: i64,
-    pub name: Option<String>,
-    pub description: Option<String>,
-    pub color: Option<String>,
-    pub representative_image_id: Option<i64>,
+pub struct BulkUpdateImagesInput {
+    pub image_ids: Vec<i64>,
+    pub comment: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub add_tags: Option<Vec<String>>,
+    pub remove_tags: Option<Vec<String>>,
+    pub rating: Option<i64>,
+    pub is_favorite: Option<i64>,
+    #[serde(default)]
+    pub record_action: bool,
 }
 
-/**
- * グループを作成します
- */
-#[tauri::command]
-pub fn create_group(input: CreateGroupInput) -> Result<i64, String> {
-    use rusqlite::Connection;
+/// images-updated イベントのペイロード
+#[derive(Clone, Serialize)]
+pub struct ImagesUpdatedPayload {
+    pub image_ids: Vec<i64>,
+}
 
-    // バリデーション
-    let name = validate_group_name(&input.name)?;
-    let description = validate_group_description(&input.description)?;
-    let color = match &input.color {
-        Some(c) => validate_color(c)?,
-        None => "#3b82f6".to_string(),
+/// バリデーション済みのメタデータ変更内容
+struct ImageMetadataChanges {
+    comment: Option<Option<String>>,
+    tags: Option<Vec<String>>,
+    add_tags: Vec<String>,
+    remove_tags: Vec<String>,
+    rating: Option<i64>,
+    is_favorite: Option<i64>,
+}
+
+impl ImageMetadataChanges {
+    fn validate(
+        comment: &Option<String>,
+        tags: &Option<Vec<String>>,
+        add_tags: &Option<Vec<String>>,
+        remove_tags: &Option<Vec<String>>,
+        rating: Option<i64>,
+        is_favorite: Option<i64>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            comment: comment.as_deref().map(validate_image_comment).transpose()?,
+            tags: tags.as_deref().map(validate_tags).transpose()?,
+            add_tags: validate_tags(add_tags.as_deref().unwrap_or_default())?,
+            remove_tags: validate_tags(remove_tags.as_deref().unwrap_or_default())?,
+            rating: rating.map(validate_rating).transpose()?,
+            is_favorite: is_favorite.map(validate_is_favorite).transpose()?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.comment.is_none()
+            && self.tags.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+            && self.rating.is_none()
+            && self.is_favorite.is_none()
+    }
+}
+
+/// DBに保存されたタグ（JSON配列文字列）をパース
+fn parse_tags(tags: Option<String>) -> Vec<String> {
+    tags.and_then(|t| serde_json::from_str::<Vec<String>>(&t).ok())
+        .unwrap_or_default()
+}
+
+/// タグ配列をDB保存用のJSON配列文字列に変換
+fn serialize_tags(tags: &[String]) -> String {
+    serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 画像のタグを置き換える（順序は tags の並びで保持）
+/// 置き換えでどの画像にも付かなくなったタグは tags テーブルからも削除する
+fn set_image_tags(conn: &rusqlite::Connection, image_id: i64, tags: &[String]) -> Result<(), String> {
+    let previous_tag_ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare("SELECT tag_id FROM image_tags WHERE image_id = ?")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let ids = stmt
+            .query_map(rusqlite::params![image_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to query image tags: {}", e))?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(|e| format!("Failed to collect image tags: {}", e))?;
+        ids
     };
 
-    let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    conn.execute("DELETE FROM image_tags WHERE image_id = ?", rusqlite::params![image_id])
+        .map_err(|e| format!("Failed to clear tags of image {}: {}", image_id, e))?;
 
-    // 代表画像IDが指定されている場合、画像の存在確認
-    if let Some(image_id) = input.representative_image_id {
-        let image_exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM images WHERE id = ?",
-                rusqlite::params![image_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+    for (position, tag) in tags.iter().enumerate() {
+        let tag_id = ensure_tag(conn, tag)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO image_tags (image_id, tag_id, position) VALUES (?, ?, ?)",
+            rusqlite::params![image_id, tag_id, position as i64],
+        )
+        .map_err(|e| format!("Failed to tag image {}: {}", image_id, e))?;
+    }
 
-        if !image_exists {
-            return Err(format!("Representative image with ID {} not found", image_id));
-        }
+    delete_unused_tags(conn, &previous_tag_ids)
+}
+
+/// 階層タグ（"people/family/alice"）の親タグのパス
+fn parent_tag_path(name: &str) -> Option<&str> {
+    name.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+/// タグのIDを取得し、なければ作成する
+/// 階層タグは親タグも作成し、parent_id でつなぐ
+fn ensure_tag(conn: &rusqlite::Connection, name: &str) -> Result<i64, String> {
+    match conn.query_row("SELECT id FROM tags WHERE name = ?", rusqlite::params![name], |row| row.get(0)) {
+        Ok(id) => return Ok(id),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {}
+        Err(e) => return Err(format!("Failed to query tag: {}", e)),
     }
 
+    let parent_id = parent_tag_path(name).map(|parent| ensure_tag(conn, parent)).transpose()?;
     conn.execute(
-        "INSERT INTO groups (name, description, color, representative_image_id) VALUES (?, ?, ?, ?)",
-        rusqlite::params![
-            name,
-            description,
-            color,
-            input.representative_image_id,
-        ],
+        "INSERT INTO tags (name, parent_id) VALUES (?, ?)",
+        rusqlite::params![name, parent_id],
     )
-    .map_err(|e| format!("Failed to create group: {}", e))?;
+    .map_err(|e| format!("Failed to insert tag {}: {}", name, e))?;
 
     Ok(conn.last_insert_rowid())
 }
 
-/**
- * 全グループを取得します（画像数を含む）
- */
-#[tauri::command]
-pub fn get_all_groups() -> Result<Vec<GroupData>, String> {
-    use rusqlite::Connection;
-
-    let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+/// 指定したタグのうち、どの画像にも付いておらず子タグもないものを削除
+/// 削除で空になった親タグもたどって削除する
+fn delete_unused_tags(conn: &rusqlite::Connection, tag_ids: &[i64]) -> Result<(), String> {
+    for &tag_id in tag_ids {
+        let mut current = Some(tag_id);
+        while let Some(id) = current {
+            let parent_id: Option<i64> = match conn.query_row(
+                "SELECT parent_id FROM tags WHERE id = ?",
+                rusqlite::params![id],
+                |row| row.get(0),
+            ) {
+                Ok(parent_id) => parent_id,
+                Err(rusqlite::Error::QueryReturnedNoRows) => break,
+                Err(e) => return Err(format!("Failed to query tag: {}", e)),
+            };
 
-    let mut stmt = conn.prepare(
-        "SELECT
-            g.id,
-            g.name,
-            g.description,
-            g.color,
-            g.representative_image_id,
-            g.created_at,
-            g.updated_at,
-            COUNT(ig.image_id) as image_count
-        FROM groups g
-        LEFT JOIN image_groups ig ON g.id = ig.group_id
-        GROUP BY g.id
-        ORDER BY g.created_at DESC"
-    )
-    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+            let deleted = conn
+                .execute(
+                    "DELETE FROM tags WHERE id = ?
+                        AND NOT EXISTS (SELECT 1 FROM image_tags WHERE tag_id = tags.id)
+                        AND NOT EXISTS (SELECT 1 FROM tags child WHERE child.parent_id = tags.id)",
+                    rusqlite::params![id],
+                )
+                .map_err(|e| format!("Failed to delete unused tag: {}", e))?;
+            current = if deleted > 0 { parent_id } else { None };
+        }
+    }
+    Ok(())
+}
 
-    let groups = stmt.query_map([], |row| {
-        Ok(GroupData {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            color: row.get(3)?,
-            representative_image_id: row.get(4)?,
-            created_at: row.get(5)?,
-            updated_at: row.get(6)?,
-            image_count: row.get(7)?,
-        })
+/// IMAGE_COLUMNS の行を ImageData に変換
+fn row_to_image_data(row: &rusqlite::Row) -> rusqlite::Result<ImageData> {
+    Ok(ImageData {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        file_name: row.get(2)?,
+        file_type: row.get::<_, Option<String>>(3)?.unwrap_or_else(|| "image".to_string()),
+        comment: row.get(4)?,
+        tags: parse_tags(row.get(5)?),
+        rating: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+        is_favorite: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        duration_seconds: row.get(10)?,
+        width: row.get(11)?,
+        height: row.get(12)?,
+        video_codec: row.get(13)?,
+        audio_codec: row.get(14)?,
+        thumbnail_path: row.get(15)?,
+        directory_id: row.get(16)?,
+        file_size: row.get(17)?,
+        file_mtime: row.get(18)?,
+        is_missing: row.get(19)?,
+        content_hash: row.get(20)?,
+        perceptual_hash: row.get(21)?,
+        captured_at: row.get(22)?,
+        camera_make: row.get(23)?,
+        camera_model: row.get(24)?,
+        lens_model: row.get(25)?,
+        iso: row.get(26)?,
+        exposure_time: row.get(27)?,
+        f_number: row.get(28)?,
+        orientation: row.get(29)?,
+        taken_at: row.get(30)?,
+        taken_at_source: row.get(31)?,
+        latitude: row.get(32)?,
+        longitude: row.get(33)?,
+        altitude: row.get(34)?,
+        country_code: row.get(35)?,
+        country: row.get(36)?,
+        region: row.get(37)?,
+        city: row.get(38)?,
     })
-    .map_err(|e| format!("Failed to query groups: {}", e))?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| format!("Failed to collect groups: {}", e))?;
+}
 
-    Ok(groups)
+/// 画像IDから ImageData を取得
+fn query_image_by_id(conn: &rusqlite::Connection, image_id: i64) -> Result<ImageData, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM images WHERE id = ?", IMAGE_COLUMNS),
+        rusqlite::params![image_id],
+        row_to_image_data,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Image with ID {} not found", image_id),
+        e => format!("Failed to query image: {}", e),
+    })
 }
 
 /**
- * グループ情報を更新します
+ * 1画像にメタデータ変更を適用します
+ * 値が変化したフィールドのみ更新し、record_action が true なら
+ * フィールドごとに action_log へ記録します（フロントエンドの update_<field> 形式と同一）
+ *
+ * @return 実際に変更があった場合 true
  */
-#[tauri::command]
-pub fn update_group(input: UpdateGroupInput) -> Result<(), String> {
-    use rusqlite::Connection;
+fn apply_image_metadata_changes(
+    conn: &rusqlite::Connection,
+    image_id: i64,
+    changes: &ImageMetadataChanges,
+    record_action: bool,
+) -> Result<bool, String> {
+    let current = query_image_by_id(conn, image_id)?;
 
-    let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    let mut updates = vec![];
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+    // (action_type, old_value, new_value)
+    let mut log_entries: Vec<(&str, Option<String>, Option<String>)> = vec![];
+
+    if let Some(comment) = &changes.comment {
+        let old = current.comment.clone().filter(|c| !c.is_empty());
+        if old != *comment {
+            updates.push("comment = ?");
+            params.push(Box::new(comment.clone()));
+            log_entries.push(("update_comment", old, comment.clone()));
+        }
+    }
 
-    // グループの存在確認
-    let group_exists: bool = conn
-        .query_row(
-            "SELECT 1 FROM groups WHERE id = ?",
-            rusqlite::params![input.id],
-            |_| Ok(true),
-        )
-        .unwrap_or(false);
+    let mut new_tags = changes.tags.clone().unwrap_or_else(|| current.tags.clone());
+    for tag in &changes.add_tags {
+        if !new_tags.contains(tag) {
+            new_tags.push(tag.clone());
+        }
+    }
+    new_tags.retain(|t| !changes.remove_tags.contains(t));
+    let tags_changed = new_tags != current.tags;
+    if tags_changed {
+        log_entries.push(("update_tags", Some(serialize_tags(&current.tags)), Some(serialize_tags(&new_tags))));
+    }
 
-    if !group_exists {
-        return Err(format!("Group with ID {} not found", input.id));
+    if let Some(rating) = changes.rating {
+        if rating != current.rating {
+            updates.push("rating = ?");
+            params.push(Box::new(rating));
+            log_entries.push(("update_rating", Some(current.rating.to_string()), Some(rating.to_string())));
+        }
     }
 
-    let mut updates = vec![];
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
-
-    // 名前のバリデーションと追加
-    if let Some(name) = input.name {
-        let validated_name = validate_group_name(&name)?;
-        updates.push("name = ?");
-        params.push(Box::new(validated_name));
+    if let Some(is_favorite) = changes.is_favorite {
+        if is_favorite != current.is_favorite {
+            updates.push("is_favorite = ?");
+            params.push(Box::new(is_favorite));
+            log_entries.push((
+                "update_is_favorite",
+                Some(current.is_favorite.to_string()),
+                Some(is_favorite.to_string()),
+            ));
+        }
     }
 
-    // 説明のバリデーションと追加
-    if let Some(description) = input.description {
-        // 空文字列の場合はNULLに設定
-        let trimmed = description.trim();
-        if trimmed.chars().count() > 500 {
-            return Err("Description is too long (max 500 characters)".to_string());
-        }
-        updates.push("description = ?");
-        if trimmed.is_empty() {
-            params.push(Box::new(None::<String>));
-        } else {
-            params.push(Box::new(trimmed.to_string()));
-        }
+    if updates.is_empty() && !tags_changed {
+        return Ok(false);
     }
 
-    // カラーコードのバリデーションと追加
-    if let Some(color) = input.color {
-        let validated_color = validate_color(&color)?;
-        updates.push("color = ?");
-        params.push(Box::new(validated_color));
+    if tags_changed {
+        set_image_tags(conn, image_id, &new_tags)?;
     }
 
-    // 代表画像IDのバリデーションと追加
-    if let Some(rep_id) = input.representative_image_id {
-        // 画像の存在確認
-        let image_exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM images WHERE id = ?",
-                rusqlite::params![rep_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+    updates.push("updated_at = CURRENT_TIMESTAMP");
+    params.push(Box::new(image_id));
 
-        if !image_exists {
-            return Err(format!("Representative image with ID {} not found", rep_id));
-        }
+    let query = format!("UPDATE images SET {} WHERE id = ?", updates.join(", "));
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
 
-        // グループに属しているか確認
-        let image_in_group: bool = conn
-            .query_row(
-                "SELECT 1 FROM image_groups WHERE image_id = ? AND group_id = ?",
-                rusqlite::params![rep_id, input.id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+    conn.execute(&query, params_refs.as_slice())
+        .map_err(|e| format!("Failed to update image {}: {}", image_id, e))?;
 
-        if !image_in_group {
-            return Err(format!(
-                "Image {} does not belong to group {}",
-                rep_id, input.id
-            ));
+    if record_action {
+        for (action_type, old_value, new_value) in log_entries {
+            insert_action_log(conn, action_type, "images", image_id, old_value, new_value)?;
         }
-
-        updates.push("representative_image_id = ?");
-        params.push(Box::new(rep_id));
-    }
-
-    if updates.is_empty() {
-        return Ok(());
     }
 
-    updates.push("updated_at = CURRENT_TIMESTAMP");
-    params.push(Box::new(input.id));
-
-    let query = format!("UPDATE groups SET {} WHERE id = ?", updates.join(", "));
-    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    Ok(true)
+}
 
-    conn.execute(&query, params_refs.as_slice())
-        .map_err(|e| format!("Failed to update group: {}", e))?;
+/// images-updated イベントを発火
+fn emit_images_updated(app: &tauri::AppHandle, image_ids: Vec<i64>) {
+    use tauri::Emitter;
 
-    Ok(())
+    if image_ids.is_empty() {
+        return;
+    }
+    if let Err(e) = app.emit(IMAGES_UPDATED_EVENT, ImagesUpdatedPayload { image_ids }) {
+        eprintln!("Failed to emit {} event: {}", IMAGES_UPDATED_EVENT, e);
+    }
 }
 
 /**
- * グループを削除します
+ * 画像・動画の一覧を取得します（撮影日時の新しい順、撮影日時が未設定なら登録日時）
+ * directory_id を指定した場合はそのディレクトリの画像のみ返します
  */
 #[tauri::command]
-pub fn delete_group(group_id: i64) -> Result<(), String> {
+pub fn get_images(directory_id: Option<i64>) -> Result<Vec<ImageData>, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // CASCADE設定により、image_groups の関連レコードも自動削除される
-    let affected_rows = conn.execute("DELETE FROM groups WHERE id = ?", rusqlite::params![group_id])
-        .map_err(|e| format!("Failed to delete group: {}", e))?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images WHERE (?1 IS NULL OR directory_id = ?1) ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+            IMAGE_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    if affected_rows == 0 {
-        return Err(format!("Group with ID {} not found", group_id));
-    }
+    let images = stmt
+        .query_map(rusqlite::params![directory_id], row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
 
-    Ok(())
+    Ok(images)
 }
 
 /**
- * 画像をグループに追加します
+ * 検索クエリ（rating>=4 tag:travel -tag:blurry type:video taken:2024-05 dir:"Camera Roll" など）に一致する画像を取得します
+ * 空白区切りの項目はすべて満たすものを返し、構文エラーはクエリ中の位置（column）付きで返します
  */
 #[tauri::command]
-pub fn add_images_to_group(image_ids: Vec<i64>, group_id: i64) -> Result<(), String> {
+pub fn query_images(query: String) -> Result<Vec<ImageData>, String> {
     use rusqlite::Connection;
 
-    // 空の配列のチェック
-    if image_ids.is_empty() {
-        return Ok(());
-    }
+    let query = crate::query_utils::compile_query(&query).map_err(|e| e.to_string())?;
 
     let db_path = crate::db::get_db_path()?;
-    let mut conn = Connection::open(&db_path)
+    let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // グループの存在確認
-    let group_exists: bool = conn
-        .query_row(
-            "SELECT 1 FROM groups WHERE id = ?",
-            rusqlite::params![group_id],
-            |_| Ok(true),
-        )
-        .unwrap_or(false);
-
-    if !group_exists {
-        return Err(format!("Group with ID {} not found", group_id));
-    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images WHERE {} ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+            IMAGE_COLUMNS, query.condition
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    // トランザクション開始（複数の書き込み操作を原子的に実行）
-    let tx = conn.transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let images = stmt
+        .query_map(rusqlite::params_from_iter(query.params), row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
 
-    // 各画像の存在確認と追加
-    for image_id in &image_ids {
-        // 画像の存在確認
-        let image_exists: bool = tx
-            .query_row(
-                "SELECT 1 FROM images WHERE id = ?",
-                rusqlite::params![image_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
+    Ok(images)
+}
 
-        if !image_exists {
-            return Err(format!("Image with ID {} not found", image_id));
-        }
+/**
+ * 撮影日ごとの件数
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TakenDateCount {
+    /// "YYYY-MM-DD"
+    pub date: String,
+    pub count: i64,
+}
 
-        // UNIQUE制約により重複挿入は無視される（INSERT OR IGNORE）
-        // ただし、その他のエラー（DB接続エラー等）は検出する
-        tx.execute(
-            "INSERT OR IGNORE INTO image_groups (image_id, group_id) VALUES (?, ?)",
-            rusqlite::params![image_id, group_id],
-        )
-        .map_err(|e| format!("Failed to add image {} to group: {}", image_id, e))?;
+/// 日付範囲の境界を "YYYY-MM-DD HH:MM:SS" に正規化
+/// 日付のみの場合、開始は 00:00:00、終了は 23:59:59 として扱う
+fn parse_date_bound(value: &str, is_end: bool) -> Result<String, String> {
+    let value = value.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if is_end { "23:59:59" } else { "00:00:00" };
+        return Ok(format!("{} {}", date.format("%Y-%m-%d"), time));
     }
-
-    // トランザクションをコミット
-    tx.commit()
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
-
-    Ok(())
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .ok_or_else(|| format!("Invalid date: {} (expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS)", value))
 }
 
 /**
- * 画像をグループから削除します
+ * 撮影日時（taken_at）が指定範囲内の画像・動画を取得します（撮影日時の古い順）
+ * 範囲は両端を含み、省略した側は制限なしです
+ *
+ * @param start 開始日（"YYYY-MM-DD" または "YYYY-MM-DD HH:MM:SS"）
+ * @param end 終了日（同上、日付のみの場合はその日の終わりまで）
+ * @param directory_id 指定時はそのディレクトリの画像のみ
  */
 #[tauri::command]
-pub fn remove_images_from_group(image_ids: Vec<i64>, group_id: i64) -> Result<(), String> {
+pub fn get_images_by_date_range(
+    start: Option<String>,
+    end: Option<String>,
+    directory_id: Option<i64>,
+) -> Result<Vec<ImageData>, String> {
     use rusqlite::Connection;
 
-    // 空の配列のチェック
-    if image_ids.is_empty() {
-        return Ok(());
+    let start = start.as_deref().map(|s| parse_date_bound(s, false)).transpose()?;
+    let end = end.as_deref().map(|e| parse_date_bound(e, true)).transpose()?;
+    if let (Some(start), Some(end)) = (&start, &end) {
+        if start > end {
+            return Err("Start date must be before end date".to_string());
+        }
     }
 
     let db_path = crate::db::get_db_path()?;
-    let mut conn = Connection::open(&db_path)
+    let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // トランザクション開始（複数の書き込み操作を原子的に実行）
-    let tx = conn.transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
-
-    for image_id in &image_ids {
-        tx.execute(
-            "DELETE FROM image_groups WHERE image_id = ? AND group_id = ?",
-            rusqlite::params![image_id, group_id],
-        )
-        .map_err(|e| format!("Failed to remove image from group: {}", e))?;
-    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images
+            WHERE taken_at IS NOT NULL
+                AND (?1 IS NULL OR taken_at >= ?1)
+                AND (?2 IS NULL OR taken_at <= ?2)
+                AND (?3 IS NULL OR directory_id = ?3)
+            ORDER BY taken_at ASC, id ASC",
+            IMAGE_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    // トランザクションをコミット
-    tx.commit()
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    let images = stmt
+        .query_map(rusqlite::params![start, end, directory_id], row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
 
-    Ok(())
+    Ok(images)
 }
 
 /**
- * グループに所属する画像IDの配列を取得します
+ * 撮影日ごとの画像・動画の件数を取得します（日付の新しい順）
+ * タイムラインやカレンダー表示用です
  */
 #[tauri::command]
-pub fn get_group_images(group_id: i64) -> Result<Vec<i64>, String> {
+pub fn get_taken_date_counts(directory_id: Option<i64>) -> Result<Vec<TakenDateCount>, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let mut stmt = conn.prepare("SELECT image_id FROM image_groups WHERE group_id = ?")
+    let mut stmt = conn
+        .prepare(
+            "SELECT substr(taken_at, 1, 10) AS taken_date, COUNT(*)
+            FROM images
+            WHERE taken_at IS NOT NULL AND (?1 IS NULL OR directory_id = ?1)
+            GROUP BY taken_date
+            ORDER BY taken_date DESC",
+        )
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let image_ids = stmt.query_map(rusqlite::params![group_id], |row| {
-        row.get(0)
-    })
-    .map_err(|e| format!("Failed to query group images: {}", e))?
-    .collect::<Result<Vec<i64>, _>>()
-    .map_err(|e| format!("Failed to collect image IDs: {}", e))?;
+    let counts = stmt
+        .query_map(rusqlite::params![directory_id], |row| {
+            Ok(TakenDateCount {
+                date: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query date counts: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect date counts: {}", e))?;
 
-    Ok(image_ids)
+    Ok(counts)
+}
+
+/**
+ * 撮影位置からの距離付きの画像
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NearbyImage {
+    pub image: ImageData,
+    /// 中心からの距離（km）
+    pub distance_km: f64,
 }
 
 /**
- * 画像が所属するグループIDの配列を取得します
+ * 撮影位置が指定範囲（緯度・経度の矩形）内の画像・動画を取得します（撮影日時の新しい順）
+ * min_longitude > max_longitude の場合は日付変更線をまたぐ範囲として扱います
  */
 #[tauri::command]
-pub fn get_image_groups(image_id: i64) -> Result<Vec<i64>, String> {
+pub fn find_images_in_bounds(
+    min_latitude: f64,
+    min_longitude: f64,
+    max_latitude: f64,
+    max_longitude: f64,
+) -> Result<Vec<ImageData>, String> {
     use rusqlite::Connection;
 
+    crate::geo_utils::validate_coordinates(min_latitude, min_longitude)?;
+    crate::geo_utils::validate_coordinates(max_latitude, max_longitude)?;
+    if min_latitude > max_latitude {
+        return Err("min_latitude must be less than or equal to max_latitude".to_string());
+    }
+
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let mut stmt = conn.prepare("SELECT group_id FROM image_groups WHERE image_id = ?")
+    query_images_in_bounds(&conn, min_latitude, min_longitude, max_latitude, max_longitude)
+}
+
+/// 撮影位置が矩形内の画像を取得（日付変更線をまたぐ場合は経度条件を OR にする）
+fn query_images_in_bounds(
+    conn: &rusqlite::Connection,
+    min_latitude: f64,
+    min_longitude: f64,
+    max_latitude: f64,
+    max_longitude: f64,
+) -> Result<Vec<ImageData>, String> {
+    let longitude_condition = if min_longitude <= max_longitude {
+        "longitude BETWEEN ?3 AND ?4"
+    } else {
+        "(longitude >= ?3 OR longitude <= ?4)"
+    };
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images
+            WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+                AND latitude BETWEEN ?1 AND ?2
+                AND {}
+            ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+            IMAGE_COLUMNS, longitude_condition
+        ))
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let group_ids = stmt.query_map(rusqlite::params![image_id], |row| {
-        row.get(0)
-    })
-    .map_err(|e| format!("Failed to query image groups: {}", e))?
-    .collect::<Result<Vec<i64>, _>>()
-    .map_err(|e| format!("Failed to collect group IDs: {}", e))?;
+    let images = stmt
+        .query_map(
+            rusqlite::params![min_latitude, max_latitude, min_longitude, max_longitude],
+            row_to_image_data,
+        )
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
 
-    Ok(group_ids)
+    Ok(images)
 }
 
-// ============================================================
-// Phase 5: グループアルバムビュー & コメント機能
-// ============================================================
-
 /**
- * グループコメント情報を表す構造体
+ * 指定地点から半径 radius_km 以内で撮影された画像・動画を取得します（近い順）
  */
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GroupComment {
-    pub id: i64,
-    pub group_id: i64,
-    pub comment: String,
-    pub created_at: String,
+#[tauri::command]
+pub fn find_images_near(
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+) -> Result<Vec<NearbyImage>, String> {
+    use rusqlite::Connection;
+
+    crate::geo_utils::validate_coordinates(latitude, longitude)?;
+    if !radius_km.is_finite() || radius_km <= 0.0 {
+        return Err(format!("Radius must be greater than 0: {}", radius_km));
+    }
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // 矩形で候補を絞り込んでから正確な距離で判定
+    let (min_lat, min_lon, max_lat, max_lon) = crate::geo_utils::bounding_box(latitude, longitude, radius_km);
+    let candidates = query_images_in_bounds(&conn, min_lat, min_lon, max_lat, max_lon)?;
+
+    let mut nearby: Vec<NearbyImage> = candidates
+        .into_iter()
+        .filter_map(|image| {
+            let distance_km = crate::geo_utils::haversine_km(
+                latitude,
+                longitude,
+                image.latitude?,
+                image.longitude?,
+            );
+            (distance_km <= radius_km).then_some(NearbyImage { image, distance_km })
+        })
+        .collect();
+    nearby.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+
+    Ok(nearby)
 }
 
 /**
- * グループコメント追加時の入力データ
+ * 地名ごとの件数
  */
-#[derive(Debug, Deserialize)]
-pub struct AddCommentInput {
-    pub group_id: i64,
-    pub comment: String,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaceCount {
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub count: i64,
 }
 
 /**
- * グループIDから詳細情報を取得します
+ * 画像・動画に記録された地名（国・地域・都市）の一覧を件数付きで取得します（件数の多い順）
+ * 地名フィルターの選択肢用です
  */
 #[tauri::command]
-pub fn get_group_by_id(group_id: i64) -> Result<GroupData, String> {
+pub fn list_places() -> Result<Vec<PlaceCount>, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let mut stmt = conn.prepare(
-        "SELECT
-            g.id,
-            g.name,
-            g.description,
-            g.color,
-            g.representative_image_id,
-            g.created_at,
-            g.updated_at,
-            COUNT(ig.image_id) as image_count
-        FROM groups g
-        LEFT JOIN image_groups ig ON g.id = ig.group_id
-        WHERE g.id = ?
-        GROUP BY g.id"
-    )
-    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT country_code, country, region, city, COUNT(*) AS image_count
+            FROM images
+            WHERE city IS NOT NULL
+            GROUP BY country_code, country, region, city
+            ORDER BY image_count DESC, country, region, city",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let group = stmt.query_row(rusqlite::params![group_id], |row| {
-        Ok(GroupData {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2)?,
-            color: row.get(3)?,
-            representative_image_id: row.get(4)?,
-            created_at: row.get(5)?,
-            updated_at: row.get(6)?,
-            image_count: row.get(7)?,
+    let places = stmt
+        .query_map([], |row| {
+            Ok(PlaceCount {
+                country_code: row.get(0)?,
+                country: row.get(1)?,
+                region: row.get(2)?,
+                city: row.get(3)?,
+                count: row.get(4)?,
+            })
         })
-    })
-    .map_err(|e| format!("Failed to query group: {}", e))?;
+        .map_err(|e| format!("Failed to query places: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect places: {}", e))?;
 
-    Ok(group)
+    Ok(places)
 }
 
 /**
- * グループの代表画像を設定します
- * 代表画像はそのグループに属している必要があります
+ * 地名で画像・動画を絞り込みます（撮影日時の新しい順）
+ * 指定した項目をすべて満たす画像を返します（大文字小文字は区別しない）
+ *
+ * @param country 国名または国コード（"Japan" / "JP"）
+ * @param region 都道府県・州など
+ * @param city 都市名
  */
 #[tauri::command]
-pub fn set_representative_image(group_id: i64, image_id: Option<i64>) -> Result<(), String> {
+pub fn get_images_by_place(
+    country: Option<String>,
+    region: Option<String>,
+    city: Option<String>,
+) -> Result<Vec<ImageData>, String> {
     use rusqlite::Connection;
 
+    let normalize = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let (country, region, city) = (normalize(country), normalize(region), normalize(city));
+    if country.is_none() && region.is_none() && city.is_none() {
+        return Err("At least one of country, region or city is required".to_string());
+    }
+
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // NULLに設定する場合はチェック不要
-    if let Some(id) = image_id {
-        // 画像の存在確認
-        let image_exists: bool = conn
-            .query_row(
-                "SELECT 1 FROM images WHERE id = ?",
-                rusqlite::params![id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
-
-        if !image_exists {
-            return Err(format!("Image with ID {} not found", id));
-        }
-
-        // グループに属しているか確認
-        let image_in_group: bool = conn
-            .query_row(
-                "SELECT 1 FROM image_groups WHERE image_id = ? AND group_id = ?",
-                rusqlite::params![id, group_id],
-                |_| Ok(true),
-            )
-            .unwrap_or(false);
-
-        if !image_in_group {
-            return Err(format!(
-                "Image {} does not belong to group {}",
-                id, group_id
-            ));
-        }
-    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images
+            WHERE (?1 IS NULL OR country = ?1 COLLATE NOCASE OR country_code = ?1 COLLATE NOCASE)
+                AND (?2 IS NULL OR region = ?2 COLLATE NOCASE)
+                AND (?3 IS NULL OR city = ?3 COLLATE NOCASE)
+            ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+            IMAGE_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    conn.execute(
-        "UPDATE groups SET representative_image_id = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
-        rusqlite::params![image_id, group_id],
-    )
-    .map_err(|e| format!("Failed to set representative image: {}", e))?;
+    let images = stmt
+        .query_map(rusqlite::params![country, region, city], row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
 
-    Ok(())
+    Ok(images)
 }
 
 /**
- * グループコメントを追加します
+ * 撮影位置を持つ全画像の地名を同梱の地名辞書で求め直します
+ * 地名辞書を差し替えた後などに使用します。近くに都市がない画像の地名は消去します
+ *
+ * @return 地名が変わった画像の数
  */
 #[tauri::command]
-pub fn add_group_comment(input: AddCommentInput) -> Result<i64, String> {
+pub async fn reverse_geocode_images(app: tauri::AppHandle) -> Result<i64, String> {
     use rusqlite::Connection;
 
-    // コメントのバリデーション
-    let comment = validate_comment(&input.comment)?;
-
     let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
+    let mut conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // グループの存在確認
-    let group_exists: bool = conn
-        .query_row(
-            "SELECT 1 FROM groups WHERE id = ?",
-            rusqlite::params![input.group_id],
-            |_| Ok(true),
-        )
-        .unwrap_or(false);
+    type PlaceColumns = (Option<String>, Option<String>, Option<String>, Option<String>);
+    let rows: Vec<(i64, f64, f64, PlaceColumns)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, latitude, longitude, country_code, country, region, city
+                FROM images WHERE latitude IS NOT NULL AND longitude IS NOT NULL",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    (row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?),
+                ))
+            })
+            .map_err(|e| format!("Failed to query images: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect images: {}", e))?;
+        rows
+    };
 
-    if !group_exists {
-        return Err(format!("Group with ID {} not found", input.group_id));
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut changed_ids = Vec::new();
+    for (id, latitude, longitude, current) in rows {
+        let place = crate::geocode_utils::reverse_geocode(latitude, longitude);
+        let next: PlaceColumns = match place {
+            Some(p) => (Some(p.country_code), Some(p.country), p.region, Some(p.city)),
+            None => (None, None, None, None),
+        };
+        if next == current {
+            continue;
+        }
+
+        tx.execute(
+            "UPDATE images SET country_code = ?, country = ?, region = ?, city = ? WHERE id = ?",
+            rusqlite::params![next.0, next.1, next.2, next.3, id],
+        )
+        .map_err(|e| format!("Failed to update image {}: {}", id, e))?;
+        changed_ids.push(id);
     }
 
-    conn.execute(
-        "INSERT INTO group_comments (group_id, comment) VALUES (?, ?)",
-        rusqlite::params![input.group_id, comment],
-    )
-    .map_err(|e| format!("Failed to add group comment: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
-    Ok(conn.last_insert_rowid())
+    let changed = changed_ids.len() as i64;
+    println!("Reverse geocoded images: {} changed", changed);
+    emit_images_updated(&app, changed_ids);
+
+    Ok(changed)
 }
 
 /**
- * グループの全コメントを取得します（新しい順）
+ * 画像1件のメタデータ（comment, tags, rating, is_favorite）を更新します
+ * 更新後の画像データを返します
  */
 #[tauri::command]
-pub fn get_group_comments(group_id: i64) -> Result<Vec<GroupComment>, String> {
+pub fn update_image_metadata(
+    app: tauri::AppHandle,
+    input: UpdateImageMetadataInput,
+) -> Result<ImageData, String> {
     use rusqlite::Connection;
 
+    let changes = ImageMetadataChanges::validate(
+        &input.comment,
+        &input.tags,
+        &None,
+        &None,
+        input.rating,
+        input.is_favorite,
+    )?;
+
     let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
+    let mut conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let mut stmt = conn.prepare(
-        "SELECT id, group_id, comment, created_at
-        FROM group_comments
-        WHERE group_id = ?
-        ORDER BY created_at DESC"
-    )
-    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
 
-    let comments = stmt.query_map(rusqlite::params![group_id], |row| {
-        Ok(GroupComment {
-            id: row.get(0)?,
-            group_id: row.get(1)?,
-            comment: row.get(2)?,
-            created_at: row.get(3)?,
-        })
-    })
-    .map_err(|e| format!("Failed to query group comments: {}", e))?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| format!("Failed to collect comments: {}", e))?;
+    let changed = apply_image_metadata_changes(&tx, input.id, &changes, input.record_action)?;
 
-    Ok(comments)
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    if changed {
+        write_enabled_xmp_sidecars(&conn, &[input.id]);
+        emit_images_updated(&app, vec![input.id]);
+    }
+
+    query_image_by_id(&conn, input.id)
 }
 
 /**
- * グループコメントを削除します
+ * 複数画像のメタデータを一括更新します
+ * 全件を1トランザクションで処理し、1件でも失敗した場合はロールバックします
+ *
+ * @return 実際に変更された画像数
  */
 #[tauri::command]
-pub fn delete_group_comment(comment_id: i64) -> Result<(), String> {
+pub fn bulk_update_images(
+    app: tauri::AppHandle,
+    input: BulkUpdateImagesInput,
+) -> Result<i64, String> {
     use rusqlite::Connection;
 
+    if input.image_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let changes = ImageMetadataChanges::validate(
+        &input.comment,
+        &input.tags,
+        &input.add_tags,
+        &input.remove_tags,
+        input.rating,
+        input.is_favorite,
+    )?;
+
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
     let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
+    let mut conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    conn.execute(
-        "DELETE FROM group_comments WHERE id = ?",
-        rusqlite::params![comment_id],
-    )
-    .map_err(|e| format!("Failed to delete group comment: {}", e))?;
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
 
-    Ok(())
+    let mut changed_ids = Vec::new();
+    for image_id in &input.image_ids {
+        if apply_image_metadata_changes(&tx, *image_id, &changes, input.record_action)? {
+            changed_ids.push(*image_id);
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    let changed_count = changed_ids.len() as i64;
+    write_enabled_xmp_sidecars(&conn, &changed_ids);
+    emit_images_updated(&app, changed_ids);
+
+    Ok(changed_count)
 }
 
 // ============================================================
-// Phase 6: マルチディレクトリ管理
+// タグ管理（ライブラリ全体のタグの名前変更・移動・統合・削除）
 // ============================================================
 
+/// タグ名 ?1 とその子孫のタグに一致する条件（tags を別名なしで参照するクエリ用）
+const TAG_SUBTREE_CONDITION: &str = "(tags.name = ?1 OR substr(tags.name, 1, length(?1) + 1) = ?1 || '/')";
+
 /**
- * ディレクトリ情報を表す構造体
+ * タグと付いている画像数
  */
 #[derive(Debug, Serialize, Deserialize)]
-pub struct DirectoryData {
+pub struct TagCount {
     pub id: i64,
-    pub path: String,
+    /// 階層タグは "/" 区切りのパス（"people/family/alice"）
     pub name: String,
-    pub is_active: i64,
-    pub last_scanned_at: Option<String>,
-    pub file_count: i64,
-    pub created_at: String,
+    pub parent_id: Option<i64>,
+    /// このタグまたは子孫のタグが付いている画像の数
+    pub count: i64,
 }
 
-/**
- * ディレクトリを追加し、初回スキャンを実行します
- */
-#[tauri::command]
-pub async fn add_directory(path: String) -> Result<DirectoryData, String> {
-    use rusqlite::Connection;
+/// タグ名からタグIDを取得
+fn find_tag_id(conn: &rusqlite::Connection, name: &str) -> Result<i64, String> {
+    conn.query_row("SELECT id FROM tags WHERE name = ?", rusqlite::params![name], |row| row.get(0))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Tag not found: {}", name),
+            e => format!("Failed to query tag: {}", e),
+        })
+}
 
-    let dir_path = Path::new(&path);
-    if !dir_path.exists() || !dir_path.is_dir() {
-        return Err(format!("Directory does not exist: {}", path));
-    }
+/// タグとその子孫のタグ (id, name) の一覧（親が先）
+fn tag_subtree(conn: &rusqlite::Connection, name: &str) -> Result<Vec<(i64, String)>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT id, name FROM tags WHERE {} ORDER BY name", TAG_SUBTREE_CONDITION))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let name = dir_path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    let tags = stmt
+        .query_map(rusqlite::params![name], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query tags: {}", e))?
+        .collect::<Result<Vec<(i64, String)>, _>>()
+        .map_err(|e| format!("Failed to collect tags: {}", e))?;
 
-    let db_path = crate::db::get_db_path()?;
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    Ok(tags)
+}
 
-    // 既存チェック
-    let existing: Option<i64> = conn
-        .query_row(
-            "SELECT id FROM directories WHERE path = ?",
-            rusqlite::params![path],
-            |row| row.get(0),
-        )
-        .ok();
+/// いずれかのタグが付いている画像IDの一覧
+fn tagged_image_ids(conn: &rusqlite::Connection, tag_ids: &[i64]) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare("SELECT image_id FROM image_tags WHERE tag_id = ? ORDER BY image_id")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    if let Some(existing_id) = existing {
-        // 既存なら再アクティブ化
-        conn.execute(
-            "UPDATE directories SET is_active = 1 WHERE id = ?",
-            rusqlite::params![existing_id],
-        )
-        .map_err(|e| format!("Failed to reactivate directory: {}", e))?;
-    } else {
-        conn.execute(
-            "INSERT INTO directories (path, name) VALUES (?, ?)",
-            rusqlite::params![path, name],
-        )
-        .map_err(|e| format!("Failed to add directory: {}", e))?;
+    let mut image_ids: Vec<i64> = Vec::new();
+    for tag_id in tag_ids {
+        let ids = stmt
+            .query_map(rusqlite::params![tag_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to query tagged images: {}", e))?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(|e| format!("Failed to collect tagged images: {}", e))?;
+        for id in ids {
+            if !image_ids.contains(&id) {
+                image_ids.push(id);
+            }
+        }
     }
 
-    // ディレクトリIDを取得
-    let dir_id: i64 = conn
-        .query_row(
-            "SELECT id FROM directories WHERE path = ?",
-            rusqlite::params![path],
-            |row| row.get(0),
-        )
-        .map_err(|e| format!("Failed to get directory id: {}", e))?;
-
-    // 既存画像のバックフィル（パスがこのディレクトリに含まれる画像のdirectory_idを設定）
-    let prefix = if path.ends_with('/') {
-        path.clone()
-    } else {
-        format!("{}/", path)
-    };
-    conn.execute(
-        "UPDATE images SET directory_id = ? WHERE file_path LIKE ? AND directory_id IS NULL",
-        rusqlite::params![dir_id, format!("{}%", prefix)],
-    )
-    .map_err(|e| format!("Failed to backfill directory_id: {}", e))?;
-
-    // ファイル数を更新
-    let file_count: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM images WHERE directory_id = ?",
-            rusqlite::params![dir_id],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+    Ok(image_ids)
+}
 
-    conn.execute(
-        "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
-        rusqlite::params![file_count, dir_id],
-    )
-    .map_err(|e| format!("Failed to update directory file count: {}", e))?;
+/// タグ名を1件バリデーション（前後の空白を除去）
+fn validate_tag_name(name: &str) -> Result<String, String> {
+    Ok(validate_tags(&[name.to_string()])?.remove(0))
+}
 
-    // 最新のディレクトリ情報を返す
-    let dir = conn
-        .query_row(
-            "SELECT id, path, name, is_active, last_scanned_at, file_count, created_at FROM directories WHERE id = ?",
-            rusqlite::params![dir_id],
-            |row| {
-                Ok(DirectoryData {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    name: row.get(2)?,
-                    is_active: row.get(3)?,
-                    last_scanned_at: row.get(4)?,
-                    file_count: row.get(5)?,
-                    created_at: row.get(6)?,
-                })
-            },
+/// タグの変更があった画像の更新日時を更新
+fn touch_images(conn: &rusqlite::Connection, image_ids: &[i64]) -> Result<(), String> {
+    for image_id in image_ids {
+        conn.execute(
+            "UPDATE images SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            rusqlite::params![image_id],
         )
-        .map_err(|e| format!("Failed to get directory data: {}", e))?;
-
-    Ok(dir)
+        .map_err(|e| format!("Failed to update image {}: {}", image_id, e))?;
+    }
+    Ok(())
 }
 
 /**
- * ディレクトリを削除します（画像データは保持、directory_idをNULLに）
+ * ライブラリ内のタグ一覧を画像数付きで取得します（件数の多い順）
+ * 件数には子孫のタグが付いた画像も含みます（タグで絞り込んだときの件数と同じ）
  */
 #[tauri::command]
-pub fn remove_directory(directory_id: i64) -> Result<(), String> {
+pub fn list_tags() -> Result<Vec<TagCount>, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // 画像のdirectory_idをNULLに
-    conn.execute(
-        "UPDATE images SET directory_id = NULL WHERE directory_id = ?",
-        rusqlite::params![directory_id],
-    )
-    .map_err(|e| format!("Failed to unlink images: {}", e))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.parent_id,
+                (SELECT COUNT(DISTINCT it.image_id) FROM image_tags it
+                JOIN tags d ON d.id = it.tag_id
+                WHERE d.name = t.name OR substr(d.name, 1, length(t.name) + 1) = t.name || '/') AS image_count
+            FROM tags t
+            ORDER BY image_count DESC, t.name",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    conn.execute(
-        "DELETE FROM directories WHERE id = ?",
-        rusqlite::params![directory_id],
-    )
-    .map_err(|e| format!("Failed to delete directory: {}", e))?;
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(TagCount {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query tags: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect tags: {}", e))?;
 
-    Ok(())
+    Ok(tags)
 }
 
 /**
- * 全ディレクトリを取得します
+ * タグで画像・動画を絞り込みます（撮影日時の新しい順）
+ * 子孫のタグが付いた画像も含みます（"people" で "people/family/alice" の画像も返す）
  */
 #[tauri::command]
-pub fn get_all_directories() -> Result<Vec<DirectoryData>, String> {
+pub fn get_images_by_tag(name: String) -> Result<Vec<ImageData>, String> {
+    use rusqlite::Connection;
+
+    let name = validate_tag_name(&name)?;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images
+            WHERE id IN (
+                SELECT image_tags.image_id FROM image_tags
+                JOIN tags ON tags.id = image_tags.tag_id
+                WHERE {}
+            )
+            ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+            IMAGE_COLUMNS, TAG_SUBTREE_CONDITION
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let images = stmt
+        .query_map(rusqlite::params![name], row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
+
+    Ok(images)
+}
+
+/// タグと子孫のタグのパスを new_name 以下に付け替え、タグが付いている画像IDを返す
+/// 付け替え先に同名のタグがある場合や、自分の子孫の下に移動する場合はエラー
+fn rename_tag_subtree(conn: &rusqlite::Connection, name: &str, new_name: &str) -> Result<Vec<i64>, String> {
+    let tag_id = find_tag_id(conn, name)?;
+    if new_name.starts_with(&format!("{}/", name)) {
+        return Err(format!("Cannot move tag {} under its own descendant {}", name, new_name));
+    }
+
+    let subtree = tag_subtree(conn, name)?;
+    let renamed: Vec<(i64, String)> = subtree
+        .iter()
+        .map(|(id, path)| (*id, format!("{}{}", new_name, &path[name.len()..])))
+        .collect();
+    for (_, path) in &renamed {
+        if let Ok(existing_id) = find_tag_id(conn, path) {
+            if !subtree.iter().any(|(id, _)| *id == existing_id) {
+                return Err(format!("Tag already exists: {} (use merge_tags to combine tags)", path));
+            }
+        }
+    }
+
+    let old_parent_id: Option<i64> = conn
+        .query_row("SELECT parent_id FROM tags WHERE id = ?", rusqlite::params![tag_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query tag: {}", e))?;
+    let new_parent_id = parent_tag_path(new_name).map(|parent| ensure_tag(conn, parent)).transpose()?;
+
+    for (id, path) in &renamed {
+        conn.execute("UPDATE tags SET name = ? WHERE id = ?", rusqlite::params![path, id])
+            .map_err(|e| format!("Failed to rename tag: {}", e))?;
+    }
+    conn.execute(
+        "UPDATE tags SET parent_id = ? WHERE id = ?",
+        rusqlite::params![new_parent_id, tag_id],
+    )
+    .map_err(|e| format!("Failed to move tag: {}", e))?;
+    delete_unused_tags(conn, &old_parent_id.into_iter().collect::<Vec<_>>())?;
+
+    let subtree_ids: Vec<i64> = subtree.iter().map(|(id, _)| *id).collect();
+    let image_ids = tagged_image_ids(conn, &subtree_ids)?;
+    touch_images(conn, &image_ids)?;
+
+    Ok(image_ids)
+}
+
+/// rename_tag / move_tag の共通処理
+fn rename_tag_path(app: &tauri::AppHandle, name: &str, new_name: &str) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    find_tag_id(&tx, name)?;
+    if name == new_name {
+        return Ok(0);
+    }
+    let image_ids = rename_tag_subtree(&tx, name, new_name)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Renamed tag {} to {} ({} images)", name, new_name, image_ids.len());
+
+    let renamed = image_ids.len() as i64;
+    write_enabled_xmp_sidecars(&conn, &image_ids);
+    emit_images_updated(app, image_ids);
+
+    Ok(renamed)
+}
+
+/**
+ * タグの名前をライブラリ全体で変更します
+ * 子孫のタグも合わせて付け替えます（"people" → "persons" で "persons/family/alice" に）
+ * 新しい名前を "/" 区切りにすると別の親の下に移動します
+ * 変更後の名前のタグが既にある場合はエラーになります（統合には merge_tags を使用）
+ *
+ * @return タグ（子孫を含む）が付いている画像の数
+ */
+#[tauri::command]
+pub fn rename_tag(app: tauri::AppHandle, name: String, new_name: String) -> Result<i64, String> {
+    let new_name = validate_tag_name(&new_name)?;
+    rename_tag_path(&app, &name, &new_name)
+}
+
+/**
+ * タグを子孫ごと別の親タグの下に移動します（親タグがなければ作成）
+ *
+ * @param new_parent 移動先の親タグ（None の場合は最上位に移動）
+ * @return タグ（子孫を含む）が付いている画像の数
+ */
+#[tauri::command]
+pub fn move_tag(app: tauri::AppHandle, name: String, new_parent: Option<String>) -> Result<i64, String> {
+    let leaf = name.rsplit('/').next().unwrap_or(&name).to_string();
+    let new_name = match new_parent {
+        Some(parent) => validate_tag_name(&format!("{}/{}", parent, leaf))?,
+        None => validate_tag_name(&leaf)?,
+    };
+    rename_tag_path(&app, &name, &new_name)
+}
+
+/**
+ * 複数のタグを1つのタグに統合します
+ * 統合元のタグが付いた画像には統合先のタグが付き、統合元のタグは削除されます
+ * 統合先のタグがなければ作成します。子タグを持つタグは統合元にできません
+ *
+ * @return タグが変わった画像の数
+ */
+#[tauri::command]
+pub fn merge_tags(app: tauri::AppHandle, source_names: Vec<String>, target_name: String) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    let target_name = validate_tag_name(&target_name)?;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut source_ids = Vec::new();
+    for name in &source_names {
+        let tag_id = find_tag_id(&tx, name)?;
+        if name != &target_name && tag_subtree(&tx, name)?.len() > 1 {
+            return Err(format!("Tag has child tags: {} (move them before merging)", name));
+        }
+        if !source_ids.contains(&tag_id) {
+            source_ids.push(tag_id);
+        }
+    }
+
+    let target_id = ensure_tag(&tx, &target_name)?;
+    source_ids.retain(|id| *id != target_id);
+    let image_ids = tagged_image_ids(&tx, &source_ids)?;
+
+    let mut parent_ids = Vec::new();
+    for source_id in &source_ids {
+        let parent_id: Option<i64> = tx
+            .query_row("SELECT parent_id FROM tags WHERE id = ?", rusqlite::params![source_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to query tag: {}", e))?;
+        parent_ids.extend(parent_id);
+
+        // 統合先のタグが既に付いている画像は、統合元のタグを外すだけにする
+        tx.execute(
+            "UPDATE OR IGNORE image_tags SET tag_id = ?1 WHERE tag_id = ?2",
+            rusqlite::params![target_id, source_id],
+        )
+        .map_err(|e| format!("Failed to merge tags: {}", e))?;
+        tx.execute("DELETE FROM image_tags WHERE tag_id = ?", rusqlite::params![source_id])
+            .map_err(|e| format!("Failed to merge tags: {}", e))?;
+        tx.execute("DELETE FROM tags WHERE id = ?", rusqlite::params![source_id])
+            .map_err(|e| format!("Failed to delete tag: {}", e))?;
+    }
+    // 統合元が空だった場合に作成した統合先のタグや、空になった親タグを残さない
+    parent_ids.push(target_id);
+    delete_unused_tags(&tx, &parent_ids)?;
+    touch_images(&tx, &image_ids)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Merged tags into {} ({} images)", target_name, image_ids.len());
+
+    let merged = image_ids.len() as i64;
+    write_enabled_xmp_sidecars(&conn, &image_ids);
+    emit_images_updated(&app, image_ids);
+
+    Ok(merged)
+}
+
+/**
+ * タグを子孫のタグごとライブラリ全体から削除します（すべての画像から外します）
+ *
+ * @return タグが外れた画像の数
+ */
+#[tauri::command]
+pub fn delete_tag(app: tauri::AppHandle, name: String) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let tag_id = find_tag_id(&tx, &name)?;
+    let parent_id: Option<i64> = tx
+        .query_row("SELECT parent_id FROM tags WHERE id = ?", rusqlite::params![tag_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query tag: {}", e))?;
+    let subtree_ids: Vec<i64> = tag_subtree(&tx, &name)?.into_iter().map(|(id, _)| id).collect();
+    let image_ids = tagged_image_ids(&tx, &subtree_ids)?;
+
+    for id in &subtree_ids {
+        tx.execute("DELETE FROM image_tags WHERE tag_id = ?", rusqlite::params![id])
+            .map_err(|e| format!("Failed to untag images: {}", e))?;
+        tx.execute("DELETE FROM tags WHERE id = ?", rusqlite::params![id])
+            .map_err(|e| format!("Failed to delete tag: {}", e))?;
+    }
+    delete_unused_tags(&tx, &parent_id.into_iter().collect::<Vec<_>>())?;
+    touch_images(&tx, &image_ids)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Deleted tag {} ({} images)", name, image_ids.len());
+
+    let deleted = image_ids.len() as i64;
+    write_enabled_xmp_sidecars(&conn, &image_ids);
+    emit_images_updated(&app, image_ids);
+
+    Ok(deleted)
+}
+
+// ============================================================
+// Phase 4: グループ管理機能
+// ============================================================
+
+/**
+ * グループ情報を表す構造体
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupData {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: String,
+    pub representative_image_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// 子孫のグループを含めた重複のない画像数（スマートグループは検索クエリを評価した件数）
+    pub image_count: i64,
+    /// スマートグループの検索クエリ（手動で画像を追加するグループは None）
+    pub smart_query: Option<String>,
+    /// 親グループのID（最上位のグループは None）
+    pub parent_id: Option<i64>,
+    /// 手動で並べ替えた順番（小さいほど前）
+    pub sort_order: i64,
+}
+
+/// グループの並び順（get_all_groups / get_group_tree）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSort {
+    /// reorder_groups で並べ替えた順
+    Manual,
+    /// 名前順（大文字小文字を区別しない）
+    Name,
+    /// 作成日時の新しい順
+    Created,
+    /// 更新日時の新しい順
+    Updated,
+    /// 画像数の多い順
+    ImageCount,
+}
+
+/**
+ * グループの階層（get_group_tree の戻り値）
+ */
+#[derive(Debug, Serialize)]
+pub struct GroupTreeNode {
+    #[serde(flatten)]
+    pub group: GroupData,
+    pub children: Vec<GroupTreeNode>,
+}
+
+/// GroupData の列（image_count は fill_group_image_counts で埋める）
+const GROUP_COLUMNS: &str = "id, name, description, color, representative_image_id, created_at, updated_at, smart_query, parent_id, sort_order";
+
+/// GROUP_COLUMNS の行を GroupData に変換
+fn row_to_group_data(row: &rusqlite::Row) -> rusqlite::Result<GroupData> {
+    Ok(GroupData {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        color: row.get(3)?,
+        representative_image_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        image_count: 0,
+        smart_query: row.get(7)?,
+        parent_id: row.get(8)?,
+        sort_order: row.get(9)?,
+    })
+}
+
+/// 全グループを指定した順番で取得（image_count を含む）
+fn load_sorted_groups(conn: &rusqlite::Connection, sort: GroupSort) -> Result<Vec<GroupData>, String> {
+    let order_by = match sort {
+        GroupSort::Manual => "sort_order, id",
+        GroupSort::Name | GroupSort::ImageCount => "name COLLATE NOCASE, id",
+        GroupSort::Created => "created_at DESC, id DESC",
+        GroupSort::Updated => "updated_at DESC, id DESC",
+    };
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM groups ORDER BY {}", GROUP_COLUMNS, order_by))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let mut groups = stmt.query_map([], row_to_group_data)
+        .map_err(|e| format!("Failed to query groups: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect groups: {}", e))?;
+
+    fill_group_image_counts(conn, &mut groups)?;
+
+    // 画像数が同じグループは名前順（安定ソート）
+    if sort == GroupSort::ImageCount {
+        groups.sort_by_key(|g| std::cmp::Reverse(g.image_count));
+    }
+
+    Ok(groups)
+}
+
+/**
+ * グループ作成時の入力データ
+ */
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub representative_image_id: Option<i64>,
+    /// 指定するとスマートグループとして作成する
+    pub smart_query: Option<String>,
+    /// 親グループのID（省略時は最上位）
+    pub parent_id: Option<i64>,
+}
+
+/**
+ * グループ更新時の入力データ
+ */
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupInput {
+    pub id: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub representative_image_id: Option<i64>,
+    /// スマートグループの検索クエリ（スマートグループのみ変更可能）
+    pub smart_query: Option<String>,
+    /// 親グループのID（省略時は変更しない、null で最上位に移動）
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub parent_id: Option<Option<i64>>,
+}
+
+/// 値があれば null も含めて Some として受け取る（省略と null を区別するため）
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// グループの検索クエリを取得（手動のグループは None、グループが無ければエラー）
+fn group_smart_query(conn: &rusqlite::Connection, group_id: i64) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT smart_query FROM groups WHERE id = ?",
+        rusqlite::params![group_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Group with ID {} not found", group_id),
+        e => format!("Failed to query group: {}", e),
+    })
+}
+
+/// 手動のグループでなければエラー（スマートグループの画像はクエリで決まるため）
+fn ensure_manual_group(conn: &rusqlite::Connection, group_id: i64) -> Result<(), String> {
+    if group_smart_query(conn, group_id)?.is_some() {
+        return Err(format!(
+            "Group {} is a smart group; its images are determined by its query",
+            group_id
+        ));
+    }
+    Ok(())
+}
+
+/// スマートグループの検索クエリを SQL に変換
+fn compile_smart_query(smart_query: &str) -> Result<crate::query_utils::ImageQuery, String> {
+    crate::query_utils::compile_query(smart_query)
+        .map_err(|e| format!("Invalid smart group query: {}", e))
+}
+
+/// 画像がスマートグループの検索クエリに一致するか
+fn image_matches_smart_query(conn: &rusqlite::Connection, image_id: i64, smart_query: &str) -> Result<bool, String> {
+    let query = compile_smart_query(smart_query)?;
+    let params = std::iter::once(rusqlite::types::Value::Integer(image_id)).chain(query.params);
+    match conn.query_row(
+        &format!("SELECT 1 FROM images WHERE id = ? AND {}", query.condition),
+        rusqlite::params_from_iter(params),
+        |_| Ok(()),
+    ) {
+        Ok(()) => Ok(true),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+        Err(e) => Err(format!("Failed to evaluate smart group query: {}", e)),
+    }
+}
+
+/// 画像がグループまたは子孫のグループに所属しているか（スマートグループはクエリに一致するか）
+fn is_image_in_group(conn: &rusqlite::Connection, group_id: i64, image_id: i64) -> Result<bool, String> {
+    for id in group_subtree_ids(conn, group_id)? {
+        let in_group = match group_smart_query(conn, id)? {
+            Some(smart_query) => image_matches_smart_query(conn, image_id, &smart_query)?,
+            None => conn
+                .query_row(
+                    "SELECT 1 FROM image_groups WHERE image_id = ? AND group_id = ?",
+                    rusqlite::params![image_id, id],
+                    |_| Ok(true),
+                )
+                .unwrap_or(false),
+        };
+        if in_group {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// グループに直接所属する画像ID（手動のグループは並び順、スマートグループはクエリを評価し撮影日時の新しい順）
+fn group_direct_image_ids(conn: &rusqlite::Connection, group_id: i64, smart_query: Option<&str>) -> Result<Vec<i64>, String> {
+    let (sql, params) = match smart_query {
+        Some(smart_query) => {
+            let query = compile_smart_query(smart_query)?;
+            (
+                format!(
+                    "SELECT id FROM images WHERE {} ORDER BY COALESCE(taken_at, created_at) DESC, id DESC",
+                    query.condition
+                ),
+                query.params,
+            )
+        }
+        None => (
+            "SELECT image_id FROM image_groups WHERE group_id = ? ORDER BY position, id".to_string(),
+            vec![rusqlite::types::Value::Integer(group_id)],
+        ),
+    };
+
+    let mut stmt = conn.prepare(&sql)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let image_ids = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+        row.get(0)
+    })
+    .map_err(|e| format!("Failed to query group images: {}", e))?
+    .collect::<Result<Vec<i64>, _>>()
+    .map_err(|e| format!("Failed to collect image IDs: {}", e))?;
+
+    Ok(image_ids)
+}
+
+/// 手動のグループの画像の並び順を ordered_ids の順（0始まり）に書き換える
+fn write_group_image_order(conn: &rusqlite::Connection, group_id: i64, ordered_ids: &[i64]) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("UPDATE image_groups SET position = ?1 WHERE group_id = ?2 AND image_id = ?3 AND position <> ?1")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    for (position, image_id) in ordered_ids.iter().enumerate() {
+        stmt.execute(rusqlite::params![position as i64, group_id, image_id])
+            .map_err(|e| format!("Failed to update image position: {}", e))?;
+    }
+    Ok(())
+}
+
+/// グループとその子孫のグループのID（自分が先頭）
+fn group_subtree_ids(conn: &rusqlite::Connection, group_id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT g.id FROM groups g JOIN subtree s ON g.parent_id = s.id
+            )
+            SELECT id FROM subtree",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let ids = stmt
+        .query_map(rusqlite::params![group_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query subgroups: {}", e))?
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|e| format!("Failed to collect subgroups: {}", e))?;
+
+    Ok(ids)
+}
+
+/// 親グループのバリデーション（存在し、group_id 自身やその子孫でないこと）
+fn validate_parent_group(conn: &rusqlite::Connection, group_id: Option<i64>, parent_id: i64) -> Result<(), String> {
+    group_smart_query(conn, parent_id)
+        .map_err(|_| format!("Parent group with ID {} not found", parent_id))?;
+
+    if let Some(group_id) = group_id {
+        if group_subtree_ids(conn, group_id)?.contains(&parent_id) {
+            return Err(format!(
+                "Cannot move group {} under itself or its descendant {}",
+                group_id, parent_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 各グループの image_count を、子孫のグループを含めた重複のない画像数にする
+fn fill_group_image_counts(conn: &rusqlite::Connection, groups: &mut [GroupData]) -> Result<(), String> {
+    // 全グループの親子関係と、直接所属する画像
+    let mut stmt = conn
+        .prepare("SELECT id, parent_id, smart_query FROM groups")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let all_groups = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, Option<String>>(2)?)))
+        .map_err(|e| format!("Failed to query groups: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect groups: {}", e))?;
+
+    let mut children: HashMap<i64, Vec<i64>> = HashMap::new();
+    for (id, parent_id, _) in &all_groups {
+        if let Some(parent_id) = parent_id {
+            children.entry(*parent_id).or_default().push(*id);
+        }
+    }
+
+    let mut members: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut stmt = conn
+        .prepare("SELECT group_id, image_id FROM image_groups")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| format!("Failed to query group images: {}", e))?;
+    for row in rows {
+        let (group_id, image_id) = row.map_err(|e| format!("Failed to read group image: {}", e))?;
+        members.entry(group_id).or_default().push(image_id);
+    }
+    for (id, _, smart_query) in &all_groups {
+        if let Some(smart_query) = smart_query {
+            let image_ids = group_direct_image_ids(conn, *id, Some(smart_query)).unwrap_or_else(|e| {
+                eprintln!("Warning: group {}: {}", id, e);
+                Vec::new()
+            });
+            members.insert(*id, image_ids);
+        }
+    }
+
+    for group in groups.iter_mut() {
+        let mut image_ids: HashSet<i64> = HashSet::new();
+        let mut visited: HashSet<i64> = HashSet::new();
+        let mut stack = vec![group.id];
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            image_ids.extend(members.get(&id).into_iter().flatten());
+            stack.extend(children.get(&id).into_iter().flatten());
+        }
+        group.image_count = image_ids.len() as i64;
+    }
+
+    Ok(())
+}
+
+/**
+ * グループを作成します
+ */
+#[tauri::command]
+pub fn create_group(input: CreateGroupInput) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    // バリデーション
+    let name = validate_group_name(&input.name)?;
+    let description = validate_group_description(&input.description)?;
+    let color = match &input.color {
+        Some(c) => validate_color(c)?,
+        None => "#3b82f6".to_string(),
+    };
+    let smart_query = input.smart_query.as_deref().map(validate_smart_query).transpose()?;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // 親グループが指定されている場合、存在確認
+    if let Some(parent_id) = input.parent_id {
+        validate_parent_group(&conn, None, parent_id)?;
+    }
+
+    // 代表画像IDが指定されている場合、画像の存在確認
+    if let Some(image_id) = input.representative_image_id {
+        let image_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM images WHERE id = ?",
+                rusqlite::params![image_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !image_exists {
+            return Err(format!("Representative image with ID {} not found", image_id));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO groups (name, description, color, representative_image_id, smart_query, parent_id, sort_order)
+        VALUES (?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM groups))",
+        rusqlite::params![
+            name,
+            description,
+            color,
+            input.representative_image_id,
+            smart_query,
+            input.parent_id,
+        ],
+    )
+    .map_err(|e| format!("Failed to create group: {}", e))?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/**
+ * 全グループを取得します（画像数を含む）
+ * 画像数は子孫のグループを含めた件数で、スマートグループは検索クエリをその場で評価します
+ * sort を省略すると作成日時の新しい順です
+ */
+#[tauri::command]
+pub fn get_all_groups(sort: Option<GroupSort>) -> Result<Vec<GroupData>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    load_sorted_groups(&conn, sort.unwrap_or(GroupSort::Created))
+}
+
+/**
+ * グループを階層構造で取得します（同じ階層は sort の順、省略時は名前順）
+ */
+#[tauri::command]
+pub fn get_group_tree(sort: Option<GroupSort>) -> Result<Vec<GroupTreeNode>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let groups = load_sorted_groups(&conn, sort.unwrap_or(GroupSort::Name))?;
+
+    // 親が見つからないグループは最上位として扱う
+    let ids: HashSet<i64> = groups.iter().map(|g| g.id).collect();
+    let mut children: HashMap<Option<i64>, Vec<GroupData>> = HashMap::new();
+    for group in groups {
+        let parent_id = group.parent_id.filter(|p| ids.contains(p));
+        children.entry(parent_id).or_default().push(group);
+    }
+
+    fn build(parent_id: Option<i64>, children: &mut HashMap<Option<i64>, Vec<GroupData>>) -> Vec<GroupTreeNode> {
+        children
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|group| {
+                let id = group.id;
+                GroupTreeNode { group, children: build(Some(id), children) }
+            })
+            .collect()
+    }
+
+    Ok(build(None, &mut children))
+}
+
+/**
+ * グループの並び順（sort が manual のときの順番）を変更します
+ * 指定しなかったグループは、指定したグループの後ろに元の順番のまま続きます
+ */
+#[tauri::command]
+pub fn reorder_groups(ordered_ids: Vec<i64>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let current_ids = {
+        let mut stmt = tx.prepare("SELECT id FROM groups ORDER BY sort_order, id")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let ids = stmt.query_map([], |row| row.get::<_, i64>(0))
+            .map_err(|e| format!("Failed to query groups: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect groups: {}", e))?;
+        ids
+    };
+
+    let existing: HashSet<i64> = current_ids.iter().copied().collect();
+    let mut listed = HashSet::new();
+    for group_id in &ordered_ids {
+        if !existing.contains(group_id) {
+            return Err(format!("Group with ID {} not found", group_id));
+        }
+        if !listed.insert(*group_id) {
+            return Err(format!("Group {} is listed more than once", group_id));
+        }
+    }
+
+    let new_order = ordered_ids
+        .iter()
+        .chain(current_ids.iter().filter(|id| !listed.contains(id)));
+    for (sort_order, group_id) in new_order.enumerate() {
+        tx.execute(
+            "UPDATE groups SET sort_order = ?1 WHERE id = ?2 AND sort_order <> ?1",
+            rusqlite::params![sort_order as i64, group_id],
+        )
+        .map_err(|e| format!("Failed to update group order: {}", e))?;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループ情報を更新します
+ */
+#[tauri::command]
+pub fn update_group(input: UpdateGroupInput) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // グループの存在確認
+    let current_smart_query = group_smart_query(&conn, input.id)?;
+
+    let mut updates = vec![];
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+
+    // 名前のバリデーションと追加
+    if let Some(name) = input.name {
+        let validated_name = validate_group_name(&name)?;
+        updates.push("name = ?");
+        params.push(Box::new(validated_name));
+    }
+
+    // 説明のバリデーションと追加
+    if let Some(description) = input.description {
+        // 空文字列の場合はNULLに設定
+        let trimmed = description.trim();
+        if trimmed.chars().count() > 500 {
+            return Err("Description is too long (max 500 characters)".to_string());
+        }
+        updates.push("description = ?");
+        if trimmed.is_empty() {
+            params.push(Box::new(None::<String>));
+        } else {
+            params.push(Box::new(trimmed.to_string()));
+        }
+    }
+
+    // カラーコードのバリデーションと追加
+    if let Some(color) = input.color {
+        let validated_color = validate_color(&color)?;
+        updates.push("color = ?");
+        params.push(Box::new(validated_color));
+    }
+
+    // 代表画像IDのバリデーションと追加
+    if let Some(rep_id) = input.representative_image_id {
+        // 画像の存在確認
+        let image_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM images WHERE id = ?",
+                rusqlite::params![rep_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !image_exists {
+            return Err(format!("Representative image with ID {} not found", rep_id));
+        }
+
+        // グループに属しているか確認
+        if !is_image_in_group(&conn, input.id, rep_id)? {
+            return Err(format!(
+                "Image {} does not belong to group {}",
+                rep_id, input.id
+            ));
+        }
+
+        updates.push("representative_image_id = ?");
+        params.push(Box::new(rep_id));
+    }
+
+    // 親グループのバリデーションと追加（自分自身や子孫の下には移動できない）
+    if let Some(parent_id) = input.parent_id {
+        if let Some(parent_id) = parent_id {
+            validate_parent_group(&conn, Some(input.id), parent_id)?;
+        }
+        updates.push("parent_id = ?");
+        params.push(Box::new(parent_id));
+    }
+
+    // 検索クエリのバリデーションと追加（手動のグループをスマートグループに変えることはできない）
+    if let Some(smart_query) = input.smart_query {
+        if current_smart_query.is_none() {
+            return Err(format!("Group {} is not a smart group", input.id));
+        }
+        updates.push("smart_query = ?");
+        params.push(Box::new(validate_smart_query(&smart_query)?));
+    }
+
+    if updates.is_empty() {
+        return Ok(());
+    }
+
+    updates.push("updated_at = CURRENT_TIMESTAMP");
+    params.push(Box::new(input.id));
+
+    let query = format!("UPDATE groups SET {} WHERE id = ?", updates.join(", "));
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+    conn.execute(&query, params_refs.as_slice())
+        .map_err(|e| format!("Failed to update group: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループを削除します
+ * cascade が true なら子孫のグループもまとめて削除し、false（省略時）なら子グループを削除するグループの親に付け替えます
+ */
+#[tauri::command]
+pub fn delete_group(group_id: i64, cascade: Option<bool>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let parent_id: Option<i64> = conn
+        .query_row(
+            "SELECT parent_id FROM groups WHERE id = ?",
+            rusqlite::params![group_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Group with ID {} not found", group_id),
+            e => format!("Failed to query group: {}", e),
+        })?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let deleted_ids = if cascade.unwrap_or(false) {
+        group_subtree_ids(&tx, group_id)?
+    } else {
+        tx.execute(
+            "UPDATE groups SET parent_id = ?, updated_at = CURRENT_TIMESTAMP WHERE parent_id = ?",
+            rusqlite::params![parent_id, group_id],
+        )
+        .map_err(|e| format!("Failed to re-parent subgroups: {}", e))?;
+        vec![group_id]
+    };
+
+    // 外部キー制約は有効にしていないため、所属とコメントも明示的に削除する
+    for id in &deleted_ids {
+        for sql in [
+            "DELETE FROM image_groups WHERE group_id = ?",
+            "DELETE FROM group_comment_edits WHERE comment_id IN (SELECT id FROM group_comments WHERE group_id = ?)",
+            "DELETE FROM group_comments WHERE group_id = ?",
+            "DELETE FROM groups WHERE id = ?",
+        ] {
+            tx.execute(sql, rusqlite::params![id])
+                .map_err(|e| format!("Failed to delete group: {}", e))?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * 画像をグループに追加します
+ * position を指定すると、その位置（0始まり）に挿入します（省略時は末尾に追加）
+ * すでにグループに所属している画像は移動しません
+ */
+#[tauri::command]
+pub fn add_images_to_group(image_ids: Vec<i64>, group_id: i64, position: Option<i64>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    // 空の配列のチェック
+    if image_ids.is_empty() {
+        return Ok(());
+    }
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // グループの存在確認（スマートグループには追加できない）
+    ensure_manual_group(&conn, group_id)?;
+
+    // トランザクション開始（複数の書き込み操作を原子的に実行）
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut order = group_direct_image_ids(&tx, group_id, None)?;
+    let mut members: HashSet<i64> = order.iter().copied().collect();
+
+    // 各画像の存在確認と追加（すでに所属している画像と重複は無視）
+    let mut new_ids = Vec::new();
+    for image_id in &image_ids {
+        // 画像の存在確認
+        let image_exists: bool = tx
+            .query_row(
+                "SELECT 1 FROM images WHERE id = ?",
+                rusqlite::params![image_id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !image_exists {
+            return Err(format!("Image with ID {} not found", image_id));
+        }
+
+        if !members.insert(*image_id) {
+            continue;
+        }
+
+        // 末尾に追加し、位置の指定があれば後で並び順を書き換える
+        tx.execute(
+            "INSERT OR IGNORE INTO image_groups (image_id, group_id, position)
+            SELECT ?1, ?2, COALESCE(MAX(position) + 1, 0) FROM image_groups WHERE group_id = ?2",
+            rusqlite::params![image_id, group_id],
+        )
+        .map_err(|e| format!("Failed to add image {} to group: {}", image_id, e))?;
+        new_ids.push(*image_id);
+    }
+
+    if let Some(position) = position {
+        let index = position.clamp(0, order.len() as i64) as usize;
+        order.splice(index..index, new_ids);
+        write_group_image_order(&tx, group_id, &order)?;
+    }
+
+    // トランザクションをコミット
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループ内の画像の並び順を変更します
+ * ordered_ids にはグループに所属する画像をすべて、新しい順番で指定します
+ */
+#[tauri::command]
+pub fn reorder_group_images(group_id: i64, ordered_ids: Vec<i64>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // スマートグループの並び順はクエリで決まる
+    ensure_manual_group(&conn, group_id)?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let members: HashSet<i64> = group_direct_image_ids(&tx, group_id, None)?.into_iter().collect();
+    let mut seen = HashSet::new();
+    for image_id in &ordered_ids {
+        if !members.contains(image_id) {
+            return Err(format!("Image {} is not in group {}", image_id, group_id));
+        }
+        if !seen.insert(*image_id) {
+            return Err(format!("Image {} is listed more than once", image_id));
+        }
+    }
+    if seen.len() != members.len() {
+        return Err(format!(
+            "All {} images of group {} must be listed, got {}",
+            members.len(),
+            group_id,
+            seen.len()
+        ));
+    }
+
+    write_group_image_order(&tx, group_id, &ordered_ids)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * 画像をグループから削除します
+ */
+#[tauri::command]
+pub fn remove_images_from_group(image_ids: Vec<i64>, group_id: i64) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    // 空の配列のチェック
+    if image_ids.is_empty() {
+        return Ok(());
+    }
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // スマートグループからは削除できない
+    ensure_manual_group(&conn, group_id)?;
+
+    // トランザクション開始（複数の書き込み操作を原子的に実行）
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    for image_id in &image_ids {
+        tx.execute(
+            "DELETE FROM image_groups WHERE image_id = ? AND group_id = ?",
+            rusqlite::params![image_id, group_id],
+        )
+        .map_err(|e| format!("Failed to remove image from group: {}", e))?;
+    }
+
+    // トランザクションをコミット
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループに所属する画像IDの配列を取得します
+ * 手動のグループは並び順、スマートグループは検索クエリをその場で評価し、撮影日時の新しい順に返します
+ * include_subgroups が true なら子孫のグループの画像も重複なしで含めます
+ */
+#[tauri::command]
+pub fn get_group_images(group_id: i64, include_subgroups: Option<bool>) -> Result<Vec<i64>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let group_ids = if include_subgroups.unwrap_or(false) {
+        group_subtree_ids(&conn, group_id)?
+    } else {
+        vec![group_id]
+    };
+
+    let mut image_ids = Vec::new();
+    let mut seen = HashSet::new();
+    for id in group_ids {
+        let smart_query = group_smart_query(&conn, id)?;
+        for image_id in group_direct_image_ids(&conn, id, smart_query.as_deref())? {
+            if seen.insert(image_id) {
+                image_ids.push(image_id);
+            }
+        }
+    }
+
+    Ok(image_ids)
+}
+
+/**
+ * 画像が所属するグループIDの配列を取得します（検索クエリに一致するスマートグループを含む）
+ */
+#[tauri::command]
+pub fn get_image_groups(image_id: i64) -> Result<Vec<i64>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn.prepare("SELECT group_id FROM image_groups WHERE image_id = ?")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let mut group_ids = stmt.query_map(rusqlite::params![image_id], |row| {
+        row.get(0)
+    })
+    .map_err(|e| format!("Failed to query image groups: {}", e))?
+    .collect::<Result<Vec<i64>, _>>()
+    .map_err(|e| format!("Failed to collect group IDs: {}", e))?;
+
+    let mut stmt = conn.prepare("SELECT id, smart_query FROM groups WHERE smart_query IS NOT NULL ORDER BY id")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let smart_groups = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query smart groups: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect smart groups: {}", e))?;
+    for (group_id, smart_query) in smart_groups {
+        if image_matches_smart_query(&conn, image_id, &smart_query)? {
+            group_ids.push(group_id);
+        }
+    }
+
+    Ok(group_ids)
+}
+
+// ============================================================
+// Phase 5: グループアルバムビュー & コメント機能
+// ============================================================
+
+/**
+ * グループコメント情報を表す構造体
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupComment {
+    pub id: i64,
+    pub group_id: i64,
+    pub comment: String,
+    pub created_at: String,
+    /// 最後に編集した日時（未編集は None）
+    pub updated_at: Option<String>,
+    /// 投稿者名（未設定は None）
+    pub author: Option<String>,
+    /// 返信先のコメントID（グループへの直接のコメントは None）
+    pub parent_comment_id: Option<i64>,
+}
+
+/**
+ * グループコメント追加時の入力データ
+ */
+#[derive(Debug, Deserialize)]
+pub struct AddCommentInput {
+    pub group_id: i64,
+    pub comment: String,
+    pub author: Option<String>,
+    pub parent_comment_id: Option<i64>,
+}
+
+/**
+ * グループコメントの編集履歴（編集前の本文）
+ */
+#[derive(Debug, Serialize)]
+pub struct GroupCommentEdit {
+    pub id: i64,
+    pub comment_id: i64,
+    pub comment: String,
+    pub edited_at: String,
+}
+
+/**
+ * グループ内の画像のキャプション
+ */
+#[derive(Debug, Serialize)]
+pub struct GroupImageCaption {
+    pub image_id: i64,
+    pub caption: String,
+}
+
+/**
+ * グループIDから詳細情報を取得します
+ */
+#[tauri::command]
+pub fn get_group_by_id(group_id: i64) -> Result<GroupData, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut group = conn
+        .query_row(
+            &format!("SELECT {} FROM groups WHERE id = ?", GROUP_COLUMNS),
+            rusqlite::params![group_id],
+            row_to_group_data,
+        )
+        .map_err(|e| format!("Failed to query group: {}", e))?;
+
+    fill_group_image_counts(&conn, std::slice::from_mut(&mut group))?;
+
+    Ok(group)
+}
+
+/**
+ * グループの代表画像を設定します
+ * 代表画像はそのグループに属している必要があります
+ */
+#[tauri::command]
+pub fn set_representative_image(group_id: i64, image_id: Option<i64>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // NULLに設定する場合はチェック不要
+    if let Some(id) = image_id {
+        // 画像の存在確認
+        let image_exists: bool = conn
+            .query_row(
+                "SELECT 1 FROM images WHERE id = ?",
+                rusqlite::params![id],
+                |_| Ok(true),
+            )
+            .unwrap_or(false);
+
+        if !image_exists {
+            return Err(format!("Image with ID {} not found", id));
+        }
+
+        // グループに属しているか確認
+        if !is_image_in_group(&conn, group_id, id)? {
+            return Err(format!(
+                "Image {} does not belong to group {}",
+                id, group_id
+            ));
+        }
+    }
+
+    conn.execute(
+        "UPDATE groups SET representative_image_id = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![image_id, group_id],
+    )
+    .map_err(|e| format!("Failed to set representative image: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループコメントを追加します
+ */
+#[tauri::command]
+pub fn add_group_comment(input: AddCommentInput) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    // コメントのバリデーション
+    let comment = validate_comment(&input.comment)?;
+    let author = input.author.as_deref().map(validate_comment_author).transpose()?.flatten();
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // グループの存在確認
+    let group_exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM groups WHERE id = ?",
+            rusqlite::params![input.group_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+
+    if !group_exists {
+        return Err(format!("Group with ID {} not found", input.group_id));
+    }
+
+    // 返信先は同じグループのコメントであること
+    if let Some(parent_id) = input.parent_comment_id {
+        let parent_group_id = comment_group_id(&conn, parent_id)?;
+        if parent_group_id != input.group_id {
+            return Err(format!(
+                "Comment {} belongs to another group",
+                parent_id
+            ));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO group_comments (group_id, comment, author, parent_comment_id) VALUES (?, ?, ?, ?)",
+        rusqlite::params![input.group_id, comment, author, input.parent_comment_id],
+    )
+    .map_err(|e| format!("Failed to add group comment: {}", e))?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// コメントが属するグループのID
+fn comment_group_id(conn: &rusqlite::Connection, comment_id: i64) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT group_id FROM group_comments WHERE id = ?",
+        rusqlite::params![comment_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Comment with ID {} not found", comment_id),
+        e => format!("Failed to query comment: {}", e),
+    })
+}
+
+/// コメントとその返信（子孫）のID
+fn comment_thread_ids(conn: &rusqlite::Connection, comment_id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE thread(id) AS (
+                SELECT ?1
+                UNION
+                SELECT c.id FROM group_comments c JOIN thread t ON c.parent_comment_id = t.id
+            )
+            SELECT id FROM thread",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let ids = stmt
+        .query_map(rusqlite::params![comment_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query replies: {}", e))?
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|e| format!("Failed to collect replies: {}", e))?;
+
+    Ok(ids)
+}
+
+/**
+ * グループの全コメントを取得します（新しい順）
+ * 返信も同じ配列に含まれ、parent_comment_id で返信先を示します
+ */
+#[tauri::command]
+pub fn get_group_comments(group_id: i64) -> Result<Vec<GroupComment>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, group_id, comment, created_at, updated_at, author, parent_comment_id
+        FROM group_comments
+        WHERE group_id = ?
+        ORDER BY created_at DESC, id DESC"
+    )
+    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let comments = stmt.query_map(rusqlite::params![group_id], |row| {
+        Ok(GroupComment {
+            id: row.get(0)?,
+            group_id: row.get(1)?,
+            comment: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            author: row.get(5)?,
+            parent_comment_id: row.get(6)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query group comments: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect comments: {}", e))?;
+
+    Ok(comments)
+}
+
+/**
+ * グループコメントを編集します
+ * 編集前の本文は編集履歴に残ります
+ */
+#[tauri::command]
+pub fn update_group_comment(comment_id: i64, comment: String) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let comment = validate_comment(&comment)?;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let current: String = tx
+        .query_row(
+            "SELECT comment FROM group_comments WHERE id = ?",
+            rusqlite::params![comment_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Comment with ID {} not found", comment_id),
+            e => format!("Failed to query comment: {}", e),
+        })?;
+
+    // 本文が変わらなければ履歴も残さない
+    if current == comment {
+        return Ok(());
+    }
+
+    tx.execute(
+        "INSERT INTO group_comment_edits (comment_id, comment) VALUES (?, ?)",
+        rusqlite::params![comment_id, current],
+    )
+    .map_err(|e| format!("Failed to save comment history: {}", e))?;
+
+    tx.execute(
+        "UPDATE group_comments SET comment = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![comment, comment_id],
+    )
+    .map_err(|e| format!("Failed to update group comment: {}", e))?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループコメントの編集履歴を取得します（新しい順）
+ */
+#[tauri::command]
+pub fn get_group_comment_history(comment_id: i64) -> Result<Vec<GroupCommentEdit>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    comment_group_id(&conn, comment_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, comment_id, comment, edited_at
+        FROM group_comment_edits
+        WHERE comment_id = ?
+        ORDER BY edited_at DESC, id DESC"
+    )
+    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let edits = stmt.query_map(rusqlite::params![comment_id], |row| {
+        Ok(GroupCommentEdit {
+            id: row.get(0)?,
+            comment_id: row.get(1)?,
+            comment: row.get(2)?,
+            edited_at: row.get(3)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query comment history: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect comment history: {}", e))?;
+
+    Ok(edits)
+}
+
+/**
+ * グループコメントを削除します（返信と編集履歴も削除）
+ */
+#[tauri::command]
+pub fn delete_group_comment(comment_id: i64) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // 外部キー制約は有効にしていないため、返信と編集履歴も明示的に削除する
+    for id in comment_thread_ids(&tx, comment_id)? {
+        for sql in [
+            "DELETE FROM group_comment_edits WHERE comment_id = ?",
+            "DELETE FROM group_comments WHERE id = ?",
+        ] {
+            tx.execute(sql, rusqlite::params![id])
+                .map_err(|e| format!("Failed to delete group comment: {}", e))?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * グループ内の画像のキャプションを設定します（空文字列または None で削除）
+ * 同じ画像でもグループごとに別のキャプションを付けられます
+ */
+#[tauri::command]
+pub fn set_group_image_caption(group_id: i64, image_id: i64, caption: Option<String>) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let caption = caption.as_deref().map(validate_caption).transpose()?.flatten();
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // スマートグループの画像は image_groups に行を持たない
+    ensure_manual_group(&conn, group_id)?;
+
+    let updated = conn
+        .execute(
+            "UPDATE image_groups SET caption = ? WHERE group_id = ? AND image_id = ?",
+            rusqlite::params![caption, group_id, image_id],
+        )
+        .map_err(|e| format!("Failed to set caption: {}", e))?;
+
+    if updated == 0 {
+        return Err(format!(
+            "Image {} does not belong to group {}",
+            image_id, group_id
+        ));
+    }
+
+    Ok(())
+}
+
+/**
+ * グループ内の画像のキャプションを取得します（キャプションのある画像のみ、グループ内の並び順）
+ */
+#[tauri::command]
+pub fn get_group_image_captions(group_id: i64) -> Result<Vec<GroupImageCaption>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT image_id, caption
+        FROM image_groups
+        WHERE group_id = ? AND caption IS NOT NULL
+        ORDER BY position, id"
+    )
+    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let captions = stmt.query_map(rusqlite::params![group_id], |row| {
+        Ok(GroupImageCaption {
+            image_id: row.get(0)?,
+            caption: row.get(1)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query captions: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect captions: {}", e))?;
+
+    Ok(captions)
+}
+
+// ============================================================
+// 全文検索（ファイル名・コメント・タグ・グループ・グループコメント）
+// ============================================================
+
+/// スニペットで一致箇所を囲む記号
+const SEARCH_MARK_START: &str = "<mark>";
+const SEARCH_MARK_END: &str = "</mark>";
+/// スニペットに含める一致箇所の前後の文字数
+const SEARCH_SNIPPET_CONTEXT: usize = 30;
+/// trigram トークナイザの MATCH で検索できる語の最小文字数
+const SEARCH_TRIGRAM_MIN_CHARS: usize = 3;
+
+/**
+ * 全文検索の結果
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// "image" / "group" / "group_comment"
+    pub kind: String,
+    /// 画像ID・グループID・グループコメントID（kind による）
+    pub id: i64,
+    /// コメントが属するグループのID（group_comment のみ）
+    pub group_id: Option<i64>,
+    /// 画像のファイル名、またはグループ名
+    pub title: String,
+    /// スニペットを取り出した項目（"file_name" / "comment" / "tags" / "name" / "description"）
+    pub field: String,
+    /// 一致箇所を <mark>…</mark> で囲んだ抜粋（HTMLエスケープはしない）
+    pub snippet: String,
+    /// 一致度（FTS5 の bm25）。小さいほど上位で、3文字以上の語を含まない検索では 0
+    pub rank: f64,
+}
+
+/// 検索語を空白で区切り、大文字小文字を区別せずに重複を除く
+fn parse_search_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query.split_whitespace() {
+        if !terms.iter().any(|t| t.to_lowercase() == term.to_lowercase()) {
+            terms.push(term.to_string());
+        }
+    }
+    terms
+}
+
+/// FTS5 テーブルの検索条件とパラメータ、MATCH を使うかどうかを返す
+/// 3文字以上の語はフレーズとして MATCH し、trigram で一致しない短い語はいずれかの列への LIKE で絞り込む
+fn search_condition(table: &str, columns: &[&str], terms: &[String]) -> (String, Vec<String>, bool) {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+
+    let phrases: Vec<String> = terms
+        .iter()
+        .filter(|t| t.chars().count() >= SEARCH_TRIGRAM_MIN_CHARS)
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    let uses_match = !phrases.is_empty();
+    if uses_match {
+        conditions.push(format!("{} MATCH ?", table));
+        params.push(phrases.join(" "));
+    }
+
+    for term in terms.iter().filter(|t| t.chars().count() < SEARCH_TRIGRAM_MIN_CHARS) {
+        let likes: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{}.{} LIKE ? ESCAPE '\\'", table, column))
+            .collect();
+        conditions.push(format!("({})", likes.join(" OR ")));
+        params.extend(std::iter::repeat(crate::query_utils::like_pattern(term)).take(columns.len()));
+    }
+
+    (conditions.join(" AND "), params, uses_match)
+}
+
+/// 検索結果の一致度の式と並び順（MATCH を使わない場合は新しい順）
+fn search_rank_order(table: &str, uses_match: bool) -> (String, String) {
+    if uses_match {
+        (format!("{}.rank", table), format!("{}.rank", table))
+    } else {
+        ("0.0".to_string(), format!("{}.rowid DESC", table))
+    }
+}
+
+/// 大文字小文字を区別しない比較用に1文字ずつ小文字にする（文字の位置は元の文字列と揃える）
+fn fold_chars(text: &str) -> Vec<char> {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+/// text 中の検索語の一致範囲（文字単位）を重なりをまとめて先頭から順に返し、一致した語の数も返す
+fn find_term_ranges(text: &[char], terms: &[Vec<char>]) -> (Vec<(usize, usize)>, usize) {
+    let mut ranges = Vec::new();
+    let mut matched_terms = 0;
+    for term in terms {
+        if term.is_empty() || term.len() > text.len() {
+            continue;
+        }
+        let before = ranges.len();
+        for start in 0..=text.len() - term.len() {
+            if text[start..start + term.len()] == term[..] {
+                ranges.push((start, start + term.len()));
+            }
+        }
+        if ranges.len() > before {
+            matched_terms += 1;
+        }
+    }
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    (merged, matched_terms)
+}
+
+/// 最初の一致箇所の前後を切り出し、一致箇所を SEARCH_MARK_START / SEARCH_MARK_END で囲む
+/// 一致した語の数とスニペットを返し、一致しない場合は None
+fn highlight_snippet(text: &str, terms: &[Vec<char>]) -> Option<(usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let (ranges, matched_terms) = find_term_ranges(&fold_chars(text), terms);
+    let first = ranges.first()?;
+    let start = first.0.saturating_sub(SEARCH_SNIPPET_CONTEXT);
+    let end = (first.1 + SEARCH_SNIPPET_CONTEXT).min(chars.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut pos = start;
+    for &(range_start, range_end) in ranges.iter().take_while(|(s, _)| *s < end) {
+        let range_end = range_end.min(end);
+        snippet.extend(&chars[pos..range_start]);
+        snippet.push_str(SEARCH_MARK_START);
+        snippet.extend(&chars[range_start..range_end]);
+        snippet.push_str(SEARCH_MARK_END);
+        pos = range_end;
+    }
+    snippet.extend(&chars[pos..end]);
+    if end < chars.len() {
+        snippet.push('…');
+    }
+
+    Some((matched_terms, snippet))
+}
+
+/// 一致した語が最も多い項目（同数なら先の項目）からスニペットを作り、(項目名, スニペット) を返す
+/// どの項目にも一致しない場合は、最初の空でない項目の先頭を強調なしで返す
+fn best_snippet(fields: &[(&str, Option<&str>)], terms: &[String]) -> (String, String) {
+    let folded: Vec<Vec<char>> = terms.iter().map(|t| fold_chars(t)).collect();
+
+    let mut best: Option<(usize, &str, String)> = None;
+    for (field, text) in fields {
+        let Some((count, snippet)) = text.and_then(|t| highlight_snippet(t, &folded)) else {
+            continue;
+        };
+        match &best {
+            Some((best_count, _, _)) if *best_count >= count => {}
+            _ => best = Some((count, field, snippet)),
+        }
+    }
+    if let Some((_, field, snippet)) = best {
+        return (field.to_string(), snippet);
+    }
+
+    fields
+        .iter()
+        .find_map(|(field, text)| {
+            text.filter(|t| !t.is_empty())
+                .map(|t| (field.to_string(), t.chars().take(SEARCH_SNIPPET_CONTEXT * 2).collect()))
+        })
+        .unwrap_or_default()
+}
+
+/**
+ * 画像のファイル名・コメント・タグ、グループ名・説明、グループコメントを全文検索します
+ * 空白区切りの語をすべて含むものを一致度順に返します（日本語は trigram で部分一致）
+ */
+#[tauri::command]
+pub fn search(query: String, limit: Option<i64>) -> Result<Vec<SearchResult>, String> {
+    use rusqlite::Connection;
+
+    let terms = parse_search_terms(&query);
+    if terms.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut results: Vec<SearchResult> = Vec::new();
+
+    // 画像
+    let (condition, params, uses_match) = search_condition("images_fts", &["file_name", "comment", "tags"], &terms);
+    let (rank, order) = search_rank_order("images_fts", uses_match);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT images_fts.rowid, images_fts.file_name, images_fts.comment, images_fts.tags, {}
+            FROM images_fts WHERE {} ORDER BY {} LIMIT {}",
+            rank, condition, order, limit
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, f64>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to search images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect search results: {}", e))?;
+    for (id, file_name, comment, tags, rank) in rows {
+        let (field, snippet) = best_snippet(
+            &[("file_name", Some(&file_name)), ("comment", comment.as_deref()), ("tags", tags.as_deref())],
+            &terms,
+        );
+        results.push(SearchResult { kind: "image".to_string(), id, group_id: None, title: file_name, field, snippet, rank });
+    }
+
+    // グループ
+    let (condition, params, uses_match) = search_condition("groups_fts", &["name", "description"], &terms);
+    let (rank, order) = search_rank_order("groups_fts", uses_match);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT groups_fts.rowid, groups_fts.name, groups_fts.description, {}
+            FROM groups_fts WHERE {} ORDER BY {} LIMIT {}",
+            rank, condition, order, limit
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to search groups: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect search results: {}", e))?;
+    for (id, name, description, rank) in rows {
+        let (field, snippet) = best_snippet(&[("name", Some(&name)), ("description", description.as_deref())], &terms);
+        results.push(SearchResult { kind: "group".to_string(), id, group_id: None, title: name, field, snippet, rank });
+    }
+
+    // グループコメント（タイトルはグループ名）
+    let (condition, params, uses_match) = search_condition("group_comments_fts", &["comment"], &terms);
+    let (rank, order) = search_rank_order("group_comments_fts", uses_match);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT group_comments_fts.rowid, group_comments_fts.comment, c.group_id, g.name, {}
+            FROM group_comments_fts
+            JOIN group_comments c ON c.id = group_comments_fts.rowid
+            JOIN groups g ON g.id = c.group_id
+            WHERE {} ORDER BY {} LIMIT {}",
+            rank, condition, order, limit
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, f64>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to search group comments: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect search results: {}", e))?;
+    for (id, comment, group_id, group_name, rank) in rows {
+        let (field, snippet) = best_snippet(&[("comment", Some(&comment))], &terms);
+        results.push(SearchResult {
+            kind: "group_comment".to_string(),
+            id,
+            group_id: Some(group_id),
+            title: group_name,
+            field,
+            snippet,
+            rank,
+        });
+    }
+
+    // 種類ごとの結果を一致度順にまとめる（同じ一致度では種類ごとの順序を保つ）
+    results.sort_by(|a, b| a.rank.partial_cmp(&b.rank).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(limit as usize);
+
+    Ok(results)
+}
+
+// ============================================================
+// Phase 6: マルチディレクトリ管理
+// ============================================================
+
+/**
+ * ディレクトリ情報を表す構造体
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DirectoryData {
+    pub id: i64,
+    pub path: String,
+    pub name: String,
+    pub is_active: i64,
+    pub last_scanned_at: Option<String>,
+    pub file_count: i64,
+    pub created_at: String,
+    /// 評価・タグ・コメントの変更をXMPサイドカーに書き出すか（0 or 1）
+    pub write_xmp_sidecars: i64,
+}
+
+/**
+ * ディレクトリを追加し、初回スキャンを実行します
+ */
+#[tauri::command]
+pub async fn add_directory(path: String) -> Result<DirectoryData, String> {
+    use rusqlite::Connection;
+
+    let dir_path = Path::new(&path);
+    if !dir_path.exists() || !dir_path.is_dir() {
+        return Err(format!("Directory does not exist: {}", path));
+    }
+
+    let name = dir_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // 既存チェック
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM directories WHERE path = ?",
+            rusqlite::params![path],
+            |row| row.get(0),
+        )
+        .ok();
+
+    if let Some(existing_id) = existing {
+        // 既存なら再アクティブ化
+        conn.execute(
+            "UPDATE directories SET is_active = 1 WHERE id = ?",
+            rusqlite::params![existing_id],
+        )
+        .map_err(|e| format!("Failed to reactivate directory: {}", e))?;
+    } else {
+        conn.execute(
+            "INSERT INTO directories (path, name) VALUES (?, ?)",
+            rusqlite::params![path, name],
+        )
+        .map_err(|e| format!("Failed to add directory: {}", e))?;
+    }
+
+    // ディレクトリIDを取得
+    let dir_id: i64 = conn
+        .query_row(
+            "SELECT id FROM directories WHERE path = ?",
+            rusqlite::params![path],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to get directory id: {}", e))?;
+
+    // 既存画像のバックフィル（パスがこのディレクトリに含まれる画像のdirectory_idを設定）
+    let prefix = if path.ends_with('/') {
+        path.clone()
+    } else {
+        format!("{}/", path)
+    };
+    conn.execute(
+        "UPDATE images SET directory_id = ? WHERE file_path LIKE ? AND directory_id IS NULL",
+        rusqlite::params![dir_id, format!("{}%", prefix)],
+    )
+    .map_err(|e| format!("Failed to backfill directory_id: {}", e))?;
+
+    // ファイル数を更新
+    let file_count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM images WHERE directory_id = ?",
+            rusqlite::params![dir_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    conn.execute(
+        "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![file_count, dir_id],
+    )
+    .map_err(|e| format!("Failed to update directory file count: {}", e))?;
+
+    // 最新のディレクトリ情報を返す
+    let dir = conn
+        .query_row(
+            "SELECT id, path, name, is_active, last_scanned_at, file_count, created_at, COALESCE(write_xmp_sidecars, 0)
+            FROM directories WHERE id = ?",
+            rusqlite::params![dir_id],
+            |row| {
+                Ok(DirectoryData {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    name: row.get(2)?,
+                    is_active: row.get(3)?,
+                    last_scanned_at: row.get(4)?,
+                    file_count: row.get(5)?,
+                    created_at: row.get(6)?,
+                    write_xmp_sidecars: row.get(7)?,
+                })
+            },
+        )
+        .map_err(|e| format!("Failed to get directory data: {}", e))?;
+
+    Ok(dir)
+}
+
+/**
+ * ディレクトリを削除します（画像データは保持、directory_idをNULLに）
+ */
+#[tauri::command]
+pub fn remove_directory(directory_id: i64) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // 画像のdirectory_idをNULLに
+    conn.execute(
+        "UPDATE images SET directory_id = NULL WHERE directory_id = ?",
+        rusqlite::params![directory_id],
+    )
+    .map_err(|e| format!("Failed to unlink images: {}", e))?;
+
+    conn.execute(
+        "DELETE FROM directories WHERE id = ?",
+        rusqlite::params![directory_id],
+    )
+    .map_err(|e| format!("Failed to delete directory: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * 全ディレクトリを取得します
+ */
+#[tauri::command]
+pub fn get_all_directories() -> Result<Vec<DirectoryData>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, path, name, is_active, last_scanned_at, file_count, created_at, COALESCE(write_xmp_sidecars, 0)
+            FROM directories ORDER BY created_at DESC",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let dirs = stmt
+        .query_map([], |row| {
+            Ok(DirectoryData {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                name: row.get(2)?,
+                is_active: row.get(3)?,
+                last_scanned_at: row.get(4)?,
+                file_count: row.get(5)?,
+                created_at: row.get(6)?,
+                write_xmp_sidecars: row.get(7)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query directories: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect directories: {}", e))?;
+
+    Ok(dirs)
+}
+
+/**
+ * ディレクトリのアクティブ/非アクティブ状態を切り替えます
+ */
+#[tauri::command]
+pub fn set_directory_active(directory_id: i64, is_active: bool) -> Result<(), String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let active_val: i64 = if is_active { 1 } else { 0 };
+    conn.execute(
+        "UPDATE directories SET is_active = ? WHERE id = ?",
+        rusqlite::params![active_val, directory_id],
+    )
+    .map_err(|e| format!("Failed to update directory active state: {}", e))?;
+
+    Ok(())
+}
+
+/**
+ * 指定ディレクトリを再スキャンします
+ */
+#[tauri::command]
+pub async fn scan_single_directory(directory_id: i64) -> Result<Vec<ImageFileInfo>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // ディレクトリパスを取得
+    let dir_path: String = conn
+        .query_row(
+            "SELECT path FROM directories WHERE id = ?",
+            rusqlite::params![directory_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Directory not found: {}", e))?;
+
+    // ファイルスキャン（変更のないファイルはメタデータ抽出をスキップ）
+    let files = crate::fs_utils::scan_images_in_directory(&dir_path)?;
+    let baseline = ScanBaseline::load(&conn, &dir_path);
+    let result = build_file_infos(files, &baseline);
+
+    // ファイル数を更新
+    conn.execute(
+        "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![result.len() as i64, directory_id],
+    )
+    .map_err(|e| format!("Failed to update directory: {}", e))?;
+
+    println!("Scanned {} files for directory {}", result.len(), dir_path);
+
+    Ok(result)
+}
+
+/**
+ * 全アクティブディレクトリをスキャンします
+ */
+#[tauri::command]
+pub async fn scan_all_active_directories() -> Result<Vec<ImageFileInfo>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, path FROM directories WHERE is_active = 1")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let dirs: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query active directories: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect directories: {}", e))?;
+
+    drop(stmt);
+
+    let mut all_results = Vec::new();
+
+    for (dir_id, dir_path) in &dirs {
+        let files = crate::fs_utils::scan_images_in_directory(dir_path)?;
+        let baseline = ScanBaseline::load(&conn, dir_path);
+        let file_infos = build_file_infos(files, &baseline);
+        let dir_count = file_infos.len() as i64;
+        all_results.extend(file_infos);
+
+        conn.execute(
+            "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
+            rusqlite::params![dir_count, dir_id],
+        )
+        .map_err(|e| format!("Failed to update directory: {}", e))?;
+    }
+
+    println!("Scanned {} files across {} active directories", all_results.len(), dirs.len());
+
+    Ok(all_results)
+}
+
+// ============================================================
+// スキャン結果のDB同期
+// ============================================================
+
+/**
+ * スキャン結果をDBに同期した結果のサマリー
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub directory_id: i64,
+    pub directory_path: String,
+    pub scanned: i64,
+    pub inserted: i64,
+    pub updated: i64,
+    pub unchanged: i64,
+    /// 移動・リネームを検出して既存行に再リンクしたファイル数
+    pub relinked: i64,
+    /// ディスク上から消えたため欠損としてマークしたファイル数
+    pub missing: i64,
+    /// XMPサイドカーから評価・タグ・コメントを取り込んだファイル数
+    pub sidecars_imported: i64,
+}
+
+/// 同期時に比較する既存 images 行のカラム
+struct SyncedImageRow {
+    id: i64,
+    file_name: String,
+    file_type: Option<String>,
+    directory_id: Option<i64>,
+    is_missing: bool,
+    file_size: Option<i64>,
+    file_mtime: Option<i64>,
+    content_hash: Option<String>,
+    perceptual_hash: Option<String>,
+    duration_seconds: Option<f64>,
+    width: Option<i64>,
+    height: Option<i64>,
+    video_codec: Option<String>,
+    audio_codec: Option<String>,
+    captured_at: Option<String>,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    lens_model: Option<String>,
+    iso: Option<i64>,
+    exposure_time: Option<f64>,
+    f_number: Option<f64>,
+    orientation: Option<i64>,
+    taken_at: Option<String>,
+    taken_at_source: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    altitude: Option<f64>,
+    metadata_version: Option<i64>,
+    city: Option<String>,
+    phash_failed_mtime: Option<i64>,
+    metadata_failed_mtime: Option<i64>,
+}
+
+/// パスに対応するディレクトリIDを取得（未登録の場合は作成）
+fn get_or_create_directory_id(conn: &rusqlite::Connection, path: &str) -> Result<i64, String> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM directories WHERE path = ?",
+            rusqlite::params![path],
+            |row| row.get(0),
+        )
+        .ok();
+
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let name = Path::new(path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    conn.execute(
+        "INSERT INTO directories (path, name) VALUES (?, ?)",
+        rusqlite::params![path, name],
+    )
+    .map_err(|e| format!("Failed to add directory: {}", e))?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/**
+ * スキャンしたファイル情報を images テーブルに upsert します
+ * 新規ファイルは挿入し、既存ファイルは差分があるカラムのみ更新します
+ * （directory_id はNULLの場合のみ設定、動画メタデータは取得できた場合のみ上書き）
+ * 全件を1トランザクションで処理し、ディレクトリのファイル数とスキャン日時も更新します
+ */
+fn sync_file_infos(
+    conn: &mut rusqlite::Connection,
+    directory_id: i64,
+    directory_path: &str,
+    file_infos: &[ImageFileInfo],
+) -> Result<SyncSummary, String> {
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut inserted = 0i64;
+    let mut updated = 0i64;
+    let mut unchanged = 0i64;
+    let mut relinked = 0i64;
+    let mut sidecars_imported = 0i64;
+
+    for info in file_infos {
+        let mut existing = query_synced_image_row(&tx, &info.file_path)?;
+
+        // 未登録のパスは、移動・リネームされた既存行がないか確認
+        let mut is_relinked = false;
+        if existing.is_none() {
+            if let Some(image_id) = find_moved_image(
+                &tx,
+                &info.file_path,
+                info.file_size,
+                info.file_mtime,
+                info.content_hash.as_deref(),
+            )? {
+                relink_image(&tx, image_id, &info.file_path, &info.file_name, directory_id)?;
+                existing = query_synced_image_row(&tx, &info.file_path)?;
+                is_relinked = true;
+                relinked += 1;
+            }
+        }
+
+        let Some(row) = existing else {
+            tx.execute(
+                "INSERT INTO images (file_path, file_name, file_type, file_size, file_mtime, content_hash, perceptual_hash, duration_seconds, width, height, video_codec, audio_codec,
+                    captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation,
+                    taken_at, taken_at_source, latitude, longitude, altitude, metadata_version,
+                    country_code, country, region, city, directory_id, phash_failed_mtime, metadata_failed_mtime)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                rusqlite::params![
+                    info.file_path,
+                    info.file_name,
+                    info.file_type,
+                    info.file_size,
+                    info.file_mtime,
+                    info.content_hash,
+                    info.perceptual_hash,
+                    info.duration_seconds,
+                    info.width,
+                    info.height,
+                    info.video_codec,
+                    info.audio_codec,
+                    info.captured_at,
+                    info.camera_make,
+                    info.camera_model,
+                    info.lens_model,
+                    info.iso,
+                    info.exposure_time,
+                    info.f_number,
+                    info.orientation,
+                    info.taken_at,
+                    info.taken_at_source,
+                    info.latitude,
+                    info.longitude,
+                    info.altitude,
+                    SCAN_METADATA_VERSION,
+                    info.country_code,
+                    info.country,
+                    info.region,
+                    info.city,
+                    directory_id,
+                    info.phash_failed_mtime,
+                    info.metadata_failed_mtime,
+                ],
+            )
+            .map_err(|e| format!("Failed to insert {}: {}", info.file_path, e))?;
+            inserted += 1;
+
+            if let Some(sidecar) = &info.xmp_sidecar {
+                import_xmp_sidecar(&tx, tx.last_insert_rowid(), sidecar)?;
+                sidecars_imported += 1;
+            }
+            continue;
+        };
+
+        if let Some(sidecar) = &info.xmp_sidecar {
+            import_xmp_sidecar(&tx, row.id, sidecar)?;
+            sidecars_imported += 1;
+        }
+
+        let mut updates = vec![];
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+
+        if row.file_name != info.file_name {
+            updates.push("file_name = ?");
+            params.push(Box::new(info.file_name.clone()));
+        }
+        if row.file_type.as_deref() != Some(info.file_type.as_str()) {
+            updates.push("file_type = ?");
+            params.push(Box::new(info.file_type.clone()));
+        }
+        if row.directory_id.is_none() {
+            updates.push("directory_id = ?");
+            params.push(Box::new(directory_id));
+        }
+        if row.is_missing {
+            // 欠損扱いだったファイルが再び見つかった
+            updates.push("is_missing = 0");
+            updates.push("missing_since = NULL");
+        }
+        if info.file_size.is_some() && info.file_size != row.file_size {
+            updates.push("file_size = ?");
+            params.push(Box::new(info.file_size));
+        }
+        if info.file_mtime.is_some() && info.file_mtime != row.file_mtime {
+            updates.push("file_mtime = ?");
+            params.push(Box::new(info.file_mtime));
+        }
+        if info.content_hash.is_some() && info.content_hash != row.content_hash {
+            updates.push("content_hash = ?");
+            params.push(Box::new(info.content_hash.clone()));
+        }
+        if info.perceptual_hash.is_some() && info.perceptual_hash != row.perceptual_hash {
+            updates.push("perceptual_hash = ?");
+            params.push(Box::new(info.perceptual_hash.clone()));
+        }
+        if info.phash_failed_mtime != row.phash_failed_mtime {
+            updates.push("phash_failed_mtime = ?");
+            params.push(Box::new(info.phash_failed_mtime));
+        }
+        if info.metadata_failed_mtime != row.metadata_failed_mtime {
+            updates.push("metadata_failed_mtime = ?");
+            params.push(Box::new(info.metadata_failed_mtime));
+        }
+        if info.duration_seconds.is_some() && info.duration_seconds != row.duration_seconds {
+            updates.push("duration_seconds = ?");
+            params.push(Box::new(info.duration_seconds));
+        }
+        if info.width.is_some() && info.width.map(i64::from) != row.width {
+            updates.push("width = ?");
+            params.push(Box::new(info.width));
+        }
+        if info.height.is_some() && info.height.map(i64::from) != row.height {
+            updates.push("height = ?");
+            params.push(Box::new(info.height));
+        }
+        if info.video_codec.is_some() && info.video_codec != row.video_codec {
+            updates.push("video_codec = ?");
+            params.push(Box::new(info.video_codec.clone()));
+        }
+        if info.audio_codec.is_some() && info.audio_codec != row.audio_codec {
+            updates.push("audio_codec = ?");
+            params.push(Box::new(info.audio_codec.clone()));
+        }
+        if info.captured_at.is_some() && info.captured_at != row.captured_at {
+            updates.push("captured_at = ?");
+            params.push(Box::new(info.captured_at.clone()));
+        }
+        if info.camera_make.is_some() && info.camera_make != row.camera_make {
+            updates.push("camera_make = ?");
+            params.push(Box::new(info.camera_make.clone()));
+        }
+        if info.camera_model.is_some() && info.camera_model != row.camera_model {
+            updates.push("camera_model = ?");
+            params.push(Box::new(info.camera_model.clone()));
+        }
+        if info.lens_model.is_some() && info.lens_model != row.lens_model {
+            updates.push("lens_model = ?");
+            params.push(Box::new(info.lens_model.clone()));
+        }
+        if info.iso.is_some() && info.iso != row.iso {
+            updates.push("iso = ?");
+            params.push(Box::new(info.iso));
+        }
+        if info.exposure_time.is_some() && info.exposure_time != row.exposure_time {
+            updates.push("exposure_time = ?");
+            params.push(Box::new(info.exposure_time));
+        }
+        if info.f_number.is_some() && info.f_number != row.f_number {
+            updates.push("f_number = ?");
+            params.push(Box::new(info.f_number));
+        }
+        if info.orientation.is_some() && info.orientation != row.orientation {
+            updates.push("orientation = ?");
+            params.push(Box::new(info.orientation));
+        }
+        if info.taken_at.is_some() && info.taken_at != row.taken_at {
+            updates.push("taken_at = ?");
+            params.push(Box::new(info.taken_at.clone()));
+        }
+        if info.taken_at_source.is_some() && info.taken_at_source != row.taken_at_source {
+            updates.push("taken_at_source = ?");
+            params.push(Box::new(info.taken_at_source.clone()));
+        }
+        if info.latitude.is_some() && info.latitude != row.latitude {
+            updates.push("latitude = ?");
+            params.push(Box::new(info.latitude));
+        }
+        if info.longitude.is_some() && info.longitude != row.longitude {
+            updates.push("longitude = ?");
+            params.push(Box::new(info.longitude));
+        }
+        if info.altitude.is_some() && info.altitude != row.altitude {
+            updates.push("altitude = ?");
+            params.push(Box::new(info.altitude));
+        }
+        if info.city.is_some() && info.city != row.city {
+            // 地名は4項目をまとめて置き換える
+            updates.push("country_code = ?");
+            params.push(Box::new(info.country_code.clone()));
+            updates.push("country = ?");
+            params.push(Box::new(info.country.clone()));
+            updates.push("region = ?");
+            params.push(Box::new(info.region.clone()));
+            updates.push("city = ?");
+            params.push(Box::new(info.city.clone()));
+        }
+        if row.metadata_version != Some(SCAN_METADATA_VERSION) {
+            updates.push("metadata_version = ?");
+            params.push(Box::new(SCAN_METADATA_VERSION));
+        }
+
+        if updates.is_empty() {
+            if !is_relinked {
+                unchanged += 1;
+            }
+            continue;
+        }
+
+        params.push(Box::new(row.id));
+        let query = format!("UPDATE images SET {} WHERE id = ?", updates.join(", "));
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+        tx.execute(&query, params_refs.as_slice())
+            .map_err(|e| format!("Failed to update {}: {}", info.file_path, e))?;
+        if !is_relinked {
+            updated += 1;
+        }
+    }
+
+    // スキャン結果に含まれない既存行を欠損としてマーク
+    let scanned_paths: HashSet<&str> = file_infos.iter().map(|f| f.file_path.as_str()).collect();
+    let missing = mark_missing_images(&tx, directory_id, &scanned_paths)?;
+
+    // ファイル数とスキャン日時を更新
+    tx.execute(
+        "UPDATE directories SET file_count = (SELECT COUNT(*) FROM images WHERE directory_id = ?1), last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        rusqlite::params![directory_id],
+    )
+    .map_err(|e| format!("Failed to update directory: {}", e))?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!(
+        "Synced {}: {} inserted, {} updated, {} unchanged, {} relinked, {} missing, {} sidecars imported",
+        directory_path, inserted, updated, unchanged, relinked, missing, sidecars_imported
+    );
+
+    Ok(SyncSummary {
+        directory_id,
+        directory_path: directory_path.to_string(),
+        scanned: file_infos.len() as i64,
+        inserted,
+        updated,
+        unchanged,
+        relinked,
+        missing,
+        sidecars_imported,
+    })
+}
+
+/// file_path で既存 images 行を取得（同期時の比較用）
+fn query_synced_image_row(
+    conn: &rusqlite::Connection,
+    file_path: &str,
+) -> Result<Option<SyncedImageRow>, String> {
+    let result = conn.query_row(
+        "SELECT id, file_name, file_type, directory_id, COALESCE(is_missing, 0), file_size, file_mtime,
+            content_hash, perceptual_hash, duration_seconds, width, height, video_codec, audio_codec,
+            captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation,
+            taken_at, taken_at_source, latitude, longitude, altitude, metadata_version, city, phash_failed_mtime, metadata_failed_mtime
+        FROM images WHERE file_path = ?",
+        rusqlite::params![file_path],
+        |row| {
+            Ok(SyncedImageRow {
+                id: row.get(0)?,
+                file_name: row.get(1)?,
+                file_type: row.get(2)?,
+                directory_id: row.get(3)?,
+                is_missing: row.get::<_, i64>(4)? != 0,
+                file_size: row.get(5)?,
+                file_mtime: row.get(6)?,
+                content_hash: row.get(7)?,
+                perceptual_hash: row.get(8)?,
+                duration_seconds: row.get(9)?,
+                width: row.get(10)?,
+                height: row.get(11)?,
+                video_codec: row.get(12)?,
+                audio_codec: row.get(13)?,
+                captured_at: row.get(14)?,
+                camera_make: row.get(15)?,
+                camera_model: row.get(16)?,
+                lens_model: row.get(17)?,
+                iso: row.get(18)?,
+                exposure_time: row.get(19)?,
+                f_number: row.get(20)?,
+                orientation: row.get(21)?,
+                taken_at: row.get(22)?,
+                taken_at_source: row.get(23)?,
+                latitude: row.get(24)?,
+                longitude: row.get(25)?,
+                altitude: row.get(26)?,
+                metadata_version: row.get(27)?,
+                city: row.get(28)?,
+                phash_failed_mtime: row.get(29)?,
+                metadata_failed_mtime: row.get(30)?,
+            })
+        },
+    );
+
+    match result {
+        Ok(row) => Ok(Some(row)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(format!("Failed to query image {}: {}", file_path, e)),
+    }
+}
+
+/**
+ * 新しいパスのファイルが、移動・リネームされた既存行かどうかを探します
+ * サイズと更新日時が一致し、かつ元のパスにファイルが存在しない行が
+ * ちょうど1件の場合はその行IDを返します。見つからない・曖昧な場合は
+ * 内容ハッシュの一致で同じ判定を行います（複数候補は曖昧なため再リンクしない）
+ */
+fn find_moved_image(
+    conn: &rusqlite::Connection,
+    new_path: &str,
+    file_size: Option<i64>,
+    file_mtime: Option<i64>,
+    content_hash: Option<&str>,
+) -> Result<Option<i64>, String> {
+    if let (Some(file_size), Some(file_mtime)) = (file_size, file_mtime) {
+        let found = find_single_moved_candidate(
+            conn,
+            "SELECT id, file_path FROM images WHERE file_size = ?1 AND file_mtime = ?2 AND file_path != ?3",
+            rusqlite::params![file_size, file_mtime, new_path],
+        )?;
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+
+    if let Some(content_hash) = content_hash {
+        return find_single_moved_candidate(
+            conn,
+            "SELECT id, file_path FROM images WHERE content_hash = ?1 AND file_path != ?2",
+            rusqlite::params![content_hash, new_path],
+        );
+    }
+
+    Ok(None)
+}
+
+/// 候補行のうち元のパスにファイルが存在しないものが1件だけならそのIDを返す
+fn find_single_moved_candidate(
+    conn: &rusqlite::Connection,
+    query: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Result<Option<i64>, String> {
+    let mut stmt = conn
+        .prepare(query)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let candidates: Vec<(i64, String)> = stmt
+        .query_map(params, |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query moved file candidates: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect moved file candidates: {}", e))?;
+
+    let mut moved = candidates
+        .into_iter()
+        .filter(|(_, path)| !Path::new(path).exists());
+
+    match (moved.next(), moved.next()) {
+        (Some((id, _)), None) => Ok(Some(id)),
+        _ => Ok(None),
+    }
+}
+
+/// 既存行を新しいパスに再リンク（コメント・評価・グループ所属はそのまま引き継がれる）
+fn relink_image(
+    conn: &rusqlite::Connection,
+    image_id: i64,
+    new_path: &str,
+    new_file_name: &str,
+    directory_id: i64,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE images SET file_path = ?, file_name = ?, directory_id = ?, is_missing = 0, missing_since = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?",
+        rusqlite::params![new_path, new_file_name, directory_id, image_id],
+    )
+    .map_err(|e| format!("Failed to relink image {}: {}", image_id, e))?;
+
+    println!("Relinked image {} to {}", image_id, new_path);
+    Ok(())
+}
+
+/**
+ * ディレクトリに属する行のうち、スキャン結果に含まれずディスク上にも存在しないものを
+ * 欠損（is_missing = 1）としてマークします
+ *
+ * @return 新たに欠損としてマークした行数
+ */
+fn mark_missing_images(
+    conn: &rusqlite::Connection,
+    directory_id: i64,
+    scanned_paths: &HashSet<&str>,
+) -> Result<i64, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, file_path FROM images WHERE directory_id = ? AND COALESCE(is_missing, 0) = 0")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows: Vec<(i64, String)> = stmt
+        .query_map(rusqlite::params![directory_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query directory images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect directory images: {}", e))?;
+
+    let mut missing = 0i64;
+    for (id, path) in rows {
+        if scanned_paths.contains(path.as_str()) || Path::new(&path).exists() {
+            continue;
+        }
+        conn.execute(
+            "UPDATE images SET is_missing = 1, missing_since = CURRENT_TIMESTAMP WHERE id = ?",
+            rusqlite::params![id],
+        )
+        .map_err(|e| format!("Failed to mark image {} as missing: {}", id, e))?;
+        missing += 1;
+    }
+
+    Ok(missing)
+}
+
+/// ディレクトリをスキャンして結果をDBに同期
+fn scan_and_sync_directory(
+    conn: &mut rusqlite::Connection,
+    directory_id: i64,
+    directory_path: &str,
+) -> Result<SyncSummary, String> {
+    // 外付けドライブ未接続などでディレクトリ自体が見えない場合、
+    // 全ファイルを欠損扱いにしないよう同期を中止する
+    if !Path::new(directory_path).is_dir() {
+        return Err(format!("Directory does not exist: {}", directory_path));
+    }
+
+    let files = crate::fs_utils::scan_images_in_directory(directory_path)?;
+    let baseline = ScanBaseline::load(conn, directory_path);
+    let file_infos = build_file_infos(files, &baseline);
+    sync_file_infos(conn, directory_id, directory_path, &file_infos)
+}
+
+/**
+ * 指定パスをスキャンし、結果をDBに同期します
+ * ディレクトリが未登録の場合は directories にも登録します
+ */
+#[tauri::command]
+pub async fn sync_directory(path: String) -> Result<SyncSummary, String> {
+    use rusqlite::Connection;
+
+    let dir_path = Path::new(&path);
+    if !dir_path.exists() || !dir_path.is_dir() {
+        return Err(format!("Directory does not exist: {}", path));
+    }
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let directory_id = get_or_create_directory_id(&conn, &path)?;
+    scan_and_sync_directory(&mut conn, directory_id, &path)
+}
+
+/**
+ * 登録済みディレクトリを再スキャンし、結果をDBに同期します
+ */
+#[tauri::command]
+pub async fn sync_single_directory(directory_id: i64) -> Result<SyncSummary, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let dir_path: String = conn
+        .query_row(
+            "SELECT path FROM directories WHERE id = ?",
+            rusqlite::params![directory_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Directory not found: {}", e))?;
+
+    scan_and_sync_directory(&mut conn, directory_id, &dir_path)
+}
+
+/**
+ * 全アクティブディレクトリを再スキャンし、結果をDBに同期します
+ */
+#[tauri::command]
+pub async fn sync_all_active_directories() -> Result<Vec<SyncSummary>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let dirs: Vec<(i64, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, path FROM directories WHERE is_active = 1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query active directories: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect directories: {}", e))?;
+        rows
+    };
+
+    let mut summaries = Vec::new();
+    for (dir_id, dir_path) in &dirs {
+        if !Path::new(dir_path).is_dir() {
+            eprintln!("Warning: Skipping unavailable directory: {}", dir_path);
+            continue;
+        }
+        summaries.push(scan_and_sync_directory(&mut conn, *dir_id, dir_path)?);
+    }
+
+    Ok(summaries)
+}
+
+// ============================================================
+// ライブラリの整合性チェック（欠損・移動ファイルの検出）
+// ============================================================
+
+/**
+ * ライブラリ整合性チェックの結果サマリー
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconcileSummary {
+    pub directories_checked: i64,
+    /// ディレクトリ自体が見つからずスキップした数（外付けドライブ未接続など）
+    pub directories_skipped: i64,
+    pub relinked: i64,
+    pub missing: i64,
+    pub restored: i64,
+}
+
+/**
+ * 登録済みの全ディレクトリについて、ディスク上のファイルとDBの整合性を取ります
+ * - 消えたファイルを欠損（is_missing = 1）としてマーク
+ * - 移動・リネームされたファイルを既存行に再リンク（評価・コメント・グループ所属を維持）
+ * - 欠損扱いだったファイルが戻っていれば欠損マークを解除
+ * 新規ファイルの登録やメタデータ抽出は行いません（sync_* コマンドを使用）
+ */
+#[tauri::command]
+pub async fn reconcile_library() -> Result<ReconcileSummary, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let dirs: Vec<(i64, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, path FROM directories")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query directories: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect directories: {}", e))?;
+        rows
+    };
+
+    let mut summary = ReconcileSummary {
+        directories_checked: 0,
+        directories_skipped: 0,
+        relinked: 0,
+        missing: 0,
+        restored: 0,
+    };
+
+    for (dir_id, dir_path) in &dirs {
+        if !Path::new(dir_path).is_dir() {
+            eprintln!("Warning: Skipping unavailable directory: {}", dir_path);
+            summary.directories_skipped += 1;
+            continue;
+        }
+
+        let files = crate::fs_utils::scan_images_in_directory(dir_path)?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for file in &files {
+            match query_synced_image_row(&tx, &file.path)? {
+                Some(row) if row.is_missing => {
+                    tx.execute(
+                        "UPDATE images SET is_missing = 0, missing_since = NULL WHERE id = ?",
+                        rusqlite::params![row.id],
+                    )
+                    .map_err(|e| format!("Failed to restore image {}: {}", row.id, e))?;
+                    summary.restored += 1;
+                }
+                Some(_) => {}
+                None => {
+                    // 未登録ファイルのみハッシュを計算して移動判定に使う
+                    let content_hash = crate::hash_utils::compute_file_hash(&file.path).ok();
+                    if let Some(image_id) = find_moved_image(
+                        &tx,
+                        &file.path,
+                        file.size,
+                        file.mtime,
+                        content_hash.as_deref(),
+                    )? {
+                        let file_name = crate::fs_utils::get_file_name(&file.path);
+                        relink_image(&tx, image_id, &file.path, &file_name, *dir_id)?;
+                        summary.relinked += 1;
+                    }
+                }
+            }
+        }
+
+        let scanned_paths: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        summary.missing += mark_missing_images(&tx, *dir_id, &scanned_paths)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        summary.directories_checked += 1;
+    }
+
+    println!(
+        "Reconciled {} directories: {} relinked, {} missing, {} restored ({} skipped)",
+        summary.directories_checked, summary.relinked, summary.missing, summary.restored,
+        summary.directories_skipped
+    );
+
+    Ok(summary)
+}
+
+/**
+ * 欠損としてマークされた画像をDBから削除します
+ * image_ids を指定した場合はその中の欠損画像のみ削除します
+ *
+ * @return 削除した行数
+ */
+#[tauri::command]
+pub fn purge_missing_images(image_ids: Option<Vec<i64>>) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let missing_ids: Vec<i64> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM images WHERE is_missing = 1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let ids = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to query missing images: {}", e))?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(|e| format!("Failed to collect missing images: {}", e))?;
+        ids
+    };
+
+    let mut purged = 0i64;
+    for id in missing_ids {
+        if let Some(ids) = &image_ids {
+            if !ids.contains(&id) {
+                continue;
+            }
+        }
+
+        // 外部キー制約に頼らず関連レコードも明示的に削除
+        tx.execute("DELETE FROM image_groups WHERE image_id = ?", rusqlite::params![id])
+            .map_err(|e| format!("Failed to delete group memberships: {}", e))?;
+        set_image_tags(&tx, id, &[])?;
+        tx.execute(
+            "UPDATE groups SET representative_image_id = NULL WHERE representative_image_id = ?",
+            rusqlite::params![id],
+        )
+        .map_err(|e| format!("Failed to clear representative image: {}", e))?;
+        tx.execute("DELETE FROM images WHERE id = ?", rusqlite::params![id])
+            .map_err(|e| format!("Failed to delete image: {}", e))?;
+        purged += 1;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    println!("Purged {} missing images", purged);
+
+    Ok(purged)
+}
+
+// ============================================================
+// 重複ファイルの検出・統合
+// ============================================================
+
+/**
+ * 重複クラスタ内の1件分の情報
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateImage {
+    pub id: i64,
+    pub file_path: String,
+    pub file_name: String,
+    pub file_size: Option<i64>,
+    pub directory_id: Option<i64>,
+    pub directory_path: Option<String>,
+    pub rating: i64,
+    pub is_favorite: i64,
+}
+
+/**
+ * 内容ハッシュが一致するファイルのまとまり
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub content_hash: String,
+    pub file_size: Option<i64>,
+    pub images: Vec<DuplicateImage>,
+}
+
+/**
+ * 内容ハッシュが一致するファイルをクラスタとして取得します
+ * 欠損としてマークされた画像は対象外です
+ */
+#[tauri::command]
+pub fn find_duplicates() -> Result<Vec<DuplicateCluster>, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
@@ -1007,171 +4813,483 @@ pub fn get_all_directories() -> Result<Vec<DirectoryData>, String> {
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, path, name, is_active, last_scanned_at, file_count, created_at
-            FROM directories ORDER BY created_at DESC",
+            "SELECT i.content_hash, i.id, i.file_path, i.file_name, i.file_size, i.directory_id, d.path,
+                COALESCE(i.rating, 0), COALESCE(i.is_favorite, 0)
+            FROM images i
+            LEFT JOIN directories d ON d.id = i.directory_id
+            WHERE i.content_hash IS NOT NULL
+                AND COALESCE(i.is_missing, 0) = 0
+                AND i.content_hash IN (
+                    SELECT content_hash FROM images
+                    WHERE content_hash IS NOT NULL AND COALESCE(is_missing, 0) = 0
+                    GROUP BY content_hash HAVING COUNT(*) > 1
+                )
+            ORDER BY i.file_size DESC, i.content_hash, i.id",
         )
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let dirs = stmt
+    let rows = stmt
         .query_map([], |row| {
-            Ok(DirectoryData {
-                id: row.get(0)?,
-                path: row.get(1)?,
-                name: row.get(2)?,
-                is_active: row.get(3)?,
-                last_scanned_at: row.get(4)?,
-                file_count: row.get(5)?,
-                created_at: row.get(6)?,
-            })
+            Ok((
+                row.get::<_, String>(0)?,
+                DuplicateImage {
+                    id: row.get(1)?,
+                    file_path: row.get(2)?,
+                    file_name: row.get(3)?,
+                    file_size: row.get(4)?,
+                    directory_id: row.get(5)?,
+                    directory_path: row.get(6)?,
+                    rating: row.get(7)?,
+                    is_favorite: row.get(8)?,
+                },
+            ))
         })
-        .map_err(|e| format!("Failed to query directories: {}", e))?
+        .map_err(|e| format!("Failed to query duplicates: {}", e))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect directories: {}", e))?;
+        .map_err(|e| format!("Failed to collect duplicates: {}", e))?;
+
+    // ORDER BY でハッシュごとに連続しているので順にまとめる
+    let mut clusters: Vec<DuplicateCluster> = Vec::new();
+    for (content_hash, image) in rows {
+        match clusters.last_mut() {
+            Some(cluster) if cluster.content_hash == content_hash => cluster.images.push(image),
+            _ => clusters.push(DuplicateCluster {
+                content_hash,
+                file_size: image.file_size,
+                images: vec![image],
+            }),
+        }
+    }
 
-    Ok(dirs)
+    Ok(clusters)
 }
 
 /**
- * ディレクトリのアクティブ/非アクティブ状態を切り替えます
+ * 重複画像のメタデータを残す画像（keeper）に統合します
+ * - 評価は最大値、お気に入りはいずれかが付いていれば付与
+ * - タグは和集合、コメントは keeper が空なら最初に見つかったコメントを採用
+ * - グループ所属と代表画像の指定を keeper に付け替え
+ * 重複側の行とファイル自体は削除しません
+ *
+ * @return 統合した重複画像の数
  */
 #[tauri::command]
-pub fn set_directory_active(directory_id: i64, is_active: bool) -> Result<(), String> {
+pub fn merge_duplicates(
+    app: tauri::AppHandle,
+    keeper_id: i64,
+    duplicate_ids: Vec<i64>,
+) -> Result<i64, String> {
+    use rusqlite::Connection;
+
+    let duplicate_ids: Vec<i64> = duplicate_ids
+        .into_iter()
+        .filter(|id| *id != keeper_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if duplicate_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let keeper = query_image_by_id(&tx, keeper_id)?;
+    let keeper_hash = keeper
+        .content_hash
+        .clone()
+        .ok_or_else(|| format!("Image {} has no content hash", keeper_id))?;
+
+    let mut duplicates = Vec::with_capacity(duplicate_ids.len());
+    for id in &duplicate_ids {
+        let image = query_image_by_id(&tx, *id)?;
+        if image.content_hash.as_deref() != Some(keeper_hash.as_str()) {
+            return Err(format!("Image {} is not a duplicate of image {}", id, keeper_id));
+        }
+        duplicates.push(image);
+    }
+
+    // メタデータの統合
+    let mut tags = keeper.tags.clone();
+    for tag in duplicates.iter().flat_map(|d| d.tags.iter()) {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    let keeper_has_comment = keeper.comment.as_deref().is_some_and(|c| !c.trim().is_empty());
+    let comment = if keeper_has_comment {
+        None
+    } else {
+        duplicates
+            .iter()
+            .filter_map(|d| d.comment.clone())
+            .find(|c| !c.trim().is_empty())
+            .map(Some)
+    };
+    let rating = duplicates.iter().map(|d| d.rating).fold(keeper.rating, i64::max);
+    let is_favorite = duplicates.iter().map(|d| d.is_favorite).fold(keeper.is_favorite, i64::max);
+
+    let changes = ImageMetadataChanges {
+        comment,
+        tags: Some(tags),
+        add_tags: vec![],
+        remove_tags: vec![],
+        rating: Some(rating),
+        is_favorite: Some(is_favorite),
+    };
+    apply_image_metadata_changes(&tx, keeper_id, &changes, false)?;
+
+    // グループ所属と代表画像の付け替え
+    for id in &duplicate_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO image_groups (image_id, group_id, position, caption)
+            SELECT ?1, group_id, position, caption FROM image_groups WHERE image_id = ?2",
+            rusqlite::params![keeper_id, id],
+        )
+        .map_err(|e| format!("Failed to move group memberships: {}", e))?;
+        tx.execute("DELETE FROM image_groups WHERE image_id = ?", rusqlite::params![id])
+            .map_err(|e| format!("Failed to delete group memberships: {}", e))?;
+        tx.execute(
+            "UPDATE groups SET representative_image_id = ?1 WHERE representative_image_id = ?2",
+            rusqlite::params![keeper_id, id],
+        )
+        .map_err(|e| format!("Failed to update representative image: {}", e))?;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    let merged = duplicate_ids.len() as i64;
+    println!("Merged {} duplicates into image {}", merged, keeper_id);
+
+    write_enabled_xmp_sidecars(&conn, &[keeper_id]);
+
+    let mut updated_ids = vec![keeper_id];
+    updated_ids.extend(duplicate_ids);
+    emit_images_updated(&app, updated_ids);
+
+    Ok(merged)
+}
+
+// ============================================================
+// 類似画像の検出（知覚ハッシュ）
+// ============================================================
+
+/**
+ * 類似画像の検索結果
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarImage {
+    pub image: ImageData,
+    /// 知覚ハッシュのハミング距離（0で見た目がほぼ同一）
+    pub distance: u32,
+}
+
+/**
+ * 見た目が似ている画像のまとまり
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarCluster {
+    pub images: Vec<ImageData>,
+}
+
+/// 類似判定のしきい値を検証（省略時はデフォルト値）
+fn validate_max_distance(max_distance: Option<u32>) -> Result<u32, String> {
+    let max_distance = max_distance.unwrap_or(crate::phash_utils::DEFAULT_MAX_DISTANCE);
+    if max_distance > crate::phash_utils::HASH_BITS {
+        return Err(format!(
+            "max_distance must be between 0 and {}",
+            crate::phash_utils::HASH_BITS
+        ));
+    }
+    Ok(max_distance)
+}
+
+/// 知覚ハッシュを持つ欠損していない画像を取得
+fn query_hashed_images(conn: &rusqlite::Connection) -> Result<Vec<(ImageData, u64)>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM images WHERE perceptual_hash IS NOT NULL AND COALESCE(is_missing, 0) = 0 ORDER BY id",
+            IMAGE_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let images = stmt
+        .query_map([], row_to_image_data)
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
+
+    Ok(images
+        .into_iter()
+        .filter_map(|image| {
+            let hash = image
+                .perceptual_hash
+                .as_deref()
+                .and_then(crate::phash_utils::parse_hash)?;
+            Some((image, hash))
+        })
+        .collect())
+}
+
+/**
+ * 指定画像と見た目が似ている画像を取得します（距離の近い順）
+ * 再エンコード・リサイズされた画像や連写などを検出できます
+ *
+ * @param image_id 基準となる画像ID
+ * @param max_distance 許容するハミング距離（0〜64、省略時は10）
+ */
+#[tauri::command]
+pub fn find_similar(image_id: i64, max_distance: Option<u32>) -> Result<Vec<SimilarImage>, String> {
+    use rusqlite::Connection;
+
+    let max_distance = validate_max_distance(max_distance)?;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let target = query_image_by_id(&conn, image_id)?;
+    let target_hash = target
+        .perceptual_hash
+        .as_deref()
+        .and_then(crate::phash_utils::parse_hash)
+        .ok_or_else(|| format!("Image {} has no perceptual hash", image_id))?;
+
+    let mut similar: Vec<SimilarImage> = query_hashed_images(&conn)?
+        .into_iter()
+        .filter(|(image, _)| image.id != image_id)
+        .filter_map(|(image, hash)| {
+            let distance = crate::phash_utils::hamming_distance(target_hash, hash);
+            (distance <= max_distance).then_some(SimilarImage { image, distance })
+        })
+        .collect();
+
+    similar.sort_by_key(|s| (s.distance, s.image.id));
+
+    Ok(similar)
+}
+
+/**
+ * ライブラリ全体で見た目が似ている画像をクラスタにまとめます
+ * 距離がしきい値以内の画像同士を連結したまとまりを返します（2件以上のみ、大きい順）
+ *
+ * @param max_distance 許容するハミング距離（0〜64、省略時は10）
+ */
+#[tauri::command]
+pub async fn cluster_similar_images(max_distance: Option<u32>) -> Result<Vec<SimilarCluster>, String> {
     use rusqlite::Connection;
 
+    let max_distance = validate_max_distance(max_distance)?;
+
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let active_val: i64 = if is_active { 1 } else { 0 };
+    let hashed = query_hashed_images(&conn)?;
+    let hashes: Vec<(i64, u64)> = hashed.iter().map(|(image, hash)| (image.id, *hash)).collect();
+
+    let mut images_by_id: HashMap<i64, ImageData> = hashed
+        .into_iter()
+        .map(|(image, _)| (image.id, image))
+        .collect();
+
+    let mut clusters: Vec<SimilarCluster> = crate::phash_utils::cluster_by_distance(&hashes, max_distance)
+        .into_iter()
+        .map(|ids| SimilarCluster {
+            images: ids.iter().filter_map(|id| images_by_id.remove(id)).collect(),
+        })
+        .collect();
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.images.len()));
+
+    println!("Found {} similar image clusters", clusters.len());
+
+    Ok(clusters)
+}
+
+// ============================================================
+// XMPサイドカー（評価・タグ・コメントの外部ツール連携）
+// ============================================================
+
+/**
+ * XMPサイドカー書き出しの結果
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmpSidecarSummary {
+    pub written: i64,
+    pub failed: i64,
+}
+
+/**
+ * XMPサイドカーの評価・タグ・説明を画像に取り込みます
+ * 評価と説明はサイドカーに値があれば上書きし、タグは既存のタグとの和集合にします
+ * （他のツールで外したタグは残りますが、DB側のタグが失われることはありません）
+ */
+fn import_xmp_sidecar(
+    conn: &rusqlite::Connection,
+    image_id: i64,
+    sidecar: &crate::xmp_utils::XmpSidecar,
+) -> Result<(), String> {
+    let metadata = &sidecar.metadata;
+    let changes = ImageMetadataChanges {
+        comment: metadata
+            .description
+            .as_deref()
+            .and_then(|d| validate_image_comment(d).ok().flatten())
+            .map(Some),
+        tags: None,
+        add_tags: metadata.tags.iter().filter_map(|t| validate_tag_name(t).ok()).collect(),
+        remove_tags: vec![],
+        rating: metadata.rating,
+        is_favorite: None,
+    };
+    apply_image_metadata_changes(conn, image_id, &changes, false)?;
+
+    conn.execute(
+        "UPDATE images SET xmp_sidecar_mtime = ? WHERE id = ?",
+        rusqlite::params![sidecar.mtime, image_id],
+    )
+    .map_err(|e| format!("Failed to import XMP sidecar for image {}: {}", image_id, e))?;
+
+    Ok(())
+}
+
+/// 画像の評価・タグ・コメントをXMPサイドカーに書き出し、サイドカーの更新日時を記録
+/// （記録した更新日時により、次回スキャンで自分の書き出しを取り込み直さない）
+fn write_xmp_sidecar_for_image(conn: &rusqlite::Connection, image_id: i64) -> Result<(), String> {
+    let (file_path, rating, tags, comment): (String, Option<i64>, Option<String>, Option<String>) = conn
+        .query_row(
+            &format!("SELECT file_path, rating, {}, comment FROM images WHERE id = ?", IMAGE_TAGS_SQL),
+            rusqlite::params![image_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("Image not found: {}: {}", image_id, e))?;
+
+    // 元ファイルがない場所にサイドカーだけ作らない
+    if !Path::new(&file_path).is_file() {
+        return Err(format!("File not found: {}", file_path));
+    }
+
+    let metadata = crate::xmp_utils::SidecarMetadata {
+        rating: Some(rating.unwrap_or(0)),
+        tags: parse_tags(tags),
+        description: comment,
+    };
+    let mtime = crate::xmp_utils::write_sidecar(&file_path, &metadata)?;
+
     conn.execute(
-        "UPDATE directories SET is_active = ? WHERE id = ?",
-        rusqlite::params![active_val, directory_id],
+        "UPDATE images SET xmp_sidecar_mtime = ? WHERE id = ?",
+        rusqlite::params![mtime, image_id],
     )
-    .map_err(|e| format!("Failed to update directory active state: {}", e))?;
+    .map_err(|e| format!("Failed to record XMP sidecar for image {}: {}", image_id, e))?;
 
     Ok(())
 }
 
+/// サイドカー書き出しが有効なディレクトリの画像について、XMPサイドカーを書き出す
+/// 書き出しに失敗してもDBの更新は取り消さず、警告のみ出力する
+fn write_enabled_xmp_sidecars(conn: &rusqlite::Connection, image_ids: &[i64]) {
+    for &image_id in image_ids {
+        let enabled: bool = conn
+            .query_row(
+                "SELECT COALESCE(d.write_xmp_sidecars, 0) FROM images i
+                JOIN directories d ON i.directory_id = d.id
+                WHERE i.id = ?",
+                rusqlite::params![image_id],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|v| v != 0)
+            .unwrap_or(false);
+
+        if enabled {
+            if let Err(e) = write_xmp_sidecar_for_image(conn, image_id) {
+                eprintln!("Warning: Failed to write XMP sidecar: {}", e);
+            }
+        }
+    }
+}
+
 /**
- * 指定ディレクトリを再スキャンします
+ * ディレクトリのXMPサイドカー書き出しを有効/無効にします
+ * 有効にすると、以降の評価・タグ・コメントの変更がサイドカーに書き出されます
+ * （既存の画像をまとめて書き出すには write_xmp_sidecars を使用）
  */
 #[tauri::command]
-pub async fn scan_single_directory(directory_id: i64) -> Result<Vec<ImageFileInfo>, String> {
+pub fn set_directory_xmp_sidecars(directory_id: i64, enabled: bool) -> Result<(), String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    // ディレクトリパスを取得
-    let dir_path: String = conn
-        .query_row(
-            "SELECT path FROM directories WHERE id = ?",
-            rusqlite::params![directory_id],
-            |row| row.get(0),
+    let updated = conn
+        .execute(
+            "UPDATE directories SET write_xmp_sidecars = ? WHERE id = ?",
+            rusqlite::params![i64::from(enabled), directory_id],
         )
-        .map_err(|e| format!("Directory not found: {}", e))?;
-
-    // ファイルスキャン
-    let file_paths = crate::fs_utils::scan_images_in_directory(&dir_path)?;
-
-    let result: Vec<ImageFileInfo> = file_paths
-        .into_iter()
-        .map(|file_path| {
-            let file_name = crate::fs_utils::get_file_name(&file_path);
-            let file_type = crate::fs_utils::get_file_type(&file_path);
-            let metadata = if file_type == "video" {
-                crate::video_utils::extract_video_metadata(&file_path).ok()
-            } else {
-                None
-            };
-
-            ImageFileInfo {
-                file_path,
-                file_name,
-                file_type,
-                duration_seconds: metadata.as_ref().map(|m| m.duration_seconds),
-                width: metadata.as_ref().map(|m| m.width),
-                height: metadata.as_ref().map(|m| m.height),
-                video_codec: metadata.as_ref().map(|m| m.video_codec.clone()),
-                audio_codec: metadata.as_ref().and_then(|m| m.audio_codec.clone()),
-            }
-        })
-        .collect();
-
-    // ファイル数を更新
-    conn.execute(
-        "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
-        rusqlite::params![result.len() as i64, directory_id],
-    )
-    .map_err(|e| format!("Failed to update directory: {}", e))?;
+        .map_err(|e| format!("Failed to update directory: {}", e))?;
 
-    println!("Scanned {} files for directory {}", result.len(), dir_path);
+    if updated == 0 {
+        return Err(format!("Directory not found: {}", directory_id));
+    }
 
-    Ok(result)
+    Ok(())
 }
 
 /**
- * 全アクティブディレクトリをスキャンします
+ * 画像の評価・タグ・コメントをXMPサイドカーに書き出します
+ * 既存のサイドカーがあれば該当項目のみ置き換え、他のツールの設定は残します
+ *
+ * @param image_ids 書き出す画像ID（省略時は書き出しが有効なディレクトリの全画像）
  */
 #[tauri::command]
-pub async fn scan_all_active_directories() -> Result<Vec<ImageFileInfo>, String> {
+pub async fn write_xmp_sidecars(image_ids: Option<Vec<i64>>) -> Result<XmpSidecarSummary, String> {
     use rusqlite::Connection;
 
     let db_path = crate::db::get_db_path()?;
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
-    let mut stmt = conn
-        .prepare("SELECT id, path FROM directories WHERE is_active = 1")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
-
-    let dirs: Vec<(i64, String)> = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(|e| format!("Failed to query active directories: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect directories: {}", e))?;
-
-    drop(stmt);
-
-    let mut all_results = Vec::new();
-
-    for (dir_id, dir_path) in &dirs {
-        let file_paths = crate::fs_utils::scan_images_in_directory(dir_path)?;
-        let mut dir_count = 0i64;
-
-        for file_path in file_paths {
-            let file_name = crate::fs_utils::get_file_name(&file_path);
-            let file_type = crate::fs_utils::get_file_type(&file_path);
-            let metadata = if file_type == "video" {
-                crate::video_utils::extract_video_metadata(&file_path).ok()
-            } else {
-                None
-            };
-
-            all_results.push(ImageFileInfo {
-                file_path,
-                file_name,
-                file_type,
-                duration_seconds: metadata.as_ref().map(|m| m.duration_seconds),
-                width: metadata.as_ref().map(|m| m.width),
-                height: metadata.as_ref().map(|m| m.height),
-                video_codec: metadata.as_ref().map(|m| m.video_codec.clone()),
-                audio_codec: metadata.as_ref().and_then(|m| m.audio_codec.clone()),
-            });
-            dir_count += 1;
+    let image_ids = match image_ids {
+        Some(ids) => ids,
+        None => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT i.id FROM images i
+                    JOIN directories d ON i.directory_id = d.id
+                    WHERE COALESCE(d.write_xmp_sidecars, 0) = 1 AND COALESCE(i.is_missing, 0) = 0
+                    ORDER BY i.id",
+                )
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            let ids = stmt
+                .query_map([], |row| row.get(0))
+                .map_err(|e| format!("Failed to query images: {}", e))?
+                .collect::<Result<Vec<i64>, _>>()
+                .map_err(|e| format!("Failed to collect images: {}", e))?;
+            ids
         }
+    };
 
-        conn.execute(
-            "UPDATE directories SET file_count = ?, last_scanned_at = CURRENT_TIMESTAMP WHERE id = ?",
-            rusqlite::params![dir_count, dir_id],
-        )
-        .map_err(|e| format!("Failed to update directory: {}", e))?;
+    let mut summary = XmpSidecarSummary { written: 0, failed: 0 };
+    for image_id in image_ids {
+        match write_xmp_sidecar_for_image(&conn, image_id) {
+            Ok(()) => summary.written += 1,
+            Err(e) => {
+                eprintln!("Warning: Failed to write XMP sidecar: {}", e);
+                summary.failed += 1;
+            }
+        }
     }
 
-    println!("Scanned {} files across {} active directories", all_results.len(), dirs.len());
+    println!("Wrote {} XMP sidecars ({} failed)", summary.written, summary.failed);
 
-    Ok(all_results)
+    Ok(summary)
 }
 
 // ============================================================
@@ -1207,6 +5325,27 @@ pub fn log_action(
     let conn = Connection::open(&db_path)
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
+    insert_action_log(
+        &conn,
+        &action_type,
+        &target_table,
+        target_id,
+        old_value,
+        new_value,
+    )
+}
+
+/**
+ * action_log にエントリを追加します（log_action と各コマンドで共有）
+ */
+fn insert_action_log(
+    conn: &rusqlite::Connection,
+    action_type: &str,
+    target_table: &str,
+    target_id: i64,
+    old_value: Option<String>,
+    new_value: Option<String>,
+) -> Result<i64, String> {
     // 新しいアクションを記録すると、それ以降のundoneアクションは無効化（redo不可に）
     conn.execute(
         "DELETE FROM action_log WHERE is_undone = 1",
@@ -1355,6 +5494,39 @@ struct ExportImageData {
     tags: Option<String>,
     rating: i64,
     is_favorite: i64,
+    // 撮影情報（ファイルから再抽出できるためインポート時は使用しない）
+    #[serde(default)]
+    width: Option<i64>,
+    #[serde(default)]
+    height: Option<i64>,
+    #[serde(default)]
+    captured_at: Option<String>,
+    #[serde(default)]
+    camera_make: Option<String>,
+    #[serde(default)]
+    camera_model: Option<String>,
+    #[serde(default)]
+    lens_model: Option<String>,
+    #[serde(default)]
+    iso: Option<i64>,
+    #[serde(default)]
+    exposure_time: Option<f64>,
+    #[serde(default)]
+    f_number: Option<f64>,
+    #[serde(default)]
+    orientation: Option<i64>,
+    #[serde(default)]
+    latitude: Option<f64>,
+    #[serde(default)]
+    longitude: Option<f64>,
+    #[serde(default)]
+    altitude: Option<f64>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    region: Option<String>,
+    #[serde(default)]
+    city: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1363,12 +5535,53 @@ struct ExportGroupData {
     name: String,
     description: Option<String>,
     color: String,
+    /// スマートグループの検索クエリ（メンバーシップはエクスポートしない）
+    #[serde(default)]
+    smart_query: Option<String>,
+    /// 親グループの id（このファイル内の id）
+    #[serde(default)]
+    parent_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ExportGroupMembership {
+    /// グループの id（このファイル内の id、無い場合は group_name でマッチ）
+    #[serde(default)]
+    group_id: Option<i64>,
     group_name: String,
     image_file_path: String,
+    /// グループ内の並び順（0始まり、無い場合は末尾に追加）
+    #[serde(default)]
+    position: Option<i64>,
+    /// グループ内での画像のキャプション
+    #[serde(default)]
+    caption: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportGroupComment {
+    /// このファイル内の id（返信先の参照に使う）
+    id: i64,
+    /// グループの id（このファイル内の id）
+    group_id: i64,
+    comment: String,
+    #[serde(default)]
+    author: Option<String>,
+    /// 返信先のコメントの id（このファイル内の id）
+    #[serde(default)]
+    parent_comment_id: Option<i64>,
+    created_at: String,
+    #[serde(default)]
+    updated_at: Option<String>,
+    /// 編集前の本文（古い順）
+    #[serde(default)]
+    edits: Vec<ExportGroupCommentEdit>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportGroupCommentEdit {
+    comment: String,
+    edited_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1378,6 +5591,8 @@ struct ExportData {
     images: Vec<ExportImageData>,
     groups: Vec<ExportGroupData>,
     group_memberships: Vec<ExportGroupMembership>,
+    #[serde(default)]
+    group_comments: Vec<ExportGroupComment>,
 }
 
 /**
@@ -1393,7 +5608,13 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
 
     // 画像メタデータを取得
     let mut stmt = conn
-        .prepare("SELECT file_path, file_name, file_type, comment, tags, rating, is_favorite FROM images ORDER BY id")
+        .prepare(&format!(
+            "SELECT file_path, file_name, file_type, comment, {}, rating, is_favorite,
+                width, height, captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation,
+                latitude, longitude, altitude, country, region, city
+            FROM images ORDER BY id",
+            IMAGE_TAGS_SQL
+        ))
         .map_err(|e| format!("Failed to prepare images query: {}", e))?;
 
     let images: Vec<ExportImageData> = stmt
@@ -1406,6 +5627,22 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
                 tags: row.get(4)?,
                 rating: row.get(5)?,
                 is_favorite: row.get(6)?,
+                width: row.get(7)?,
+                height: row.get(8)?,
+                captured_at: row.get(9)?,
+                camera_make: row.get(10)?,
+                camera_model: row.get(11)?,
+                lens_model: row.get(12)?,
+                iso: row.get(13)?,
+                exposure_time: row.get(14)?,
+                f_number: row.get(15)?,
+                orientation: row.get(16)?,
+                latitude: row.get(17)?,
+                longitude: row.get(18)?,
+                altitude: row.get(19)?,
+                country: row.get(20)?,
+                region: row.get(21)?,
+                city: row.get(22)?,
             })
         })
         .map_err(|e| format!("Failed to query images: {}", e))?
@@ -1414,7 +5651,7 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
 
     // グループ情報を取得
     let mut stmt = conn
-        .prepare("SELECT id, name, description, color FROM groups ORDER BY id")
+        .prepare("SELECT id, name, description, color, smart_query, parent_id FROM groups ORDER BY sort_order, id")
         .map_err(|e| format!("Failed to prepare groups query: {}", e))?;
 
     let groups: Vec<ExportGroupData> = stmt
@@ -1424,6 +5661,8 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
                 name: row.get(1)?,
                 description: row.get(2)?,
                 color: row.get(3)?,
+                smart_query: row.get(4)?,
+                parent_id: row.get(5)?,
             })
         })
         .map_err(|e| format!("Failed to query groups: {}", e))?
@@ -1433,31 +5672,76 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
     // グループメンバーシップを取得
     let mut stmt = conn
         .prepare(
-            "SELECT g.name, i.file_path
+            "SELECT g.id, g.name, i.file_path,
+                ROW_NUMBER() OVER (PARTITION BY ig.group_id ORDER BY ig.position, ig.id) - 1, ig.caption
             FROM image_groups ig
             JOIN groups g ON ig.group_id = g.id
             JOIN images i ON ig.image_id = i.id
-            ORDER BY g.name, i.file_path",
+            ORDER BY g.name, g.id, ig.position, ig.id",
         )
         .map_err(|e| format!("Failed to prepare memberships query: {}", e))?;
 
     let memberships: Vec<ExportGroupMembership> = stmt
         .query_map([], |row| {
             Ok(ExportGroupMembership {
-                group_name: row.get(0)?,
-                image_file_path: row.get(1)?,
+                group_id: row.get(0)?,
+                group_name: row.get(1)?,
+                image_file_path: row.get(2)?,
+                position: row.get(3)?,
+                caption: row.get(4)?,
             })
         })
         .map_err(|e| format!("Failed to query memberships: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Failed to collect memberships: {}", e))?;
 
+    // グループコメントと編集履歴を取得（返信先が先に来るよう id 順）
+    let mut edits: HashMap<i64, Vec<ExportGroupCommentEdit>> = HashMap::new();
+    let mut stmt = conn
+        .prepare("SELECT comment_id, comment, edited_at FROM group_comment_edits ORDER BY edited_at, id")
+        .map_err(|e| format!("Failed to prepare comment history query: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, ExportGroupCommentEdit { comment: row.get(1)?, edited_at: row.get(2)? }))
+        })
+        .map_err(|e| format!("Failed to query comment history: {}", e))?;
+    for row in rows {
+        let (comment_id, edit) = row.map_err(|e| format!("Failed to read comment history: {}", e))?;
+        edits.entry(comment_id).or_default().push(edit);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, group_id, comment, author, parent_comment_id, created_at, updated_at
+            FROM group_comments ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to prepare comments query: {}", e))?;
+
+    let group_comments: Vec<ExportGroupComment> = stmt
+        .query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(ExportGroupComment {
+                id,
+                group_id: row.get(1)?,
+                comment: row.get(2)?,
+                author: row.get(3)?,
+                parent_comment_id: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+                edits: edits.remove(&id).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| format!("Failed to query comments: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect comments: {}", e))?;
+
     let export_data = ExportData {
         version: "1.0".to_string(),
         exported_at: chrono::Local::now().to_rfc3339(),
         images,
         groups,
         group_memberships: memberships,
+        group_comments,
     };
 
     let json = serde_json::to_string_pretty(&export_data)
@@ -1469,8 +5753,8 @@ pub fn export_metadata_json(output_path: String) -> Result<String, String> {
     file.write_all(json.as_bytes())
         .map_err(|e| format!("Failed to write JSON file: {}", e))?;
 
-    println!("Exported {} images, {} groups, {} memberships to JSON",
-        export_data.images.len(), export_data.groups.len(), export_data.group_memberships.len());
+    println!("Exported {} images, {} groups, {} memberships, {} comments to JSON",
+        export_data.images.len(), export_data.groups.len(), export_data.group_memberships.len(), export_data.group_comments.len());
 
     Ok(output_path)
 }
@@ -1487,47 +5771,68 @@ pub fn export_metadata_csv(output_path: String) -> Result<String, String> {
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
     let mut stmt = conn
-        .prepare(
-            "SELECT file_path, file_name, file_type, comment, tags, rating, is_favorite FROM images ORDER BY id",
-        )
+        .prepare(&format!(
+            "SELECT file_path, file_name, file_type, comment, {}, rating, is_favorite,
+                width, height, captured_at, camera_make, camera_model, lens_model, iso, exposure_time, f_number, orientation,
+                latitude, longitude, altitude, country, region, city
+            FROM images ORDER BY id",
+            IMAGE_TAGS_SQL
+        ))
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
 
     let mut wtr = csv::Writer::from_path(&output_path)
         .map_err(|e| format!("Failed to create CSV file: {}", e))?;
 
     // ヘッダー
-    wtr.write_record(["file_path", "file_name", "file_type", "comment", "tags", "rating", "is_favorite"])
-        .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+    wtr.write_record([
+        "file_path", "file_name", "file_type", "comment", "tags", "rating", "is_favorite",
+        "width", "height", "captured_at", "camera_make", "camera_model", "lens_model",
+        "iso", "exposure_time", "f_number", "orientation",
+        "latitude", "longitude", "altitude", "country", "region", "city",
+    ])
+    .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+
+    // NULLは空文字として出力
+    fn opt<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
 
     let mut count = 0u64;
     let rows = stmt
         .query_map([], |row| {
-            Ok((
+            Ok([
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, String>(2)?,
-                row.get::<_, Option<String>>(3)?,
-                row.get::<_, Option<String>>(4)?,
-                row.get::<_, i64>(5)?,
-                row.get::<_, i64>(6)?,
-            ))
+                opt(row.get::<_, Option<String>>(3)?),
+                opt(row.get::<_, Option<String>>(4)?),
+                row.get::<_, i64>(5)?.to_string(),
+                row.get::<_, i64>(6)?.to_string(),
+                opt(row.get::<_, Option<i64>>(7)?),
+                opt(row.get::<_, Option<i64>>(8)?),
+                opt(row.get::<_, Option<String>>(9)?),
+                opt(row.get::<_, Option<String>>(10)?),
+                opt(row.get::<_, Option<String>>(11)?),
+                opt(row.get::<_, Option<String>>(12)?),
+                opt(row.get::<_, Option<i64>>(13)?),
+                opt(row.get::<_, Option<f64>>(14)?),
+                opt(row.get::<_, Option<f64>>(15)?),
+                opt(row.get::<_, Option<i64>>(16)?),
+                opt(row.get::<_, Option<f64>>(17)?),
+                opt(row.get::<_, Option<f64>>(18)?),
+                opt(row.get::<_, Option<f64>>(19)?),
+                opt(row.get::<_, Option<String>>(20)?),
+                opt(row.get::<_, Option<String>>(21)?),
+                opt(row.get::<_, Option<String>>(22)?),
+            ])
         })
         .map_err(|e| format!("Failed to query images: {}", e))?;
 
     for row_result in rows {
-        let (file_path, file_name, file_type, comment, tags, rating, is_favorite) =
-            row_result.map_err(|e| format!("Failed to read row: {}", e))?;
-
-        wtr.write_record([
-            &file_path,
-            &file_name,
-            &file_type,
-            &comment.unwrap_or_default(),
-            &tags.unwrap_or_default(),
-            &rating.to_string(),
-            &is_favorite.to_string(),
-        ])
-        .map_err(|e| format!("Failed to write CSV row: {}", e))?;
+        let record = row_result.map_err(|e| format!("Failed to read row: {}", e))?;
+
+        wtr.write_record(&record)
+            .map_err(|e| format!("Failed to write CSV row: {}", e))?;
 
         count += 1;
     }
@@ -1540,6 +5845,87 @@ pub fn export_metadata_csv(output_path: String) -> Result<String, String> {
     Ok(output_path)
 }
 
+/**
+ * 撮影位置を持つ画像・動画をGeoJSON（FeatureCollection）形式でエクスポートします
+ * 各画像を Point フィーチャーとし、座標は [経度, 緯度, 標高] の順で出力します（地名は properties に含めます）
+ */
+#[tauri::command]
+pub fn export_locations_geojson(output_path: String) -> Result<String, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, file_path, file_name, file_type, taken_at, rating, is_favorite, {},
+                latitude, longitude, altitude, country, region, city
+            FROM images
+            WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+            ORDER BY id",
+            IMAGE_TAGS_SQL
+        ))
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let features = stmt
+        .query_map([], |row| {
+            let longitude: f64 = row.get(9)?;
+            let latitude: f64 = row.get(8)?;
+            let coordinates = match row.get::<_, Option<f64>>(10)? {
+                Some(altitude) => serde_json::json!([longitude, latitude, altitude]),
+                None => serde_json::json!([longitude, latitude]),
+            };
+            let tags: Vec<String> = row
+                .get::<_, Option<String>>(7)?
+                .and_then(|t| serde_json::from_str(&t).ok())
+                .unwrap_or_default();
+
+            Ok(serde_json::json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": coordinates,
+                },
+                "properties": {
+                    "id": row.get::<_, i64>(0)?,
+                    "file_path": row.get::<_, String>(1)?,
+                    "file_name": row.get::<_, String>(2)?,
+                    "file_type": row.get::<_, Option<String>>(3)?,
+                    "taken_at": row.get::<_, Option<String>>(4)?,
+                    "rating": row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+                    "is_favorite": row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                    "tags": tags,
+                    "country": row.get::<_, Option<String>>(11)?,
+                    "region": row.get::<_, Option<String>>(12)?,
+                    "city": row.get::<_, Option<String>>(13)?,
+                },
+            }))
+        })
+        .map_err(|e| format!("Failed to query images: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect images: {}", e))?;
+
+    let count = features.len();
+    let geojson = serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+
+    let json = serde_json::to_string_pretty(&geojson)
+        .map_err(|e| format!("Failed to serialize GeoJSON: {}", e))?;
+
+    let mut file = fs::File::create(&output_path)
+        .map_err(|e| format!("Failed to create output file: {}", e))?;
+
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("Failed to write GeoJSON file: {}", e))?;
+
+    println!("Exported {} image locations to GeoJSON", count);
+
+    Ok(output_path)
+}
+
 /**
  * JSONファイルからメタデータをインポートします
  */
@@ -1563,56 +5949,97 @@ pub fn import_metadata_json(input_path: String) -> Result<String, String> {
     let mut updated_images = 0u64;
     let mut created_groups = 0u64;
     let mut added_memberships = 0u64;
+    let mut added_comments = 0u64;
 
     // 画像メタデータの更新（file_pathでマッチ）
+    let mut updated_image_ids = Vec::new();
     for img in &export_data.images {
-        let exists: bool = tx
+        let existing_id: Option<i64> = tx
             .query_row(
-                "SELECT 1 FROM images WHERE file_path = ?",
+                "SELECT id FROM images WHERE file_path = ?",
                 rusqlite::params![img.file_path],
-                |_| Ok(true),
+                |row| row.get(0),
             )
-            .unwrap_or(false);
+            .ok();
 
-        if exists {
+        if let Some(image_id) = existing_id {
             tx.execute(
-                "UPDATE images SET comment = ?, tags = ?, rating = ?, is_favorite = ?, updated_at = CURRENT_TIMESTAMP WHERE file_path = ?",
-                rusqlite::params![img.comment, img.tags, img.rating, img.is_favorite, img.file_path],
+                "UPDATE images SET comment = ?, rating = ?, is_favorite = ?, updated_at = CURRENT_TIMESTAMP WHERE file_path = ?",
+                rusqlite::params![img.comment, img.rating, img.is_favorite, img.file_path],
             )
             .map_err(|e| format!("Failed to update image: {}", e))?;
+            set_image_tags(&tx, image_id, &parse_tags(img.tags.clone()))?;
             updated_images += 1;
+            updated_image_ids.push(image_id);
         }
     }
 
-    // グループのインポート（名前でマッチ、なければ新規作成）
-    for group in &export_data.groups {
-        let existing_id: Option<i64> = tx
-            .query_row(
-                "SELECT id FROM groups WHERE name = ?",
-                rusqlite::params![group.name],
-                |row| row.get(0),
-            )
-            .ok();
-
-        if existing_id.is_none() {
-            tx.execute(
-                "INSERT INTO groups (name, description, color) VALUES (?, ?, ?)",
-                rusqlite::params![group.name, group.description, group.color],
-            )
-            .map_err(|e| format!("Failed to create group: {}", e))?;
-            created_groups += 1;
+    // グループのインポート（親から順に、同じ親の下の同名グループにマッチ、なければ新規作成）
+    // ファイル内のグループID → インポート先のグループID
+    let mut imported_group_ids: HashMap<i64, i64> = HashMap::new();
+    let exported_group_ids: HashSet<i64> = export_data.groups.iter().map(|g| g.id).collect();
+    let mut pending: Vec<&ExportGroupData> = export_data.groups.iter().collect();
+    while !pending.is_empty() {
+        // 親を取り込み済みのグループ（親がファイルに無ければ最上位）から処理する
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|g| {
+            g.parent_id.map_or(true, |p| !exported_group_ids.contains(&p) || imported_group_ids.contains_key(&p))
+        });
+        if ready.is_empty() {
+            return Err("Group hierarchy in the import file contains a cycle".to_string());
+        }
+        pending = rest;
+
+        for group in ready {
+            let parent_id = group.parent_id.and_then(|p| imported_group_ids.get(&p).copied());
+            let existing_id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM groups WHERE name = ? AND parent_id IS ?",
+                    rusqlite::params![group.name, parent_id],
+                    |row| row.get(0),
+                )
+                .ok();
+
+            let group_id = match existing_id {
+                Some(id) => id,
+                None => {
+                    let smart_query = group.smart_query.as_deref().map(validate_smart_query).transpose()?;
+                    tx.execute(
+                        "INSERT INTO groups (name, description, color, smart_query, parent_id, sort_order)
+                        VALUES (?, ?, ?, ?, ?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM groups))",
+                        rusqlite::params![group.name, group.description, group.color, smart_query, parent_id],
+                    )
+                    .map_err(|e| format!("Failed to create group: {}", e))?;
+                    created_groups += 1;
+                    tx.last_insert_rowid()
+                }
+            };
+            imported_group_ids.insert(group.id, group_id);
         }
     }
 
-    // グループメンバーシップの復元
+    // グループメンバーシップの復元（スマートグループの画像はクエリで決まるため対象外）
+    // 並び順を取り込んだグループ → (ファイル内の位置, 画像ID)
+    let mut imported_orders: HashMap<i64, Vec<(i64, i64)>> = HashMap::new();
     for membership in &export_data.group_memberships {
-        let group_id: Option<i64> = tx
-            .query_row(
-                "SELECT id FROM groups WHERE name = ?",
-                rusqlite::params![membership.group_name],
-                |row| row.get(0),
+        let group_id: Option<i64> = match membership.group_id.and_then(|id| imported_group_ids.get(&id)) {
+            Some(id) => Some(*id),
+            // group_id の無い古い形式のファイルは名前でマッチ
+            None => tx
+                .query_row(
+                    "SELECT id FROM groups WHERE name = ?",
+                    rusqlite::params![membership.group_name],
+                    |row| row.get(0),
+                )
+                .ok(),
+        };
+        let group_id = group_id.filter(|id| {
+            tx.query_row(
+                "SELECT smart_query IS NULL FROM groups WHERE id = ?",
+                rusqlite::params![id],
+                |row| row.get::<_, bool>(0),
             )
-            .ok();
+            .unwrap_or(false)
+        });
 
         let image_id: Option<i64> = tx
             .query_row(
@@ -1623,21 +6050,103 @@ pub fn import_metadata_json(input_path: String) -> Result<String, String> {
             .ok();
 
         if let (Some(gid), Some(iid)) = (group_id, image_id) {
-            tx.execute(
-                "INSERT OR IGNORE INTO image_groups (image_id, group_id) VALUES (?, ?)",
-                rusqlite::params![iid, gid],
-            )
+            // 並び順があればファイルの順に揃え、無ければ末尾に追加
+            match membership.position {
+                Some(position) => tx.execute(
+                    "INSERT INTO image_groups (image_id, group_id, position) VALUES (?, ?, ?)
+                    ON CONFLICT(image_id, group_id) DO UPDATE SET position = excluded.position",
+                    rusqlite::params![iid, gid, position],
+                ),
+                None => tx.execute(
+                    "INSERT OR IGNORE INTO image_groups (image_id, group_id, position)
+                    SELECT ?1, ?2, COALESCE(MAX(position) + 1, 0) FROM image_groups WHERE group_id = ?2",
+                    rusqlite::params![iid, gid],
+                ),
+            }
             .map_err(|e| format!("Failed to add membership: {}", e))?;
+            if let Some(position) = membership.position {
+                imported_orders.entry(gid).or_default().push((position, iid));
+            }
+
+            // キャプションがあれば上書き（無い場合は既存のキャプションを残す）
+            if let Some(caption) = membership.caption.as_deref().map(validate_caption).transpose()?.flatten() {
+                tx.execute(
+                    "UPDATE image_groups SET caption = ? WHERE image_id = ? AND group_id = ?",
+                    rusqlite::params![caption, iid, gid],
+                )
+                .map_err(|e| format!("Failed to set caption: {}", e))?;
+            }
             added_memberships += 1;
         }
     }
 
+    // 取り込んだ位置が既存の画像の位置と重ならないよう、ファイルの順を先頭に、
+    // 残りの画像を元の並び順のまま後ろに続けて振り直す
+    for (group_id, mut imported) in imported_orders {
+        imported.sort_by_key(|(position, _)| *position);
+        let mut seen = HashSet::new();
+        let mut ordered_ids: Vec<i64> = imported.into_iter().map(|(_, id)| id).filter(|id| seen.insert(*id)).collect();
+        ordered_ids.extend(
+            group_direct_image_ids(&tx, group_id, None)?
+                .into_iter()
+                .filter(|id| !seen.contains(id)),
+        );
+        write_group_image_order(&tx, group_id, &ordered_ids)?;
+    }
+
+    // グループコメントの復元（同じグループ・本文・投稿日時のコメントは取り込み済みとみなす）
+    // ファイル内の返信先は id 順で先に現れるため、取り込み済みの id に付け替えられる
+    let mut imported_comment_ids: HashMap<i64, i64> = HashMap::new();
+    for group_comment in &export_data.group_comments {
+        let group_id = match imported_group_ids.get(&group_comment.group_id) {
+            Some(id) => *id,
+            None => continue,
+        };
+        let comment = validate_comment(&group_comment.comment)?;
+        let author = group_comment.author.as_deref().map(validate_comment_author).transpose()?.flatten();
+        let parent_id = group_comment.parent_comment_id.and_then(|p| imported_comment_ids.get(&p).copied());
+
+        let existing_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM group_comments WHERE group_id = ? AND comment = ? AND created_at = ?",
+                rusqlite::params![group_id, comment, group_comment.created_at],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let comment_id = match existing_id {
+            Some(id) => id,
+            None => {
+                tx.execute(
+                    "INSERT INTO group_comments (group_id, comment, author, parent_comment_id, created_at, updated_at)
+                    VALUES (?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![group_id, comment, author, parent_id, group_comment.created_at, group_comment.updated_at],
+                )
+                .map_err(|e| format!("Failed to add group comment: {}", e))?;
+                let comment_id = tx.last_insert_rowid();
+
+                for edit in &group_comment.edits {
+                    tx.execute(
+                        "INSERT INTO group_comment_edits (comment_id, comment, edited_at) VALUES (?, ?, ?)",
+                        rusqlite::params![comment_id, edit.comment, edit.edited_at],
+                    )
+                    .map_err(|e| format!("Failed to add comment history: {}", e))?;
+                }
+                added_comments += 1;
+                comment_id
+            }
+        };
+        imported_comment_ids.insert(group_comment.id, comment_id);
+    }
+
     tx.commit()
         .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
+    write_enabled_xmp_sidecars(&conn, &updated_image_ids);
+
     let summary = format!(
-        "Import complete: {} images updated, {} groups created, {} memberships added",
-        updated_images, created_groups, added_memberships
+        "Import complete: {} images updated, {} groups created, {} memberships added, {} comments added",
+        updated_images, created_groups, added_memberships, added_comments
     );
     println!("{}", summary);
 
@@ -1671,3 +6180,306 @@ pub fn stop_file_watcher(app: tauri::AppHandle) -> Result<(), String> {
 pub fn get_watched_directories(app: tauri::AppHandle) -> Result<Vec<String>, String> {
     crate::watcher::get_watched_paths(&app)
 }
+
+// ============================================================
+// バックグラウンドジョブ（スキャン・サムネイル生成）
+// ============================================================
+
+/// スキャンジョブの本体。ディレクトリごとにファイル情報をワーカープールで作成し、DBに同期します
+fn run_scan_job(
+    ctx: &crate::jobs::JobContext,
+    directory_id: Option<i64>,
+) -> Result<Vec<SyncSummary>, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let mut conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let dirs: Vec<(i64, String)> = {
+        let (query, params): (&str, Vec<i64>) = match directory_id {
+            Some(id) => ("SELECT id, path FROM directories WHERE id = ?", vec![id]),
+            None => ("SELECT id, path FROM directories WHERE is_active = 1", vec![]),
+        };
+        let mut stmt = conn
+            .prepare(query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query directories: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect directories: {}", e))?;
+        rows
+    };
+    if let (Some(id), true) = (directory_id, dirs.is_empty()) {
+        return Err(format!("Directory not found: {}", id));
+    }
+
+    // 総数を先に確定させるため、全ディレクトリのファイル一覧を先に取得
+    let mut plans = Vec::new();
+    for (dir_id, dir_path) in dirs {
+        if !Path::new(&dir_path).is_dir() {
+            if directory_id.is_some() {
+                return Err(format!("Directory does not exist: {}", dir_path));
+            }
+            eprintln!("Warning: Skipping unavailable directory: {}", dir_path);
+            continue;
+        }
+        let files = crate::fs_utils::scan_images_in_directory(&dir_path)?;
+        let baseline = ScanBaseline::load(&conn, &dir_path);
+        plans.push((dir_id, dir_path, files, baseline));
+    }
+    ctx.set_total(plans.iter().map(|(_, _, files, _)| files.len() as u64).sum());
+
+    let mut summaries = Vec::new();
+    for (dir_id, dir_path, files, baseline) in plans {
+        let file_infos = ctx.run_parallel(files, crate::video_utils::metadata_worker_count(), |file| {
+            let path = file.path.clone();
+            let (info, _) = build_file_info(file, &baseline);
+            ctx.advance(&path);
+            info
+        });
+
+        // キャンセル時は同期済みのディレクトリまでで終了
+        let Some(file_infos) = file_infos else {
+            break;
+        };
+        summaries.push(sync_file_infos(&mut conn, dir_id, &dir_path, &file_infos)?);
+    }
+
+    Ok(summaries)
+}
+
+/**
+ * ディレクトリのスキャン・同期をバックグラウンドジョブとして開始します
+ * 進捗は job-progress イベントで通知され、完了時の result は SyncSummary の配列です
+ * キャンセル時は同期済みのディレクトリの SyncSummary が result に残ります
+ *
+ * @param directory_id 対象ディレクトリ（省略時は全アクティブディレクトリ）
+ * @return ジョブID
+ */
+#[tauri::command]
+pub fn start_scan_job(app: tauri::AppHandle, directory_id: Option<i64>) -> Result<u64, String> {
+    crate::jobs::enqueue_job(&app, "scan", move |ctx| {
+        let summaries = run_scan_job(ctx, directory_id)?;
+        serde_json::to_value(summaries).map_err(|e| format!("Failed to serialize result: {}", e))
+    })
+}
+
+/**
+ * サムネイルの一括生成をバックグラウンドジョブとして開始します
+ * 完了時の result は { generated, failed } です（キャンセル時は中断までの件数）
+ *
+ * @param image_ids 対象画像（省略時は欠損していない全画像）
+ * @param size サムネイルのサイズ（省略時は medium）
+ * @return ジョブID
+ */
+#[tauri::command]
+pub fn start_thumbnail_job(
+    app: tauri::AppHandle,
+    image_ids: Option<Vec<i64>>,
+    size: Option<crate::thumbnail_utils::ThumbnailSize>,
+) -> Result<u64, String> {
+    use rusqlite::Connection;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let size = size.unwrap_or(crate::thumbnail_utils::ThumbnailSize::Medium);
+    let image_ids: Option<HashSet<i64>> = image_ids.map(|ids| ids.into_iter().collect());
+
+    crate::jobs::enqueue_job(&app, "thumbnails", move |ctx| {
+        let db_path = crate::db::get_db_path()?;
+        let conn = Connection::open(&db_path)
+            .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+        let targets: Vec<(i64, String, String)> = {
+            let mut stmt = conn
+                .prepare("SELECT id, file_path, COALESCE(file_type, 'image') FROM images WHERE COALESCE(is_missing, 0) = 0 ORDER BY id")
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .map_err(|e| format!("Failed to query images: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to collect images: {}", e))?;
+            rows
+        };
+        let targets: Vec<_> = match &image_ids {
+            Some(ids) => targets.into_iter().filter(|(id, _, _)| ids.contains(id)).collect(),
+            None => targets,
+        };
+        ctx.set_total(targets.len() as u64);
+
+        // キャンセル時も中断までに処理した件数を返すため、結果は逐次数える
+        let generated = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        ctx.run_parallel(targets, crate::jobs::worker_count(), |(id, file_path, file_type)| {
+            match crate::thumbnail_utils::ensure_thumbnail(&file_path, &file_type, id, size) {
+                Ok(_) => generated.fetch_add(1, Ordering::Relaxed),
+                Err(e) => {
+                    eprintln!("Warning: Failed to generate thumbnail for image {}: {}", id, e);
+                    failed.fetch_add(1, Ordering::Relaxed)
+                }
+            };
+            ctx.advance(&file_path);
+        });
+
+        Ok(serde_json::json!({
+            "generated": generated.into_inner(),
+            "failed": failed.into_inner(),
+        }))
+    })
+}
+
+/**
+ * ジョブのキャンセルを要求します
+ *
+ * @return キャンセルを要求できた場合 true（既に終了していた場合 false）
+ */
+#[tauri::command]
+pub fn cancel_job(app: tauri::AppHandle, job_id: u64) -> Result<bool, String> {
+    crate::jobs::cancel_job(&app, job_id)
+}
+
+/**
+ * 実行中・待機中・最近終了したジョブの一覧を取得します
+ */
+#[tauri::command]
+pub fn list_jobs(app: tauri::AppHandle) -> Result<Vec<crate::jobs::JobInfo>, String> {
+    crate::jobs::list_jobs(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn folded(terms: &[&str]) -> Vec<Vec<char>> {
+        terms.iter().map(|t| fold_chars(t)).collect()
+    }
+
+    /// trigram の FTS5 テーブルに search_condition の条件で問い合わせ、一致した rowid を返す
+    fn search_rowids(conn: &rusqlite::Connection, terms: &[&str]) -> Vec<i64> {
+        let (condition, params, _) = search_condition("images_fts", &["file_name", "comment"], &strings(terms));
+        let mut stmt = conn
+            .prepare(&format!("SELECT rowid FROM images_fts WHERE {} ORDER BY rowid", condition))
+            .unwrap();
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<i64>, _>>()
+            .unwrap();
+        rows
+    }
+
+    #[test]
+    fn parse_search_terms_removes_case_insensitive_duplicates() {
+        assert_eq!(parse_search_terms("  Sunset 夕日 sunset  旅 夕日 "), strings(&["Sunset", "夕日", "旅"]));
+        assert!(parse_search_terms("   ").is_empty());
+    }
+
+    #[test]
+    fn search_condition_matches_long_terms_and_likes_short_ones() {
+        let (condition, params, uses_match) =
+            search_condition("images_fts", &["file_name", "comment"], &strings(&["夕日の写真", "旅", "say\"hi"]));
+
+        assert!(uses_match);
+        assert_eq!(
+            condition,
+            "images_fts MATCH ? AND (images_fts.file_name LIKE ? ESCAPE '\\' OR images_fts.comment LIKE ? ESCAPE '\\')"
+        );
+        assert_eq!(params, strings(&["\"夕日の写真\" \"say\"\"hi\"", "%旅%", "%旅%"]));
+    }
+
+    #[test]
+    fn search_condition_without_long_terms_does_not_match() {
+        let (condition, params, uses_match) = search_condition("groups_fts", &["name"], &strings(&["京都", "5%"]));
+
+        assert!(!uses_match);
+        assert_eq!(
+            condition,
+            "(groups_fts.name LIKE ? ESCAPE '\\') AND (groups_fts.name LIKE ? ESCAPE '\\')"
+        );
+        assert_eq!(params, strings(&["%京都%", "%5\\%%"]));
+    }
+
+    #[test]
+    fn search_condition_finds_japanese_and_short_terms_in_trigram_index() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE images_fts USING fts5(file_name, comment, tokenize = 'trigram');
+            INSERT INTO images_fts (rowid, file_name, comment) VALUES
+                (1, '京都の夕日.jpg', '旅行の写真'),
+                (2, 'tokyo.jpg', '夜景'),
+                (3, 'IMG_0001.jpg', 'Summer 旅');",
+        )
+        .unwrap();
+
+        assert_eq!(search_rowids(&conn, &["旅行の"]), vec![1]);
+        assert_eq!(search_rowids(&conn, &["夕日"]), vec![1]);
+        assert_eq!(search_rowids(&conn, &["旅"]), vec![1, 3]);
+        assert_eq!(search_rowids(&conn, &["summer", "旅"]), vec![3]);
+        assert_eq!(search_rowids(&conn, &["TOKYO"]), vec![2]);
+        assert_eq!(search_rowids(&conn, &["_0"]), vec![3]);
+        assert!(search_rowids(&conn, &["夜景", "旅"]).is_empty());
+    }
+
+    #[test]
+    fn find_term_ranges_merges_overlaps_and_counts_matched_terms() {
+        let text = fold_chars("Sunset sunSET");
+        assert_eq!(
+            find_term_ranges(&text, &folded(&["sun", "set", "missing"])),
+            (vec![(0, 6), (7, 13)], 2)
+        );
+
+        assert_eq!(find_term_ranges(&fold_chars("夕日と夕焼け"), &folded(&["夕"])), (vec![(0, 1), (3, 4)], 1));
+        assert_eq!(find_term_ranges(&fold_chars("aaaa"), &folded(&["aa"])), (vec![(0, 4)], 1));
+        assert_eq!(find_term_ranges(&fold_chars("旅"), &folded(&["", "旅行"])), (vec![], 0));
+    }
+
+    #[test]
+    fn highlight_snippet_marks_matches_with_context() {
+        assert_eq!(
+            highlight_snippet("京都の夕日", &folded(&["夕日"])),
+            Some((1, "京都の<mark>夕日</mark>".to_string()))
+        );
+
+        let text = format!("{}Tokyo{}", "あ".repeat(40), "い".repeat(40));
+        assert_eq!(
+            highlight_snippet(&text, &folded(&["tokyo"])),
+            Some((1, format!("…{}<mark>Tokyo</mark>{}…", "あ".repeat(30), "い".repeat(30))))
+        );
+
+        assert_eq!(highlight_snippet("夜景", &folded(&["夕日"])), None);
+    }
+
+    #[test]
+    fn best_snippet_prefers_the_field_matching_most_terms() {
+        let fields = [
+            ("file_name", Some("sunset.jpg")),
+            ("comment", Some("Sunset over 京都")),
+            ("tags", None),
+        ];
+
+        assert_eq!(
+            best_snippet(&fields, &strings(&["sunset", "京都"])),
+            ("comment".to_string(), "<mark>Sunset</mark> over <mark>京都</mark>".to_string())
+        );
+        assert_eq!(
+            best_snippet(&fields, &strings(&["SUNSET"])),
+            ("file_name".to_string(), "<mark>sunset</mark>.jpg".to_string())
+        );
+    }
+
+    #[test]
+    fn best_snippet_falls_back_to_the_first_non_empty_field() {
+        let comment = "写".repeat(70);
+        let fields = [("file_name", Some("")), ("comment", Some(comment.as_str()))];
+
+        assert_eq!(best_snippet(&fields, &strings(&["夕日"])), ("comment".to_string(), "写".repeat(60)));
+        assert_eq!(best_snippet(&[("tags", None)], &strings(&["夕日"])), (String::new(), String::new()));
+    }
+}
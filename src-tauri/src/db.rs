@@ -41,6 +41,90 @@ pub fn get_db_path() -> Result<PathBuf, String> {
     Ok(new_db_path)
 }
 
+/// 全文検索用の FTS5 テーブル（trigram トークナイザ）と同期用のトリガー
+/// マイグレーション（get_migrations）と init_db の両方から使うため、文字列リテラルとして定義する
+macro_rules! search_index_sql {
+    () => {
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS images_fts USING fts5(file_name, comment, tags, tokenize = 'trigram');
+        CREATE VIRTUAL TABLE IF NOT EXISTS groups_fts USING fts5(name, description, tokenize = 'trigram');
+        CREATE VIRTUAL TABLE IF NOT EXISTS group_comments_fts USING fts5(comment, tokenize = 'trigram');
+
+        -- 画像（rowid = images.id）
+        CREATE TRIGGER IF NOT EXISTS images_fts_insert AFTER INSERT ON images BEGIN
+            INSERT INTO images_fts (rowid, file_name, comment, tags) VALUES (new.id, new.file_name, new.comment, '');
+        END;
+        CREATE TRIGGER IF NOT EXISTS images_fts_update AFTER UPDATE OF file_name, comment ON images BEGIN
+            UPDATE images_fts SET file_name = new.file_name, comment = new.comment WHERE rowid = new.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS images_fts_delete AFTER DELETE ON images BEGIN
+            DELETE FROM images_fts WHERE rowid = old.id;
+        END;
+
+        -- タグの付け外し・名前変更で画像のタグ列を作り直す
+        CREATE TRIGGER IF NOT EXISTS image_tags_fts_insert AFTER INSERT ON image_tags BEGIN
+            UPDATE images_fts SET tags = (
+                SELECT group_concat(t.name, ' ') FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = new.image_id
+            ) WHERE rowid = new.image_id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS image_tags_fts_update AFTER UPDATE ON image_tags BEGIN
+            UPDATE images_fts SET tags = (
+                SELECT group_concat(t.name, ' ') FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = new.image_id
+            ) WHERE rowid = new.image_id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS image_tags_fts_delete AFTER DELETE ON image_tags BEGIN
+            UPDATE images_fts SET tags = (
+                SELECT group_concat(t.name, ' ') FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = old.image_id
+            ) WHERE rowid = old.image_id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS tags_fts_rename AFTER UPDATE OF name ON tags BEGIN
+            UPDATE images_fts SET tags = (
+                SELECT group_concat(t.name, ' ') FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = images_fts.rowid
+            ) WHERE rowid IN (SELECT image_id FROM image_tags WHERE tag_id = new.id);
+        END;
+
+        -- グループ（rowid = groups.id）
+        CREATE TRIGGER IF NOT EXISTS groups_fts_insert AFTER INSERT ON groups BEGIN
+            INSERT INTO groups_fts (rowid, name, description) VALUES (new.id, new.name, new.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS groups_fts_update AFTER UPDATE OF name, description ON groups BEGIN
+            UPDATE groups_fts SET name = new.name, description = new.description WHERE rowid = new.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS groups_fts_delete AFTER DELETE ON groups BEGIN
+            DELETE FROM groups_fts WHERE rowid = old.id;
+        END;
+
+        -- グループコメント（rowid = group_comments.id）
+        CREATE TRIGGER IF NOT EXISTS group_comments_fts_insert AFTER INSERT ON group_comments BEGIN
+            INSERT INTO group_comments_fts (rowid, comment) VALUES (new.id, new.comment);
+        END;
+        CREATE TRIGGER IF NOT EXISTS group_comments_fts_update AFTER UPDATE OF comment ON group_comments BEGIN
+            UPDATE group_comments_fts SET comment = new.comment WHERE rowid = new.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS group_comments_fts_delete AFTER DELETE ON group_comments BEGIN
+            DELETE FROM group_comments_fts WHERE rowid = old.id;
+        END;
+        "
+    };
+}
+
+/// 既存のデータを全文検索の索引に登録する
+macro_rules! search_index_backfill_sql {
+    () => {
+        "
+        INSERT INTO images_fts (rowid, file_name, comment, tags)
+        SELECT id, file_name, comment, (
+            SELECT group_concat(t.name, ' ') FROM image_tags it JOIN tags t ON t.id = it.tag_id WHERE it.image_id = images.id
+        ) FROM images;
+        INSERT INTO groups_fts (rowid, name, description) SELECT id, name, description FROM groups;
+        INSERT INTO group_comments_fts (rowid, comment) SELECT id, comment FROM group_comments;
+        "
+    };
+}
+
+const SEARCH_INDEX_SQL: &str = search_index_sql!();
+const SEARCH_INDEX_BACKFILL_SQL: &str = search_index_backfill_sql!();
+
 pub fn get_migrations() -> Vec<Migration> {
     vec![
         Migration {
@@ -182,10 +266,335 @@ pub fn get_migrations() -> Vec<Migration> {
                 CREATE INDEX IF NOT EXISTS idx_action_log_is_undone ON action_log(is_undone);
             ",
             kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add_file_size_and_mtime_columns",
+            sql: "
+                -- 差分スキャン用（前回スキャン時のファイルサイズ・更新日時）
+                ALTER TABLE images ADD COLUMN file_size INTEGER;
+                ALTER TABLE images ADD COLUMN file_mtime INTEGER;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add_missing_file_tracking",
+            sql: "
+                -- 欠損ファイル検出・移動ファイル再リンク用
+                ALTER TABLE images ADD COLUMN is_missing INTEGER DEFAULT 0;
+                ALTER TABLE images ADD COLUMN missing_since TEXT;
+                CREATE INDEX IF NOT EXISTS idx_images_is_missing ON images(is_missing);
+                CREATE INDEX IF NOT EXISTS idx_images_size_mtime ON images(file_size, file_mtime);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add_content_hash_column",
+            sql: "
+                -- 重複検出用のファイル内容ハッシュ
+                ALTER TABLE images ADD COLUMN content_hash TEXT;
+                CREATE INDEX IF NOT EXISTS idx_images_content_hash ON images(content_hash);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add_perceptual_hash_column",
+            sql: "
+                -- 類似画像検出用の知覚ハッシュ（dHash、16進文字列）
+                ALTER TABLE images ADD COLUMN perceptual_hash TEXT;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add_exif_columns",
+            sql: "
+                -- 静止画のEXIF/XMPメタデータ
+                ALTER TABLE images ADD COLUMN captured_at TEXT;
+                ALTER TABLE images ADD COLUMN camera_make TEXT;
+                ALTER TABLE images ADD COLUMN camera_model TEXT;
+                ALTER TABLE images ADD COLUMN lens_model TEXT;
+                ALTER TABLE images ADD COLUMN iso INTEGER;
+                ALTER TABLE images ADD COLUMN exposure_time REAL;
+                ALTER TABLE images ADD COLUMN f_number REAL;
+                ALTER TABLE images ADD COLUMN orientation INTEGER;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_taken_at_columns",
+            sql: "
+                -- 撮影日時（EXIF / 動画のcreation_time / ファイル更新日時）と取得元
+                ALTER TABLE images ADD COLUMN taken_at TEXT;
+                ALTER TABLE images ADD COLUMN taken_at_source TEXT;
+                CREATE INDEX IF NOT EXISTS idx_images_taken_at ON images(taken_at);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add_location_columns",
+            sql: "
+                -- 撮影位置（GPS）とメタデータ抽出の版（版が古い行は次回スキャンで再抽出）
+                ALTER TABLE images ADD COLUMN latitude REAL;
+                ALTER TABLE images ADD COLUMN longitude REAL;
+                ALTER TABLE images ADD COLUMN altitude REAL;
+                ALTER TABLE images ADD COLUMN metadata_version INTEGER;
+                CREATE INDEX IF NOT EXISTS idx_images_location ON images(latitude, longitude);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add_place_columns",
+            sql: "
+                -- 撮影位置の地名（同梱の地名辞書による逆ジオコーディング）
+                ALTER TABLE images ADD COLUMN country_code TEXT;
+                ALTER TABLE images ADD COLUMN country TEXT;
+                ALTER TABLE images ADD COLUMN region TEXT;
+                ALTER TABLE images ADD COLUMN city TEXT;
+                CREATE INDEX IF NOT EXISTS idx_images_place ON images(country, region, city);
+                CREATE INDEX IF NOT EXISTS idx_images_city ON images(city);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "add_xmp_sidecar_columns",
+            sql: "
+                -- XMPサイドカーの書き出し設定（ディレクトリ単位、既定は無効）
+                ALTER TABLE directories ADD COLUMN write_xmp_sidecars INTEGER DEFAULT 0;
+                -- 最後に取り込み・書き出しをしたサイドカーの更新日時
+                ALTER TABLE images ADD COLUMN xmp_sidecar_mtime INTEGER;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "normalize_tags",
+            sql: "
+                CREATE TABLE IF NOT EXISTS tags (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL UNIQUE,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS image_tags (
+                    image_id INTEGER NOT NULL,
+                    tag_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (image_id, tag_id),
+                    FOREIGN KEY (image_id) REFERENCES images(id) ON DELETE CASCADE,
+                    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_image_tags_tag ON image_tags(tag_id);
+
+                -- images.tags（JSON配列）から移行し、列を削除
+                INSERT OR IGNORE INTO tags (name)
+                SELECT DISTINCT trim(j.value) FROM images i, json_each(i.tags) j
+                WHERE json_valid(i.tags) AND j.type = 'text' AND trim(j.value) <> '';
+
+                INSERT OR IGNORE INTO image_tags (image_id, tag_id, position)
+                SELECT i.id, t.id, j.key FROM images i, json_each(i.tags) j
+                JOIN tags t ON t.name = trim(j.value)
+                WHERE json_valid(i.tags) AND j.type = 'text';
+
+                ALTER TABLE images DROP COLUMN tags;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "add_tag_hierarchy",
+            sql: "
+                ALTER TABLE tags ADD COLUMN parent_id INTEGER REFERENCES tags(id) ON DELETE CASCADE;
+                CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent_id);
+
+                -- 既存の「親/子」形式のタグに親タグを作成し、parent_id でつなぐ
+                WITH RECURSIVE ancestors(path) AS (
+                    SELECT substr(name, 1, length(rtrim(name, replace(name, '/', ''))) - 1) FROM tags WHERE instr(name, '/') > 0
+                    UNION
+                    SELECT substr(path, 1, length(rtrim(path, replace(path, '/', ''))) - 1) FROM ancestors WHERE instr(path, '/') > 0
+                )
+                INSERT OR IGNORE INTO tags (name) SELECT path FROM ancestors;
+
+                UPDATE tags SET parent_id = (
+                    SELECT p.id FROM tags p
+                    WHERE p.name = substr(tags.name, 1, length(rtrim(tags.name, replace(tags.name, '/', ''))) - 1)
+                )
+                WHERE parent_id IS NULL AND instr(name, '/') > 0;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "add_search_index",
+            sql: concat!(search_index_sql!(), search_index_backfill_sql!()),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "add_smart_groups",
+            sql: "
+                -- スマートグループの検索クエリ（NULL は手動で画像を追加するグループ）
+                ALTER TABLE groups ADD COLUMN smart_query TEXT;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "add_group_hierarchy",
+            sql: "
+                ALTER TABLE groups ADD COLUMN parent_id INTEGER REFERENCES groups(id) ON DELETE SET NULL;
+                CREATE INDEX IF NOT EXISTS idx_groups_parent ON groups(parent_id);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "add_group_image_position",
+            sql: "
+                ALTER TABLE image_groups ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+                CREATE INDEX IF NOT EXISTS idx_image_groups_group_position ON image_groups(group_id, position);
+
+                -- 既存の所属は追加した順に並べる
+                UPDATE image_groups SET position = (
+                    SELECT COUNT(*) FROM image_groups p
+                    WHERE p.group_id = image_groups.group_id AND p.id < image_groups.id
+                );
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "add_group_sort_order",
+            sql: "
+                ALTER TABLE groups ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+                CREATE INDEX IF NOT EXISTS idx_groups_sort_order ON groups(sort_order);
+
+                -- 既存のグループは作成日時の新しい順（これまでの表示順）に並べる
+                UPDATE groups SET sort_order = (
+                    SELECT COUNT(*) FROM groups g
+                    WHERE g.created_at > groups.created_at OR (g.created_at = groups.created_at AND g.id > groups.id)
+                );
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "add_group_image_caption",
+            sql: "
+                -- グループごとの画像のキャプション（同じ画像でもグループによって異なる）
+                ALTER TABLE image_groups ADD COLUMN caption TEXT;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "add_group_comment_threads_and_edits",
+            sql: "
+                ALTER TABLE group_comments ADD COLUMN updated_at TEXT;
+                ALTER TABLE group_comments ADD COLUMN author TEXT;
+                ALTER TABLE group_comments ADD COLUMN parent_comment_id INTEGER REFERENCES group_comments(id) ON DELETE CASCADE;
+                CREATE INDEX IF NOT EXISTS idx_group_comments_parent ON group_comments(parent_comment_id);
+
+                -- コメントの編集履歴（編集前の本文）
+                CREATE TABLE IF NOT EXISTS group_comment_edits (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    comment_id INTEGER NOT NULL,
+                    comment TEXT NOT NULL,
+                    edited_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (comment_id) REFERENCES group_comments(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_group_comment_edits_comment ON group_comment_edits(comment_id);
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "add_phash_failed_mtime",
+            sql: "
+                -- 知覚ハッシュの計算に失敗したときのファイル更新日時（変更がなければ再計算しない）
+                ALTER TABLE images ADD COLUMN phash_failed_mtime INTEGER;
+            ",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "add_metadata_failed_mtime",
+            sql: "
+                -- メタデータ（動画の ffprobe・静止画のサイズ）の抽出に失敗したときのファイル更新日時（変更がなければ再抽出しない）
+                ALTER TABLE images ADD COLUMN metadata_failed_mtime INTEGER;
+            ",
+            kind: MigrationKind::Up,
         }
     ]
 }
 
+/// images.tags（JSON配列）のタグを tags / image_tags に移し、列を削除する
+/// 列が残っている間だけ実行し、移行と列の削除は1トランザクションで行う
+fn migrate_legacy_tags(conn: &rusqlite::Connection) -> Result<(), String> {
+    let has_legacy_column = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('images') WHERE name = 'tags'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|e| format!("Failed to inspect images table: {}", e))?
+        > 0;
+    if !has_legacy_column {
+        return Ok(());
+    }
+
+    conn.execute_batch("
+        BEGIN;
+        INSERT OR IGNORE INTO tags (name)
+        SELECT DISTINCT trim(j.value) FROM images i, json_each(i.tags) j
+        WHERE json_valid(i.tags) AND j.type = 'text' AND trim(j.value) <> '';
+
+        INSERT OR IGNORE INTO image_tags (image_id, tag_id, position)
+        SELECT i.id, t.id, j.key FROM images i, json_each(i.tags) j
+        JOIN tags t ON t.name = trim(j.value)
+        WHERE json_valid(i.tags) AND j.type = 'text';
+
+        ALTER TABLE images DROP COLUMN tags;
+        COMMIT;
+    ").map_err(|e| {
+        let _ = conn.execute_batch("ROLLBACK");
+        format!("Failed to migrate tags: {}", e)
+    })
+}
+
+/// 全文検索用の FTS5 テーブル（trigram トークナイザ）と、同期用のトリガーを作成する
+/// テーブルを新しく作ったときだけ既存のデータを登録する
+fn create_search_index(conn: &rusqlite::Connection) -> Result<(), String> {
+    let exists = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'images_fts'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|e| format!("Failed to inspect search index: {}", e))?
+        > 0;
+
+    conn.execute_batch(&format!("BEGIN;{}COMMIT;", SEARCH_INDEX_SQL)).map_err(|e| {
+        let _ = conn.execute_batch("ROLLBACK");
+        format!("Failed to create search index: {}", e)
+    })?;
+    if exists {
+        return Ok(());
+    }
+
+    conn.execute_batch(&format!("BEGIN;{}COMMIT;", SEARCH_INDEX_BACKFILL_SQL)).map_err(|e| {
+        let _ = conn.execute_batch("ROLLBACK");
+        format!("Failed to build search index: {}", e)
+    })
+}
+
 pub async fn init_db() -> Result<(), String> {
     use rusqlite::Connection;
 
@@ -299,6 +708,158 @@ pub async fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE images ADD COLUMN directory_id INTEGER REFERENCES directories(id) ON DELETE SET NULL", []);
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_directory_id ON images(directory_id)", []);
 
+    // Migration 8: 差分スキャン用のファイルサイズ・更新日時（UNIX秒）
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN file_size INTEGER", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN file_mtime INTEGER", []);
+
+    // Migration 9: 欠損ファイル検出・移動ファイル再リンク用
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN is_missing INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN missing_since TEXT", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_is_missing ON images(is_missing)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_size_mtime ON images(file_size, file_mtime)", []);
+
+    // Migration 10: 重複検出用のファイル内容ハッシュ
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN content_hash TEXT", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_content_hash ON images(content_hash)", []);
+
+    // Migration 11: 類似画像検出用の知覚ハッシュ
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN perceptual_hash TEXT", []);
+
+    // Migration 12: 静止画のEXIF/XMPメタデータ
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN captured_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN camera_make TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN camera_model TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN lens_model TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN iso INTEGER", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN exposure_time REAL", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN f_number REAL", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN orientation INTEGER", []);
+
+    // Migration 13: 撮影日時と取得元
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN taken_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN taken_at_source TEXT", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_taken_at ON images(taken_at)", []);
+
+    // Migration 14: 撮影位置とメタデータ抽出の版
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN latitude REAL", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN longitude REAL", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN altitude REAL", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN metadata_version INTEGER", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_location ON images(latitude, longitude)", []);
+
+    // Migration 15: 撮影位置の地名
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN country_code TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN country TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN region TEXT", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN city TEXT", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_place ON images(country, region, city)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_images_city ON images(city)", []);
+
+    // Migration 16: XMPサイドカー
+    let _ = conn.execute("ALTER TABLE directories ADD COLUMN write_xmp_sidecars INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN xmp_sidecar_mtime INTEGER", []);
+
+    // Migration 17: タグの正規化（tags + image_tags）
+    conn.execute_batch("
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS image_tags (
+            image_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (image_id, tag_id),
+            FOREIGN KEY (image_id) REFERENCES images(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_image_tags_tag ON image_tags(tag_id);
+    ").map_err(|e| format!("Failed to create tags tables: {}", e))?;
+    migrate_legacy_tags(&conn)?;
+
+    // Migration 18: 階層タグ
+    let _ = conn.execute("ALTER TABLE tags ADD COLUMN parent_id INTEGER REFERENCES tags(id) ON DELETE CASCADE", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent_id)", []);
+    conn.execute_batch("
+        -- 既存の「親/子」形式のタグに親タグを作成し、parent_id でつなぐ
+        WITH RECURSIVE ancestors(path) AS (
+            SELECT substr(name, 1, length(rtrim(name, replace(name, '/', ''))) - 1) FROM tags WHERE instr(name, '/') > 0
+            UNION
+            SELECT substr(path, 1, length(rtrim(path, replace(path, '/', ''))) - 1) FROM ancestors WHERE instr(path, '/') > 0
+        )
+        INSERT OR IGNORE INTO tags (name) SELECT path FROM ancestors;
+
+        UPDATE tags SET parent_id = (
+            SELECT p.id FROM tags p
+            WHERE p.name = substr(tags.name, 1, length(rtrim(tags.name, replace(tags.name, '/', ''))) - 1)
+        )
+        WHERE parent_id IS NULL AND instr(name, '/') > 0;
+    ").map_err(|e| format!("Failed to link tag hierarchy: {}", e))?;
+
+    // Migration 19: 全文検索インデックス
+    create_search_index(&conn)?;
+
+    // Migration 20: スマートグループ
+    let _ = conn.execute("ALTER TABLE groups ADD COLUMN smart_query TEXT", []);
+
+    // Migration 21: グループの階層
+    let _ = conn.execute("ALTER TABLE groups ADD COLUMN parent_id INTEGER REFERENCES groups(id) ON DELETE SET NULL", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_groups_parent ON groups(parent_id)", []);
+
+    // Migration 22: グループ内の画像の並び順（列を追加したときだけ既存の所属を追加した順に並べる）
+    if conn.execute("ALTER TABLE image_groups ADD COLUMN position INTEGER NOT NULL DEFAULT 0", []).is_ok() {
+        conn.execute(
+            "UPDATE image_groups SET position = (
+                SELECT COUNT(*) FROM image_groups p
+                WHERE p.group_id = image_groups.group_id AND p.id < image_groups.id
+            )",
+            [],
+        )
+        .map_err(|e| format!("Failed to initialize group image positions: {}", e))?;
+    }
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_image_groups_group_position ON image_groups(group_id, position)", []);
+
+    // Migration 23: グループの並び順（列を追加したときだけ既存のグループを作成日時の新しい順に並べる）
+    if conn.execute("ALTER TABLE groups ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0", []).is_ok() {
+        conn.execute(
+            "UPDATE groups SET sort_order = (
+                SELECT COUNT(*) FROM groups g
+                WHERE g.created_at > groups.created_at OR (g.created_at = groups.created_at AND g.id > groups.id)
+            )",
+            [],
+        )
+        .map_err(|e| format!("Failed to initialize group sort order: {}", e))?;
+    }
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_groups_sort_order ON groups(sort_order)", []);
+
+    // Migration 24: グループ内の画像のキャプション
+    let _ = conn.execute("ALTER TABLE image_groups ADD COLUMN caption TEXT", []);
+
+    // Migration 25: グループコメントの編集・返信・投稿者
+    let _ = conn.execute("ALTER TABLE group_comments ADD COLUMN updated_at TEXT", []);
+    let _ = conn.execute("ALTER TABLE group_comments ADD COLUMN author TEXT", []);
+    let _ = conn.execute("ALTER TABLE group_comments ADD COLUMN parent_comment_id INTEGER REFERENCES group_comments(id) ON DELETE CASCADE", []);
+    conn.execute_batch("
+        CREATE INDEX IF NOT EXISTS idx_group_comments_parent ON group_comments(parent_comment_id);
+
+        CREATE TABLE IF NOT EXISTS group_comment_edits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            comment_id INTEGER NOT NULL,
+            comment TEXT NOT NULL,
+            edited_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (comment_id) REFERENCES group_comments(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_group_comment_edits_comment ON group_comment_edits(comment_id);
+    ").map_err(|e| format!("Failed to create group_comment_edits table: {}", e))?;
+
+    // Migration 26: 知覚ハッシュの計算に失敗したファイルの記録
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN phash_failed_mtime INTEGER", []);
+
+    // Migration 27: メタデータの抽出に失敗したファイルの記録
+    let _ = conn.execute("ALTER TABLE images ADD COLUMN metadata_failed_mtime INTEGER", []);
+
     println!("Database initialization completed");
     Ok(())
 }
@@ -0,0 +1,427 @@
+use crate::geo_utils::{dms_to_degrees, GeoLocation};
+use exif::{In, Reader, Tag, Value};
+use image::ImageReader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+
+/// XMPパケットを探す範囲（XMPは通常ファイル先頭付近に埋め込まれる）
+const XMP_SEARCH_BYTES: u64 = 4 * 1024 * 1024;
+
+/// 静止画から抽出したメタデータ
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImageMetadata {
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// 撮影日時（"YYYY-MM-DD HH:MM:SS"、カメラのローカル時刻）
+    pub captured_at: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub iso: Option<i64>,
+    /// シャッタースピード（秒）
+    pub exposure_time: Option<f64>,
+    /// 絞り値（F値）
+    pub f_number: Option<f64>,
+    /// EXIFの回転情報（1-8）
+    pub orientation: Option<i64>,
+    /// 撮影位置（GPS）
+    pub location: Option<GeoLocation>,
+}
+
+impl ImageMetadata {
+    /// 未取得の項目を other の値で補完
+    fn fill_from(&mut self, other: ImageMetadata) {
+        self.width = self.width.or(other.width);
+        self.height = self.height.or(other.height);
+        self.captured_at = self.captured_at.take().or(other.captured_at);
+        self.camera_make = self.camera_make.take().or(other.camera_make);
+        self.camera_model = self.camera_model.take().or(other.camera_model);
+        self.lens_model = self.lens_model.take().or(other.lens_model);
+        self.iso = self.iso.or(other.iso);
+        self.exposure_time = self.exposure_time.or(other.exposure_time);
+        self.f_number = self.f_number.or(other.f_number);
+        self.orientation = self.orientation.or(other.orientation);
+        self.location = self.location.or(other.location);
+    }
+}
+
+/// 静止画（JPG/PNG/GIF/WebP）のサイズ・EXIF・XMPを抽出
+/// EXIFの値を優先し、EXIFにない項目をXMPで補完する。読み取れない項目は None のまま
+pub fn extract_image_metadata(path: &str) -> ImageMetadata {
+    let mut metadata = read_exif(path).unwrap_or_default();
+    if let Some(xmp) = read_xmp(path) {
+        metadata.fill_from(xmp);
+    }
+
+    // 画像サイズはEXIFより実際の画像ヘッダーを優先
+    if let Some((width, height)) = read_dimensions(path) {
+        metadata.width = Some(width);
+        metadata.height = Some(height);
+    }
+
+    metadata
+}
+
+/// 画像ヘッダーから幅・高さを取得（全体はデコードしない）
+fn read_dimensions(path: &str) -> Option<(i32, i32)> {
+    let (width, height) = ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    Some((i32::try_from(width).ok()?, i32::try_from(height).ok()?))
+}
+
+/// EXIFを読み取る
+fn read_exif(path: &str) -> Option<ImageMetadata> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let field = |tag: Tag| exif.get_field(tag, In::PRIMARY).map(|f| &f.value);
+
+    let ascii = |tag: Tag| match field(tag) {
+        Some(Value::Ascii(values)) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    };
+
+    let rational = |tag: Tag| match field(tag) {
+        Some(Value::Rational(values)) => values
+            .first()
+            .filter(|r| r.denom != 0)
+            .map(|r| r.to_f64()),
+        _ => None,
+    };
+
+    let uint = |tag: Tag| field(tag).and_then(|v| v.get_uint(0)).map(i64::from);
+
+    let captured_at = [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| match field(tag) {
+            Some(Value::Ascii(values)) => values
+                .first()
+                .and_then(|v| exif::DateTime::from_ascii(v).ok())
+                .map(|dt| dt.to_string()),
+            _ => None,
+        });
+
+    Some(ImageMetadata {
+        width: uint(Tag::PixelXDimension).and_then(|v| i32::try_from(v).ok()),
+        height: uint(Tag::PixelYDimension).and_then(|v| i32::try_from(v).ok()),
+        captured_at,
+        camera_make: ascii(Tag::Make),
+        camera_model: ascii(Tag::Model),
+        lens_model: ascii(Tag::LensModel),
+        iso: uint(Tag::PhotographicSensitivity),
+        exposure_time: rational(Tag::ExposureTime),
+        f_number: rational(Tag::FNumber),
+        orientation: uint(Tag::Orientation).filter(|o| (1..=8).contains(o)),
+        location: read_gps(&exif),
+    })
+}
+
+/// EXIFのGPS IFDから撮影位置を読み取る
+fn read_gps(exif: &exif::Exif) -> Option<GeoLocation> {
+    let field = |tag: Tag| exif.get_field(tag, In::PRIMARY).map(|f| &f.value);
+
+    let reference = |tag: Tag| match field(tag) {
+        Some(Value::Ascii(values)) => values.first().map(|v| String::from_utf8_lossy(v).to_string()),
+        _ => None,
+    };
+
+    // 度・分・秒の3つの有理数
+    let coordinate = |tag: Tag, ref_tag: Tag| match field(tag) {
+        Some(Value::Rational(values)) if values.len() == 3 && values.iter().all(|r| r.denom != 0) => Some(dms_to_degrees(
+            values[0].to_f64(),
+            values[1].to_f64(),
+            values[2].to_f64(),
+            &reference(ref_tag).unwrap_or_default(),
+        )),
+        _ => None,
+    };
+
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef)?;
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef)?;
+
+    // GPSAltitudeRef: 0 = 海抜, 1 = 海面下
+    let altitude = match field(Tag::GPSAltitude) {
+        Some(Value::Rational(values)) => values.first().filter(|r| r.denom != 0).map(|r| r.to_f64()),
+        _ => None,
+    }
+    .map(|altitude| match field(Tag::GPSAltitudeRef).and_then(|v| v.get_uint(0)) {
+        Some(1) => -altitude,
+        _ => altitude,
+    });
+
+    GeoLocation::new(latitude, longitude, altitude)
+}
+
+/// ファイルに埋め込まれたXMPパケットを読み取る
+fn read_xmp(path: &str) -> Option<ImageMetadata> {
+    let mut buffer = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(XMP_SEARCH_BYTES)
+        .read_to_end(&mut buffer)
+        .ok()?;
+
+    let packet = find_xmp_packet(&buffer)?;
+    parse_xmp(packet)
+}
+
+/// バイト列から <x:xmpmeta>...</x:xmpmeta> を切り出す
+fn find_xmp_packet(buffer: &[u8]) -> Option<&str> {
+    const START: &[u8] = b"<x:xmpmeta";
+    const END: &[u8] = b"</x:xmpmeta>";
+
+    let start = buffer.windows(START.len()).position(|w| w == START)?;
+    let end = buffer[start..]
+        .windows(END.len())
+        .position(|w| w == END)
+        .map(|pos| start + pos + END.len())?;
+
+    std::str::from_utf8(&buffer[start..end]).ok()
+}
+
+/// XMP（RDF/XML）から撮影情報を取り出す
+/// プロパティは属性形式・要素形式（rdf:Seq/rdf:Alt を含む）のどちらでも記述されうるため、
+/// ローカル名をキーに最初に見つかった値を採用する
+pub fn parse_xmp(xml: &str) -> Option<ImageMetadata> {
+    let doc = roxmltree::Document::parse(xml).ok()?;
+
+    let mut values: HashMap<&str, String> = HashMap::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        for attr in node.attributes() {
+            values
+                .entry(attr.name())
+                .or_insert_with(|| attr.value().trim().to_string());
+        }
+
+        let text = node
+            .children()
+            .find(|c| c.is_text() && !c.text().unwrap_or("").trim().is_empty())
+            .and_then(|c| c.text())
+            .or_else(|| {
+                // rdf:Seq / rdf:Alt の最初の要素
+                node.descendants()
+                    .find(|d| d.is_element() && d.tag_name().name() == "li")
+                    .and_then(|li| li.text())
+            });
+        if let Some(text) = text.map(str::trim).filter(|t| !t.is_empty()) {
+            values
+                .entry(node.tag_name().name())
+                .or_insert_with(|| text.to_string());
+        }
+    }
+
+    let get = |keys: &[&str]| keys.iter().find_map(|k| values.get(k).cloned());
+
+    Some(ImageMetadata {
+        width: get(&["PixelXDimension", "ImageWidth"]).and_then(|v| v.parse().ok()),
+        height: get(&["PixelYDimension", "ImageLength"]).and_then(|v| v.parse().ok()),
+        captured_at: get(&["DateTimeOriginal", "CreateDate", "DateCreated"]).and_then(|v| normalize_xmp_date(&v)),
+        camera_make: get(&["Make"]),
+        camera_model: get(&["Model"]),
+        lens_model: get(&["LensModel", "Lens"]),
+        iso: get(&["ISOSpeedRatings", "PhotographicSensitivity"]).and_then(|v| v.parse().ok()),
+        exposure_time: get(&["ExposureTime"]).and_then(|v| parse_rational(&v)),
+        f_number: get(&["FNumber"]).and_then(|v| parse_rational(&v)),
+        orientation: get(&["Orientation"])
+            .and_then(|v| v.parse().ok())
+            .filter(|o| (1..=8).contains(o)),
+        location: parse_xmp_location(
+            get(&["GPSLatitude"]).as_deref(),
+            get(&["GPSLongitude"]).as_deref(),
+            get(&["GPSAltitude"]).as_deref(),
+            get(&["GPSAltitudeRef"]).as_deref(),
+        ),
+    })
+}
+
+/// XMPのGPS座標（"35,40.5N" や "35,40,30N" 形式）から撮影位置を求める
+fn parse_xmp_location(
+    latitude: Option<&str>,
+    longitude: Option<&str>,
+    altitude: Option<&str>,
+    altitude_ref: Option<&str>,
+) -> Option<GeoLocation> {
+    let latitude = parse_xmp_coordinate(latitude?)?;
+    let longitude = parse_xmp_coordinate(longitude?)?;
+    let altitude = altitude.and_then(parse_rational).map(|a| match altitude_ref {
+        Some("1") => -a,
+        _ => a,
+    });
+    GeoLocation::new(latitude, longitude, altitude)
+}
+
+/// "DDD,MM.mmk" / "DDD,MM,SSk"（k は N/S/E/W）を10進の度に変換
+fn parse_xmp_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let reference = value.chars().last().filter(|c| c.is_ascii_alphabetic())?;
+    let numbers = value[..value.len() - 1]
+        .split(',')
+        .map(|n| n.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let (degrees, minutes, seconds) = match numbers.as_slice() {
+        [d, m] => (*d, *m, 0.0),
+        [d, m, s] => (*d, *m, *s),
+        _ => return None,
+    };
+    Some(dms_to_degrees(degrees, minutes, seconds, &reference.to_string()))
+}
+
+/// XMPの日時（ISO 8601）を "YYYY-MM-DD HH:MM:SS" に変換（タイムゾーンは無視）
+/// 時刻は "hh:mm" / "hh:mm:ss" / "hh:mm:ss.fff" のいずれでもよく、省略時は 00:00:00 とする
+fn normalize_xmp_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let date = chrono::NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+
+    let rest = value.get(10..)?;
+    let time = if rest.is_empty() {
+        chrono::NaiveTime::MIN
+    } else {
+        let time = rest.strip_prefix(['T', ' '])?;
+        // タイムゾーン指定（"Z" / "+hh:mm" / "-hh:mm"）を除く
+        let time = time.trim_end_matches('Z');
+        let time = time.rfind(['+', '-']).map_or(time, |i| &time[..i]);
+        chrono::NaiveTime::parse_from_str(time, "%H:%M:%S%.f")
+            .or_else(|_| chrono::NaiveTime::parse_from_str(time, "%H:%M"))
+            .ok()?
+    };
+
+    Some(date.and_time(time).format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// "1/250" や "28/10" 形式の有理数を小数に変換
+fn parse_rational(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((num, denom)) => {
+            let num: f64 = num.trim().parse().ok()?;
+            let denom: f64 = denom.trim().parse().ok()?;
+            (denom != 0.0).then(|| num / denom)
+        }
+        None => value.trim().parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value should be parsed");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn normalize_xmp_date_accepts_iso8601_variants() {
+        let cases = [
+            ("2024-05-03T18:00:15", "2024-05-03 18:00:15"),
+            ("2024-05-03T18:00:15.123", "2024-05-03 18:00:15"),
+            ("2024-05-03T18:00:15+09:00", "2024-05-03 18:00:15"),
+            ("2024-05-03T18:00:15.5-05:00", "2024-05-03 18:00:15"),
+            ("2024-05-03T18:00Z", "2024-05-03 18:00:00"),
+            ("2024-05-03T18:00+09:00", "2024-05-03 18:00:00"),
+            ("2024-05-03T18:00", "2024-05-03 18:00:00"),
+            ("2024-05-03 18:00:15", "2024-05-03 18:00:15"),
+            ("2024-05-03", "2024-05-03 00:00:00"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_xmp_date(input).as_deref(), Some(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn normalize_xmp_date_rejects_invalid_values() {
+        for input in ["", "2024", "2024-05", "2024-13-01", "2024-05-03T25:00", "2024-05-03T18", "2024-05-03X18:00"] {
+            assert_eq!(normalize_xmp_date(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_rational_handles_fractions_and_decimals() {
+        assert_close(parse_rational("1/250"), 0.004);
+        assert_close(parse_rational("28/10"), 2.8);
+        assert_close(parse_rational(" 5.6 "), 5.6);
+        assert_eq!(parse_rational("1/0"), None);
+        assert_eq!(parse_rational("abc"), None);
+    }
+
+    #[test]
+    fn parse_xmp_coordinate_converts_to_degrees() {
+        assert_close(parse_xmp_coordinate("35,30.0N"), 35.5);
+        assert_close(parse_xmp_coordinate("139,45,36E"), 139.76);
+        assert_close(parse_xmp_coordinate("33,52.2S"), -33.87);
+        assert_eq!(parse_xmp_coordinate("35,30.0"), None);
+        assert_eq!(parse_xmp_coordinate("35N"), None);
+    }
+
+    #[test]
+    fn parse_xmp_reads_attribute_and_element_properties() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description
+        xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+        xmlns:exif="http://ns.adobe.com/exif/1.0/"
+        xmlns:exifEX="http://cipa.jp/exif/1.0/"
+        tiff:Make="Canon"
+        tiff:Model="EOS R5"
+        tiff:Orientation="6"
+        exif:PixelXDimension="8192"
+        exif:PixelYDimension="5464"
+        exif:DateTimeOriginal="2024-05-03T18:00+09:00"
+        exif:ExposureTime="1/250"
+        exif:FNumber="28/10"
+        exif:GPSLatitude="35,30.0N"
+        exif:GPSLongitude="139,45,36E"
+        exif:GPSAltitude="400/10"
+        exif:GPSAltitudeRef="1"
+        exifEX:LensModel="RF24-70mm F2.8 L IS USM">
+      <exif:ISOSpeedRatings>
+        <rdf:Seq><rdf:li>400</rdf:li></rdf:Seq>
+      </exif:ISOSpeedRatings>
+    </rdf:Description>
+  </rdf:RDF>
+</x:xmpmeta>"#;
+
+        let metadata = parse_xmp(xml).unwrap();
+        assert_eq!(metadata.width, Some(8192));
+        assert_eq!(metadata.height, Some(5464));
+        assert_eq!(metadata.captured_at.as_deref(), Some("2024-05-03 18:00:00"));
+        assert_eq!(metadata.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(metadata.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(metadata.lens_model.as_deref(), Some("RF24-70mm F2.8 L IS USM"));
+        assert_eq!(metadata.iso, Some(400));
+        assert_close(metadata.exposure_time, 0.004);
+        assert_close(metadata.f_number, 2.8);
+        assert_eq!(metadata.orientation, Some(6));
+
+        let location = metadata.location.unwrap();
+        assert_close(Some(location.latitude), 35.5);
+        assert_close(Some(location.longitude), 139.76);
+        assert_close(location.altitude, -40.0);
+    }
+
+    #[test]
+    fn parse_xmp_ignores_invalid_values() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+  <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+    <rdf:Description xmlns:tiff="http://ns.adobe.com/tiff/1.0/" xmlns:exif="http://ns.adobe.com/exif/1.0/"
+        tiff:Orientation="9" exif:DateTimeOriginal="not a date" exif:GPSLatitude="35,30.0N"/>
+  </rdf:RDF>
+</x:xmpmeta>"#;
+
+        let metadata = parse_xmp(xml).unwrap();
+        assert_eq!(metadata.orientation, None);
+        assert_eq!(metadata.captured_at, None);
+        assert_eq!(metadata.location, None);
+        assert!(parse_xmp("<x:xmpmeta>").is_none());
+    }
+}
@@ -33,15 +33,27 @@ pub fn get_file_type(path: &str) -> String {
     "unknown".to_string()
 }
 
+/// スキャンで見つかったメディアファイルの情報
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    /// ファイルの絶対パス
+    pub path: String,
+    /// ファイルサイズ（バイト）
+    pub size: Option<i64>,
+    /// 最終更新日時（UNIX秒）
+    pub mtime: Option<i64>,
+}
+
 /**
  * 指定されたディレクトリ内のメディアファイル（画像・動画）をスキャンします
+ * 差分スキャン用にファイルサイズと最終更新日時も取得します
  *
  * @param dir_path スキャンするディレクトリのパス
- * @return 見つかったメディアファイルのパスの配列
+ * @return 見つかったメディアファイルの配列
  */
-pub fn scan_images_in_directory(dir_path: &str) -> Result<Vec<String>, String> {
+pub fn scan_images_in_directory(dir_path: &str) -> Result<Vec<ScannedFile>, String> {
     let valid_extensions = get_all_extensions();
-    let mut file_paths = Vec::new();
+    let mut files = Vec::new();
 
     for entry in WalkDir::new(dir_path)
         .into_iter()
@@ -51,13 +63,32 @@ pub fn scan_images_in_directory(dir_path: &str) -> Result<Vec<String>, String> {
             if let Some(ext) = entry.path().extension() {
                 let ext_str = ext.to_str().unwrap_or("").to_lowercase();
                 if valid_extensions.contains(&ext_str.as_str()) {
-                    file_paths.push(entry.path().to_string_lossy().to_string());
+                    let metadata = entry.metadata().ok();
+                    files.push(ScannedFile {
+                        path: entry.path().to_string_lossy().to_string(),
+                        size: metadata.as_ref().map(|m| m.len() as i64),
+                        mtime: metadata.as_ref().and_then(get_mtime_secs),
+                    });
                 }
             }
         }
     }
 
-    Ok(file_paths)
+    Ok(files)
+}
+
+/**
+ * ファイルメタデータから最終更新日時（UNIX秒）を取得します
+ *
+ * @param metadata ファイルメタデータ
+ * @return UNIX秒、取得できない場合は None
+ */
+pub fn get_mtime_secs(metadata: &std::fs::Metadata) -> Option<i64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
 }
 
 /**
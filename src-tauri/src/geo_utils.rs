@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+/// 地球の平均半径（km）
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// 撮影位置（WGS84）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// 標高（m、海面下は負の値）
+    pub altitude: Option<f64>,
+}
+
+impl GeoLocation {
+    /// 緯度・経度が有効範囲内なら GeoLocation を作成
+    /// GPS未測位のカメラが書き込む (0, 0) は無効として扱う
+    pub fn new(latitude: f64, longitude: f64, altitude: Option<f64>) -> Option<Self> {
+        if validate_coordinates(latitude, longitude).is_err() || (latitude == 0.0 && longitude == 0.0) {
+            return None;
+        }
+        Some(Self {
+            latitude,
+            longitude,
+            altitude: altitude.filter(|a| a.is_finite()),
+        })
+    }
+}
+
+/// 緯度・経度の範囲チェック
+pub fn validate_coordinates(latitude: f64, longitude: f64) -> Result<(), String> {
+    if !latitude.is_finite() || !(-90.0..=90.0).contains(&latitude) {
+        return Err(format!("Latitude must be between -90 and 90: {}", latitude));
+    }
+    if !longitude.is_finite() || !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!("Longitude must be between -180 and 180: {}", longitude));
+    }
+    Ok(())
+}
+
+/// 度・分・秒を10進の度に変換（ref が S / W の場合は負の値）
+pub fn dms_to_degrees(degrees: f64, minutes: f64, seconds: f64, reference: &str) -> f64 {
+    let value = degrees + minutes / 60.0 + seconds / 3600.0;
+    match reference.trim().to_ascii_uppercase().as_str() {
+        "S" | "W" => -value,
+        _ => value,
+    }
+}
+
+/// ISO 6709 形式の位置（"+35.6895+139.6917+040.000/" など）を解析
+/// QuickTime / MP4 の location タグで使われる形式。標高は省略可
+pub fn parse_iso6709(value: &str) -> Option<GeoLocation> {
+    let value = value.trim().trim_end_matches('/');
+
+    // 符号の位置で区切る（先頭は必ず符号）
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in value.char_indices().skip(1) {
+        if c == '+' || c == '-' {
+            parts.push(&value[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&value[start..]);
+
+    if !parts.iter().all(|p| p.starts_with('+') || p.starts_with('-')) {
+        return None;
+    }
+
+    let mut numbers = parts.iter().map(|p| p.parse::<f64>().ok());
+    let latitude = numbers.next()??;
+    let longitude = numbers.next()??;
+    let altitude = numbers.next().flatten();
+
+    GeoLocation::new(latitude, longitude, altitude)
+}
+
+/// 2点間の大円距離（km、ハーバサイン公式）
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// 中心から半径 radius_km の円を含む緯度・経度の範囲
+/// (最小緯度, 最小経度, 最大緯度, 最大経度) を返す。極をまたぐ場合は経度を全範囲にする
+/// 日付変更線をまたぐ場合は最小経度 > 最大経度 になる
+pub fn bounding_box(latitude: f64, longitude: f64, radius_km: f64) -> (f64, f64, f64, f64) {
+    let d_lat = (radius_km / EARTH_RADIUS_KM).to_degrees();
+    let min_lat = latitude - d_lat;
+    let max_lat = latitude + d_lat;
+
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return (min_lat.max(-90.0), -180.0, max_lat.min(90.0), 180.0);
+    }
+
+    let d_lon = (radius_km / (EARTH_RADIUS_KM * latitude.to_radians().cos())).to_degrees();
+    if d_lon >= 180.0 {
+        return (min_lat, -180.0, max_lat, 180.0);
+    }
+
+    let wrap = |lon: f64| {
+        if lon < -180.0 {
+            lon + 360.0
+        } else if lon > 180.0 {
+            lon - 360.0
+        } else {
+            lon
+        }
+    };
+    (min_lat, wrap(longitude - d_lon), max_lat, wrap(longitude + d_lon))
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// 同梱の地名辞書（リソースディレクトリからの相対パス）
+const GAZETTEER_RESOURCE: &str = "resources/geonames/cities.tsv";
+
+/// 最寄りの都市がこれより遠い場合は地名なしとする（海上など）
+const MAX_PLACE_DISTANCE_KM: f64 = 100.0;
+
+/// 検索用グリッドの1セルの大きさ（度）
+const GRID_CELL_DEGREES: f64 = 1.0;
+
+/// リソースディレクトリ（起動時に設定）
+static RESOURCE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 読み込み済みの地名辞書（読み込みに失敗した場合は None）
+static GAZETTEER: OnceLock<Option<Gazetteer>> = OnceLock::new();
+
+/// 逆ジオコーディングの結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    /// ISO 3166-1 alpha-2
+    pub country_code: String,
+    pub country: String,
+    /// 都道府県・州など
+    pub region: Option<String>,
+    pub city: String,
+}
+
+/// 地名辞書（都市の位置と、近傍検索用のグリッド）
+struct Gazetteer {
+    cities: Vec<City>,
+    grid: HashMap<(i32, i32), Vec<usize>>,
+}
+
+struct City {
+    latitude: f64,
+    longitude: f64,
+    place: Place,
+}
+
+/// リソースディレクトリを設定（アプリ起動時に1回呼ぶ）
+pub fn set_resource_dir(dir: PathBuf) {
+    let _ = RESOURCE_DIR.set(dir);
+}
+
+/// 地名辞書のパス
+fn gazetteer_path() -> PathBuf {
+    match RESOURCE_DIR.get() {
+        Some(dir) => dir.join(GAZETTEER_RESOURCE),
+        // 開発時（リソースディレクトリ未設定）はソースツリーのファイルを使用
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(GAZETTEER_RESOURCE),
+    }
+}
+
+/// 緯度・経度が属するグリッドセル
+fn cell_of(latitude: f64, longitude: f64) -> (i32, i32) {
+    (
+        (latitude / GRID_CELL_DEGREES).floor() as i32,
+        (longitude / GRID_CELL_DEGREES).floor() as i32,
+    )
+}
+
+impl Gazetteer {
+    /// タブ区切りの地名辞書を解析
+    /// 列: name, latitude, longitude, country_code, country, region（# で始まる行は無視）
+    fn parse(content: &str) -> Self {
+        let mut cities = Vec::new();
+        let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+
+        for line in content.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [name, latitude, longitude, country_code, country, region, ..] = columns.as_slice() else {
+                continue;
+            };
+            let (Ok(latitude), Ok(longitude)) = (latitude.parse::<f64>(), longitude.parse::<f64>()) else {
+                continue;
+            };
+            if name.is_empty() || crate::geo_utils::validate_coordinates(latitude, longitude).is_err() {
+                continue;
+            }
+
+            grid.entry(cell_of(latitude, longitude)).or_default().push(cities.len());
+            cities.push(City {
+                latitude,
+                longitude,
+                place: Place {
+                    country_code: country_code.to_string(),
+                    country: country.to_string(),
+                    region: Some(region.to_string()).filter(|r| !r.is_empty()),
+                    city: name.to_string(),
+                },
+            });
+        }
+
+        Self { cities, grid }
+    }
+
+    /// 指定位置から MAX_PLACE_DISTANCE_KM 以内で最も近い都市
+    fn nearest(&self, latitude: f64, longitude: f64) -> Option<&Place> {
+        let (min_lat, min_lon, max_lat, max_lon) =
+            crate::geo_utils::bounding_box(latitude, longitude, MAX_PLACE_DISTANCE_KM);
+        let (min_row, min_col) = cell_of(min_lat, min_lon);
+        let (max_row, max_col) = cell_of(max_lat, max_lon);
+
+        // 日付変更線をまたぐ場合は経度方向のセルを2つの範囲に分ける
+        let columns: Vec<i32> = if min_col <= max_col {
+            (min_col..=max_col).collect()
+        } else {
+            let (_, last_col) = cell_of(0.0, 180.0);
+            let (_, first_col) = cell_of(0.0, -180.0);
+            (min_col..=last_col).chain(first_col..=max_col).collect()
+        };
+
+        (min_row..=max_row)
+            .flat_map(|row| columns.iter().map(move |col| (row, *col)))
+            .filter_map(|cell| self.grid.get(&cell))
+            .flatten()
+            .map(|&i| {
+                let city = &self.cities[i];
+                let distance = crate::geo_utils::haversine_km(latitude, longitude, city.latitude, city.longitude);
+                (distance, city)
+            })
+            .filter(|(distance, _)| *distance <= MAX_PLACE_DISTANCE_KM)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, city)| &city.place)
+    }
+}
+
+/// 地名辞書を取得（初回のみファイルから読み込む）
+fn gazetteer() -> Option<&'static Gazetteer> {
+    GAZETTEER
+        .get_or_init(|| {
+            let path = gazetteer_path();
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    let gazetteer = Gazetteer::parse(&content);
+                    println!("Loaded {} places from {}", gazetteer.cities.len(), path.display());
+                    Some(gazetteer)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to load gazetteer {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// 緯度・経度から地名を求める（ネットワークを使わず同梱の地名辞書で判定）
+/// 地名辞書が読み込めない場合や、近くに都市がない場合は None
+pub fn reverse_geocode(latitude: f64, longitude: f64) -> Option<Place> {
+    gazetteer()?.nearest(latitude, longitude).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn city_name(gazetteer: &Gazetteer, latitude: f64, longitude: f64) -> Option<&str> {
+        gazetteer.nearest(latitude, longitude).map(|p| p.city.as_str())
+    }
+
+    #[test]
+    fn parse_reads_rows_and_skips_invalid_lines() {
+        let gazetteer = Gazetteer::parse(
+            "# name\tlatitude\tlongitude\tcountry_code\tcountry\tregion\n\
+             \n\
+             Tokyo\t35.6895\t139.69171\tJP\tJapan\tTokyo\n\
+             Monaco\t43.73333\t7.41667\tMC\tMonaco\t\n\
+             Extra\t1.0\t2.0\tXX\tCountry\tRegion\textra column\n\
+             \t10.0\t10.0\tXX\tCountry\tRegion\n\
+             Nowhere\tabc\t10.0\tXX\tCountry\tRegion\n\
+             Pole\t91.0\t10.0\tXX\tCountry\tRegion\n\
+             Short\t10.0\t10.0\tXX\n",
+        );
+
+        let names: Vec<&str> = gazetteer.cities.iter().map(|c| c.place.city.as_str()).collect();
+        assert_eq!(names, vec!["Tokyo", "Monaco", "Extra"]);
+
+        let tokyo = &gazetteer.cities[0];
+        assert_eq!((tokyo.latitude, tokyo.longitude), (35.6895, 139.69171));
+        assert_eq!(
+            tokyo.place,
+            Place {
+                country_code: "JP".to_string(),
+                country: "Japan".to_string(),
+                region: Some("Tokyo".to_string()),
+                city: "Tokyo".to_string(),
+            }
+        );
+        // 地域が空の行は None
+        assert_eq!(gazetteer.cities[1].place.region, None);
+    }
+
+    #[test]
+    fn nearest_picks_the_closest_city_within_range() {
+        let gazetteer = Gazetteer::parse(
+            "Tokyo\t35.6895\t139.69171\tJP\tJapan\tTokyo\n\
+             Yokohama\t35.44778\t139.6425\tJP\tJapan\tKanagawa\n\
+             Osaka\t34.69374\t135.50218\tJP\tJapan\tOsaka\n",
+        );
+
+        assert_eq!(city_name(&gazetteer, 35.68, 139.70), Some("Tokyo"));
+        assert_eq!(city_name(&gazetteer, 35.46, 139.63), Some("Yokohama"));
+        // 隣のグリッドセルにある都市も見つかる
+        assert_eq!(city_name(&gazetteer, 35.01, 135.76), Some("Osaka"));
+        // 100km より遠い場合は None（太平洋上）
+        assert_eq!(city_name(&gazetteer, 30.0, 150.0), None);
+        assert_eq!(city_name(&Gazetteer::parse(""), 35.68, 139.70), None);
+    }
+
+    #[test]
+    fn nearest_searches_across_the_antimeridian() {
+        let gazetteer = Gazetteer::parse(
+            "Labasa\t-16.41667\t179.38333\tFJ\tFiji\tNorthern\n\
+             East\t-16.5\t-179.8\tFJ\tFiji\tEastern\n\
+             Meridian\t-20.0\t180.0\tXX\tCountry\tRegion\n",
+        );
+
+        // 東経側から西経側の都市を探す
+        assert_eq!(city_name(&gazetteer, -16.5, 179.9), Some("East"));
+        // 西経側から東経側の都市を探す
+        assert_eq!(city_name(&gazetteer, -16.42, -179.6), Some("East"));
+        assert_eq!(city_name(&gazetteer, -16.42, 179.4), Some("Labasa"));
+        // 経度 180 ちょうどの都市も両側から見つかる
+        assert_eq!(city_name(&gazetteer, -20.0, 179.9), Some("Meridian"));
+        assert_eq!(city_name(&gazetteer, -20.0, -179.9), Some("Meridian"));
+    }
+
+    #[test]
+    fn nearest_handles_the_poles() {
+        let gazetteer = Gazetteer::parse("Station\t89.5\t0.0\tXX\tCountry\tRegion\n");
+
+        // 極の近くでは経度方向の全セルを探す
+        assert_eq!(city_name(&gazetteer, 89.9, 170.0), Some("Station"));
+        assert_eq!(city_name(&gazetteer, 80.0, 0.0), None);
+    }
+}
@@ -0,0 +1,26 @@
+use std::fs::File;
+use std::io::Read;
+
+/// ハッシュ計算時の読み込みバッファサイズ（大きな動画でもメモリを食わないよう分割して読む）
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// ファイル内容のBLAKE3ハッシュを計算（16進文字列）
+pub fn compute_file_hash(path: &str) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open file for hashing: {}: {}", path, e))?;
+
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file for hashing: {}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
@@ -0,0 +1,402 @@
+use serde::Serialize;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// ジョブの進捗イベント名
+pub const JOB_PROGRESS_EVENT: &str = "job-progress";
+
+/// 進捗イベントの最小送信間隔（大量ファイル時にイベントが溢れないよう間引く）
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 保持する終了済みジョブの最大数
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// ジョブの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// ジョブの情報（job-progress イベントのペイロードを兼ねる）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    /// ジョブの種類（"scan", "thumbnails" など）
+    pub kind: String,
+    pub status: JobStatus,
+    pub total: u64,
+    pub processed: u64,
+    pub current_file: Option<String>,
+    pub error: Option<String>,
+    /// 完了時の結果（ジョブの種類ごとの形式、キャンセル時は中断までに処理した分）
+    pub result: Option<serde_json::Value>,
+}
+
+/// ジョブの処理本体
+type JobWork = Box<dyn FnOnce(&JobContext) -> Result<serde_json::Value, String> + Send>;
+
+/// キューに積まれたジョブ
+struct QueuedJob {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    work: JobWork,
+}
+
+/// ジョブキューの状態を保持
+pub struct JobState {
+    jobs: HashMap<u64, JobInfo>,
+    /// 登録順（一覧表示と古いジョブの削除に使用）
+    order: VecDeque<u64>,
+    cancel_flags: HashMap<u64, Arc<AtomicBool>>,
+    next_id: u64,
+    /// ディスパッチャースレッドへの送信側（最初のジョブ登録時に起動）
+    sender: Option<Sender<QueuedJob>>,
+}
+
+impl JobState {
+    pub fn new() -> Self {
+        Self {
+            jobs: HashMap::new(),
+            order: VecDeque::new(),
+            cancel_flags: HashMap::new(),
+            next_id: 1,
+            sender: None,
+        }
+    }
+
+    /// 終了済みジョブが上限を超えたら古いものから削除
+    fn prune_finished(&mut self) {
+        let finished = self
+            .order
+            .iter()
+            .filter(|id| self.jobs.get(id).is_some_and(|j| j.status.is_finished()))
+            .count();
+
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        let jobs = &mut self.jobs;
+        let cancel_flags = &mut self.cancel_flags;
+        self.order.retain(|id| {
+            if excess > 0 && jobs.get(id).is_some_and(|j| j.status.is_finished()) {
+                jobs.remove(id);
+                cancel_flags.remove(id);
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// 実行中ジョブから進捗報告・キャンセル確認を行うためのコンテキスト
+pub struct JobContext {
+    app: AppHandle,
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    processed: AtomicU64,
+    last_emit: Mutex<Instant>,
+    /// キャンセルによって処理が途中で打ち切られたか
+    interrupted: AtomicBool,
+}
+
+impl JobContext {
+    /// 処理対象の総数を設定
+    pub fn set_total(&self, total: u64) {
+        update_job(&self.app, self.id, |job| job.total = total);
+    }
+
+    /// 1件処理したことを報告
+    pub fn advance(&self, current_file: &str) {
+        let processed = self.processed.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let should_emit = match self.last_emit.lock() {
+            Ok(mut last_emit) if last_emit.elapsed() >= PROGRESS_EMIT_INTERVAL => {
+                *last_emit = Instant::now();
+                true
+            }
+            _ => false,
+        };
+
+        let current_file = current_file.to_string();
+        let update = |job: &mut JobInfo| {
+            job.processed = job.processed.max(processed);
+            job.current_file = Some(current_file);
+        };
+
+        if should_emit {
+            update_job(&self.app, self.id, update);
+        } else {
+            update_job_silently(&self.app, self.id, update);
+        }
+    }
+
+    /// キャンセルによって処理が途中で打ち切られたか
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    /// 要素をワーカープールで並列処理（結果は入力順）
+    /// キャンセルで未処理の要素が残った場合は None を返し、ジョブを中断扱いにする
+    /// （全件処理し終えた後のキャンセルは中断扱いにしない）
+    pub fn run_parallel<T, R, F>(&self, items: Vec<T>, workers: usize, f: F) -> Option<Vec<R>>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Sync,
+    {
+        let results: Option<Vec<R>> = parallel_map(items, workers, &self.cancel, f).into_iter().collect();
+        if results.is_none() {
+            self.interrupted.store(true, Ordering::Relaxed);
+        }
+        results
+    }
+}
+
+/// ワーカースレッド数（CPUコア数、最大8）
+pub fn worker_count() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(1, 8)
+}
+
+/// 要素を最大workers個のスレッドで並列処理（結果は入力順）
+/// cancel が立った時点で未着手の要素は処理せず None のまま返す
+pub fn parallel_map<T, R, F>(items: Vec<T>, workers: usize, cancel: &AtomicBool, f: F) -> Vec<Option<R>>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let len = items.len();
+    let inputs: Vec<Mutex<Option<T>>> = items.into_iter().map(|i| Mutex::new(Some(i))).collect();
+    let outputs: Vec<Mutex<Option<R>>> = (0..len).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, len.max(1)) {
+            scope.spawn(|| loop {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= len {
+                    break;
+                }
+                let item = inputs[index].lock().ok().and_then(|mut i| i.take());
+                if let Some(item) = item {
+                    let result = f(item);
+                    if let Ok(mut slot) = outputs[index].lock() {
+                        *slot = Some(result);
+                    }
+                }
+            });
+        }
+    });
+
+    outputs
+        .into_iter()
+        .map(|slot| slot.into_inner().ok().flatten())
+        .collect()
+}
+
+/// ジョブ情報を更新して job-progress イベントを送信
+fn update_job(app: &AppHandle, id: u64, f: impl FnOnce(&mut JobInfo)) {
+    let snapshot = {
+        let state = app.state::<Mutex<JobState>>();
+        let Ok(mut job_state) = state.lock() else {
+            return;
+        };
+        let Some(job) = job_state.jobs.get_mut(&id) else {
+            return;
+        };
+        f(job);
+        job.clone()
+    };
+
+    if let Err(e) = app.emit(JOB_PROGRESS_EVENT, &snapshot) {
+        eprintln!("Failed to emit {} event: {}", JOB_PROGRESS_EVENT, e);
+    }
+}
+
+/// ジョブ情報をイベント送信なしで更新
+fn update_job_silently(app: &AppHandle, id: u64, f: impl FnOnce(&mut JobInfo)) {
+    let state = app.state::<Mutex<JobState>>();
+    let Ok(mut job_state) = state.lock() else {
+        return;
+    };
+    if let Some(job) = job_state.jobs.get_mut(&id) {
+        f(job);
+    }
+}
+
+/// ジョブをキューに登録（キュー内のジョブは登録順に1件ずつ実行）
+pub fn enqueue_job<F>(app: &AppHandle, kind: &str, work: F) -> Result<u64, String>
+where
+    F: FnOnce(&JobContext) -> Result<serde_json::Value, String> + Send + 'static,
+{
+    let state = app.state::<Mutex<JobState>>();
+    let mut job_state = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    let id = job_state.next_id;
+    job_state.next_id += 1;
+
+    let info = JobInfo {
+        id,
+        kind: kind.to_string(),
+        status: JobStatus::Queued,
+        total: 0,
+        processed: 0,
+        current_file: None,
+        error: None,
+        result: None,
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    job_state.jobs.insert(id, info.clone());
+    job_state.order.push_back(id);
+    job_state.cancel_flags.insert(id, cancel.clone());
+    job_state.prune_finished();
+
+    let sender = match &job_state.sender {
+        Some(sender) => sender.clone(),
+        None => {
+            let sender = start_dispatcher(app.clone());
+            job_state.sender = Some(sender.clone());
+            sender
+        }
+    };
+    drop(job_state);
+
+    sender
+        .send(QueuedJob {
+            id,
+            cancel,
+            work: Box::new(work),
+        })
+        .map_err(|e| format!("Failed to queue job: {}", e))?;
+
+    if let Err(e) = app.emit(JOB_PROGRESS_EVENT, &info) {
+        eprintln!("Failed to emit {} event: {}", JOB_PROGRESS_EVENT, e);
+    }
+
+    println!("Job {} ({}) queued", id, kind);
+
+    Ok(id)
+}
+
+/// キューからジョブを取り出して順に実行するスレッドを起動
+fn start_dispatcher(app: AppHandle) -> Sender<QueuedJob> {
+    let (sender, receiver) = channel::<QueuedJob>();
+
+    thread::spawn(move || {
+        for job in receiver {
+            // 待機中にキャンセルされたジョブは実行しない
+            if job.cancel.load(Ordering::Relaxed) {
+                update_job(&app, job.id, |info| info.status = JobStatus::Cancelled);
+                continue;
+            }
+
+            update_job(&app, job.id, |info| info.status = JobStatus::Running);
+
+            let context = JobContext {
+                app: app.clone(),
+                id: job.id,
+                cancel: job.cancel.clone(),
+                processed: AtomicU64::new(0),
+                last_emit: Mutex::new(Instant::now()),
+                interrupted: AtomicBool::new(false),
+            };
+            // ジョブ内でパニックしてもディスパッチャーは止めず、そのジョブを失敗として扱う
+            let outcome = catch_unwind(AssertUnwindSafe(|| (job.work)(&context)))
+                .unwrap_or_else(|payload| Err(format!("Job panicked: {}", panic_message(payload.as_ref()))));
+
+            update_job(&app, job.id, |info| {
+                info.current_file = None;
+                // 処理が打ち切られた場合のみキャンセル扱いにし、それまでの結果は残す
+                match outcome {
+                    Ok(result) if context.was_interrupted() => {
+                        info.status = JobStatus::Cancelled;
+                        info.result = Some(result);
+                    }
+                    Ok(result) => {
+                        info.status = JobStatus::Completed;
+                        info.processed = info.total.max(info.processed);
+                        info.result = Some(result);
+                    }
+                    Err(_) if context.was_interrupted() => info.status = JobStatus::Cancelled,
+                    Err(e) => {
+                        eprintln!("Job {} failed: {}", info.id, e);
+                        info.status = JobStatus::Failed;
+                        info.error = Some(e);
+                    }
+                }
+            });
+        }
+    });
+
+    sender
+}
+
+/// パニックのペイロードからメッセージを取り出す
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// ジョブのキャンセルを要求（既に終了している場合は false）
+pub fn cancel_job(app: &AppHandle, job_id: u64) -> Result<bool, String> {
+    let state = app.state::<Mutex<JobState>>();
+    let job_state = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    let job = job_state
+        .jobs
+        .get(&job_id)
+        .ok_or_else(|| format!("Job not found: {}", job_id))?;
+    if job.status.is_finished() {
+        return Ok(false);
+    }
+
+    if let Some(cancel) = job_state.cancel_flags.get(&job_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+
+    println!("Job {} cancellation requested", job_id);
+
+    Ok(true)
+}
+
+/// ジョブの一覧を登録順に取得
+pub fn list_jobs(app: &AppHandle) -> Result<Vec<JobInfo>, String> {
+    let state = app.state::<Mutex<JobState>>();
+    let job_state = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+
+    Ok(job_state
+        .order
+        .iter()
+        .filter_map(|id| job_state.jobs.get(id).cloned())
+        .collect())
+}
@@ -1,11 +1,21 @@
 mod db;
 mod commands;
+mod exif_utils;
 mod fs_utils;
+mod geo_utils;
+mod geocode_utils;
+mod hash_utils;
+mod jobs;
+mod phash_utils;
+mod query_utils;
+mod thumbnail_utils;
 mod video_utils;
 mod watcher;
+mod xmp_utils;
 
 use commands::*;
 use std::sync::Mutex;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -14,7 +24,13 @@ pub fn run() {
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_fs::init())
     .manage(Mutex::new(watcher::WatcherState::new()))
+    .manage(Mutex::new(jobs::JobState::new()))
     .setup(|app| {
+      // 逆ジオコーディング用の地名辞書はリソースディレクトリに同梱
+      match app.path().resource_dir() {
+        Ok(dir) => geocode_utils::set_resource_dir(dir),
+        Err(e) => eprintln!("Warning: Failed to resolve resource directory: {}", e),
+      }
       if cfg!(debug_assertions) {
         app.handle().plugin(
           tauri_plugin_log::Builder::default()
@@ -31,15 +47,35 @@ pub fn run() {
       reset_database,
       select_directory,
       scan_directory,
+      // 画像メタデータAPI
+      get_images,
+      query_images,
+      update_image_metadata,
+      bulk_update_images,
+      get_images_by_date_range,
+      get_taken_date_counts,
+      find_images_in_bounds,
+      find_images_near,
+      list_places,
+      get_images_by_place,
+      reverse_geocode_images,
       video_utils::check_ffmpeg_available,
       video_utils::generate_video_thumbnail,
+      video_utils::get_metadata_workers,
+      video_utils::set_metadata_workers,
+      // サムネイル（画像・動画共通）
+      thumbnail_utils::get_thumbnail,
+      thumbnail_utils::gc_thumbnails,
       // Phase 4: グループ管理コマンド
       create_group,
       get_all_groups,
+      get_group_tree,
+      reorder_groups,
       update_group,
       delete_group,
       add_images_to_group,
       remove_images_from_group,
+      reorder_group_images,
       get_group_images,
       get_image_groups,
       // Phase 5: グループアルバムビュー & コメント機能
@@ -47,7 +83,13 @@ pub fn run() {
       set_representative_image,
       add_group_comment,
       get_group_comments,
+      update_group_comment,
+      get_group_comment_history,
       delete_group_comment,
+      set_group_image_caption,
+      get_group_image_captions,
+      // 全文検索
+      search,
       // Phase 6: マルチディレクトリ管理
       add_directory,
       remove_directory,
@@ -55,6 +97,29 @@ pub fn run() {
       set_directory_active,
       scan_single_directory,
       scan_all_active_directories,
+      // スキャン結果のDB同期
+      sync_directory,
+      sync_single_directory,
+      sync_all_active_directories,
+      // ライブラリの整合性チェック
+      reconcile_library,
+      purge_missing_images,
+      // 重複ファイルの検出・統合
+      find_duplicates,
+      merge_duplicates,
+      // 類似画像の検出
+      find_similar,
+      cluster_similar_images,
+      // タグ管理
+      list_tags,
+      get_images_by_tag,
+      rename_tag,
+      move_tag,
+      merge_tags,
+      delete_tag,
+      // XMPサイドカー
+      set_directory_xmp_sidecars,
+      write_xmp_sidecars,
       // Phase 6: Undo/Redo
       log_action,
       get_last_undoable_action,
@@ -64,11 +129,17 @@ pub fn run() {
       // Phase 6: エクスポート/インポート
       export_metadata_json,
       export_metadata_csv,
+      export_locations_geojson,
       import_metadata_json,
       // Phase 6: ファイルウォッチャー
       start_file_watcher,
       stop_file_watcher,
       get_watched_directories,
+      // バックグラウンドジョブ
+      start_scan_job,
+      start_thumbnail_job,
+      cancel_job,
+      list_jobs,
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
@@ -0,0 +1,264 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::collections::HashMap;
+
+/// 類似判定のデフォルトしきい値（64ビット中の異なるビット数）
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+/// ハッシュのビット数（しきい値の上限）
+pub const HASH_BITS: u32 = 64;
+
+/// dHash（差分ハッシュ）を計算
+/// 9x8のグレースケールに縮小し、横方向に隣接するピクセルの明暗を64ビットに詰める。
+/// 再エンコードやリサイズ、わずかな色調補正ではほとんど変化しない
+pub fn compute_dhash(img: &DynamicImage) -> u64 {
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+/// 画像ファイルの知覚ハッシュを計算（16進文字列）
+pub fn compute_image_phash(path: &str) -> Result<String, String> {
+    let img = image::open(path)
+        .map_err(|e| format!("Failed to decode image for perceptual hash: {}: {}", path, e))?;
+    Ok(format_hash(compute_dhash(&img)))
+}
+
+/// 画像データ（メモリ上のエンコード済みバイト列）の知覚ハッシュを計算（16進文字列）
+pub fn compute_phash_from_bytes(bytes: &[u8]) -> Result<String, String> {
+    let img = image::load_from_memory(bytes)
+        .map_err(|e| format!("Failed to decode frame for perceptual hash: {}", e))?;
+    Ok(format_hash(compute_dhash(&img)))
+}
+
+/// ハッシュ値をDB保存用の16進文字列に変換
+/// （JSのNumberでは64ビット整数を正確に扱えないため文字列で保持する）
+pub fn format_hash(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+/// DBに保存された16進文字列をハッシュ値に戻す
+pub fn parse_hash(hash: &str) -> Option<u64> {
+    u64::from_str_radix(hash, 16).ok()
+}
+
+/// 2つのハッシュのハミング距離
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// ハミング距離がしきい値以内の画像を連結成分としてまとめる
+/// 2件以上のクラスタのみを、入力順を保った画像IDのリストとして返す
+///
+/// 全組み合わせの比較は件数の2乗に比例するため、64ビットを max_distance + 1 個の帯に分け、
+/// いずれかの帯が一致する組だけを比較する（距離が max_distance 以内なら鳩の巣原理で必ずどれかの帯が一致する）。
+/// しきい値が大きいほど帯が細くなって候補が増えるため、既定値（10）前後での利用を想定している
+pub fn cluster_by_distance(hashes: &[(i64, u64)], max_distance: u32) -> Vec<Vec<i64>> {
+    // Union-Find
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    fn union(parent: &mut [usize], i: usize, j: usize) {
+        let (a, b) = (find(parent, i), find(parent, j));
+        if a != b {
+            parent[b] = a;
+        }
+    }
+
+    // 同じハッシュの画像は先にまとめ、以降は代表（最初の1件）だけを比較する
+    let mut first_index: HashMap<u64, usize> = HashMap::new();
+    let mut representatives: Vec<usize> = Vec::new();
+    for (i, (_, hash)) in hashes.iter().enumerate() {
+        match first_index.get(hash) {
+            Some(&first) => union(&mut parent, first, i),
+            None => {
+                first_index.insert(*hash, i);
+                representatives.push(i);
+            }
+        }
+    }
+
+    if max_distance >= HASH_BITS {
+        // すべての組がしきい値以内
+        for pair in representatives.windows(2) {
+            union(&mut parent, pair[0], pair[1]);
+        }
+    } else {
+        let bands = max_distance + 1;
+        let mut buckets: HashMap<(u32, u64), Vec<usize>> = HashMap::new();
+        for &i in &representatives {
+            for band in 0..bands {
+                let start = band * HASH_BITS / bands;
+                let width = (band + 1) * HASH_BITS / bands - start;
+                let mask = u64::MAX >> (HASH_BITS - width);
+                buckets.entry((band, (hashes[i].1 >> start) & mask)).or_default().push(i);
+            }
+        }
+
+        for members in buckets.values() {
+            for (k, &i) in members.iter().enumerate() {
+                for &j in &members[k + 1..] {
+                    if find(&mut parent, i) != find(&mut parent, j)
+                        && hamming_distance(hashes[i].1, hashes[j].1) <= max_distance
+                    {
+                        union(&mut parent, i, j);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = Vec::new();
+    let mut members: HashMap<usize, Vec<i64>> = HashMap::new();
+    for (i, (id, _)) in hashes.iter().enumerate() {
+        let root = find(&mut parent, i);
+        let entry = members.entry(root).or_default();
+        if entry.is_empty() {
+            order.push(root);
+        }
+        entry.push(*id);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|root| members.remove(&root))
+        .filter(|ids| ids.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// 全組み合わせを比較する素朴な実装（帯による絞り込みの検証用）
+    fn cluster_all_pairs(hashes: &[(i64, u64)], max_distance: u32) -> Vec<Vec<i64>> {
+        let mut cluster_of: Vec<usize> = (0..hashes.len()).collect();
+        for i in 0..hashes.len() {
+            for j in (i + 1)..hashes.len() {
+                if hamming_distance(hashes[i].1, hashes[j].1) <= max_distance {
+                    let (from, to) = (cluster_of[j], cluster_of[i]);
+                    if from != to {
+                        cluster_of.iter_mut().filter(|c| **c == from).for_each(|c| *c = to);
+                    }
+                }
+            }
+        }
+
+        let mut clusters: Vec<(usize, Vec<i64>)> = Vec::new();
+        for (i, (id, _)) in hashes.iter().enumerate() {
+            match clusters.iter_mut().find(|(c, _)| *c == cluster_of[i]) {
+                Some((_, ids)) => ids.push(*id),
+                None => clusters.push((cluster_of[i], vec![*id])),
+            }
+        }
+        clusters.into_iter().map(|(_, ids)| ids).filter(|ids| ids.len() > 1).collect()
+    }
+
+    fn gradient(width: u32, height: u32, descending: bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+            let value = (x * 255 / (width - 1)) as u8;
+            Luma([if descending { 255 - value } else { value }])
+        }))
+    }
+
+    #[test]
+    fn dhash_encodes_horizontal_gradients() {
+        // 左が明るい画像はすべてのビットが立ち、右が明るい・一様な画像は立たない
+        assert_eq!(compute_dhash(&gradient(90, 80, true)), u64::MAX);
+        assert_eq!(compute_dhash(&gradient(90, 80, false)), 0);
+        assert_eq!(compute_dhash(&DynamicImage::ImageLuma8(GrayImage::from_pixel(32, 32, Luma([128])))), 0);
+    }
+
+    #[test]
+    fn dhash_is_stable_across_resizes() {
+        let img = DynamicImage::ImageLuma8(GrayImage::from_fn(640, 480, |x, y| {
+            Luma([((x / 80 + y / 60) % 2 * 200 + (x * 50 / 640)) as u8])
+        }));
+        let resized = img.resize_exact(320, 240, image::imageops::FilterType::Lanczos3);
+
+        assert!(hamming_distance(compute_dhash(&img), compute_dhash(&resized)) <= 2);
+        assert_eq!(parse_hash(&format_hash(compute_dhash(&img))), Some(compute_dhash(&img)));
+    }
+
+    #[test]
+    fn hamming_distance_counts_differing_bits() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+        assert_eq!(hamming_distance(1 << 63, 1), 2);
+    }
+
+    #[test]
+    fn cluster_joins_transitively_and_keeps_input_order() {
+        let hashes = [
+            (10, 0b0000),
+            (20, u64::MAX),
+            (30, 0b0111),
+            (40, 0b0011_1111),
+            (50, u64::MAX ^ 1),
+            (60, 1 << 40),
+        ];
+
+        // 10-30（距離3）、30-40（距離3）で連結されるが、10-40（距離6）は直接は近くない
+        assert_eq!(cluster_by_distance(&hashes, 3), vec![vec![10, 30, 40, 60], vec![20, 50]]);
+        assert_eq!(cluster_by_distance(&hashes, 1), vec![vec![10, 60], vec![20, 50]]);
+        assert_eq!(cluster_by_distance(&hashes, 0), Vec::<Vec<i64>>::new());
+    }
+
+    #[test]
+    fn cluster_handles_identical_hashes_and_extreme_thresholds() {
+        let hashes = [(1, 42), (2, 42), (3, 42), (4, !42)];
+
+        assert_eq!(cluster_by_distance(&hashes, 0), vec![vec![1, 2, 3]]);
+        assert_eq!(cluster_by_distance(&hashes, 63), vec![vec![1, 2, 3]]);
+        assert_eq!(cluster_by_distance(&hashes, HASH_BITS), vec![vec![1, 2, 3, 4]]);
+        assert!(cluster_by_distance(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn cluster_matches_all_pairs_comparison() {
+        // 線形合同法で再現可能な疑似乱数を作り、近いハッシュを含む集合で比較する
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            state
+        };
+
+        let mut hashes: Vec<(i64, u64)> = Vec::new();
+        for id in 0..300 {
+            let hash = if id % 3 == 0 || hashes.is_empty() {
+                next()
+            } else {
+                // 既存のハッシュから数ビットだけ変えたもの
+                let base = hashes[(next() % hashes.len() as u64) as usize].1;
+                (0..next() % 12).fold(base, |h, _| h ^ (1 << (next() % 64)))
+            };
+            hashes.push((id, hash));
+        }
+
+        for max_distance in [0, 1, 5, 10, 20, 40] {
+            assert_eq!(
+                cluster_by_distance(&hashes, max_distance),
+                cluster_all_pairs(&hashes, max_distance),
+                "max_distance = {}",
+                max_distance
+            );
+        }
+    }
+}
@@ -0,0 +1,652 @@
+use rusqlite::types::Value;
+
+/// 検索クエリの構文エラー
+/// start / end はクエリ文字列中の位置（文字単位、0始まり、end は含まない）
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        Self { message: message.into(), start, end }
+    }
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (column {})", self.message, self.start + 1)
+    }
+}
+
+/// 比較演算子（":" は "=" と同じ）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        }
+    }
+}
+
+/// 検索できる項目
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Rating,
+    Favorite,
+    Tag,
+    Type,
+    Duration,
+    Width,
+    Height,
+    Taken,
+    Dir,
+    Group,
+    Name,
+    Comment,
+    Camera,
+    Place,
+}
+
+impl Field {
+    /// 項目名（大文字小文字を区別しない）から Field を取得
+    fn from_name(name: &str) -> Option<Field> {
+        let field = match name.to_ascii_lowercase().as_str() {
+            "rating" => Field::Rating,
+            "favorite" | "fav" => Field::Favorite,
+            "tag" => Field::Tag,
+            "type" => Field::Type,
+            "duration" => Field::Duration,
+            "width" => Field::Width,
+            "height" => Field::Height,
+            "taken" => Field::Taken,
+            "dir" | "directory" => Field::Dir,
+            "group" => Field::Group,
+            "name" | "file" => Field::Name,
+            "comment" => Field::Comment,
+            "camera" => Field::Camera,
+            "place" => Field::Place,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Rating => "rating",
+            Field::Favorite => "favorite",
+            Field::Tag => "tag",
+            Field::Type => "type",
+            Field::Duration => "duration",
+            Field::Width => "width",
+            Field::Height => "height",
+            Field::Taken => "taken",
+            Field::Dir => "dir",
+            Field::Group => "group",
+            Field::Name => "name",
+            Field::Comment => "comment",
+            Field::Camera => "camera",
+            Field::Place => "place",
+        }
+    }
+
+    /// 大小比較（> >= < <=）ができる項目か
+    fn is_ordered(self) -> bool {
+        matches!(self, Field::Rating | Field::Duration | Field::Width | Field::Height | Field::Taken)
+    }
+}
+
+/// クエリの1項目
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// 項目の条件（rating>=4、tag:travel など）
+    Filter { field: Field, op: Op, value: String },
+    /// 項目を指定しない語（ファイル名・コメント・タグの部分一致）
+    Text(String),
+}
+
+/// 先頭に "-" を付けると条件を否定する
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub term: Term,
+    /// 演算子の位置（Text では値と同じ）
+    pub op_start: usize,
+    pub op_end: usize,
+    /// 値の位置（引用符を含む）
+    pub value_start: usize,
+    pub value_end: usize,
+}
+
+/// SQL に変換したクエリ
+/// condition は images を別名なしで参照する WHERE 条件で、params は "?" の順に並ぶ
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageQuery {
+    pub condition: String,
+    pub params: Vec<Value>,
+}
+
+/// LIKE の部分一致パターン（\ をエスケープ文字として % _ をエスケープ）
+pub fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// クエリ文字列を解析して SQL に変換する
+pub fn compile_query(input: &str) -> Result<ImageQuery, QueryError> {
+    build_query(&parse_query(input)?)
+}
+
+/// クエリ文字列を空白区切りの項目に分解する
+/// 項目は `[-]field(:|=|!=|>|>=|<|<=)value` か語で、値と語は "..." で囲むと空白を含められる（\" \\ でエスケープ）
+pub fn parse_query(input: &str) -> Result<Vec<Clause>, QueryError> {
+    let mut parser = Parser { chars: input.chars().collect(), pos: 0 };
+    let mut clauses = Vec::new();
+    loop {
+        parser.skip_whitespace();
+        if parser.pos >= parser.chars.len() {
+            return Ok(clauses);
+        }
+        clauses.push(parser.parse_clause()?);
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// 次の文字が無いか空白か
+    fn at_term_end(&self) -> bool {
+        self.peek().map_or(true, char::is_whitespace)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_clause(&mut self) -> Result<Clause, QueryError> {
+        let start = self.pos;
+        let negated = self.peek() == Some('-');
+        if negated {
+            self.pos += 1;
+            if self.at_term_end() {
+                return Err(QueryError::new("Expected a search term after \"-\"", start, start + 1));
+            }
+        }
+
+        let term_start = self.pos;
+        let ident_end = self.scan_identifier();
+        if ident_end > term_start {
+            let op_start = ident_end;
+            self.pos = ident_end;
+            if let Some(op) = self.parse_operator() {
+                let op_end = self.pos;
+                let name: String = self.chars[term_start..ident_end].iter().collect();
+                let field = Field::from_name(&name)
+                    .ok_or_else(|| QueryError::new(format!("Unknown field \"{}\"", name), term_start, ident_end))?;
+
+                let value_start = self.pos;
+                let value = self.parse_value()?;
+                let value_end = self.pos;
+                if value.trim().is_empty() {
+                    let op_text: String = self.chars[op_start..op_end].iter().collect();
+                    return Err(QueryError::new(
+                        format!("Missing value after \"{}{}\"", name, op_text),
+                        term_start,
+                        value_end.max(op_end),
+                    ));
+                }
+
+                return Ok(Clause {
+                    negated,
+                    term: Term::Filter { field, op, value: value.trim().to_string() },
+                    op_start,
+                    op_end,
+                    value_start,
+                    value_end,
+                });
+            }
+            self.pos = term_start;
+        }
+
+        let value = self.parse_value()?;
+        let value_end = self.pos;
+        if value.trim().is_empty() {
+            return Err(QueryError::new("Empty search term", term_start, value_end));
+        }
+        Ok(Clause {
+            negated,
+            term: Term::Text(value.trim().to_string()),
+            op_start: term_start,
+            op_end: value_end,
+            value_start: term_start,
+            value_end,
+        })
+    }
+
+    /// 現在位置から項目名（英字または _ で始まる英数字）の終わりを探す。項目名でなければ現在位置を返す
+    fn scan_identifier(&self) -> usize {
+        let mut end = self.pos;
+        while let Some(&c) = self.chars.get(end) {
+            let valid = if end == self.pos { c.is_ascii_alphabetic() || c == '_' } else { c.is_ascii_alphanumeric() || c == '_' };
+            if !valid {
+                break;
+            }
+            end += 1;
+        }
+        end
+    }
+
+    fn parse_operator(&mut self) -> Option<Op> {
+        let next = self.chars.get(self.pos + 1).copied();
+        let (op, len) = match (self.peek()?, next) {
+            ('>', Some('=')) => (Op::Ge, 2),
+            ('<', Some('=')) => (Op::Le, 2),
+            ('!', Some('=')) => (Op::Ne, 2),
+            (':', _) | ('=', _) => (Op::Eq, 1),
+            ('>', _) => (Op::Gt, 1),
+            ('<', _) => (Op::Lt, 1),
+            _ => return None,
+        };
+        self.pos += len;
+        Some(op)
+    }
+
+    /// 引用符で囲んだ値か、次の空白までの値を読む
+    fn parse_value(&mut self) -> Result<String, QueryError> {
+        if self.peek() != Some('"') {
+            let start = self.pos;
+            while !self.at_term_end() {
+                self.pos += 1;
+            }
+            return Ok(self.chars[start..self.pos].iter().collect());
+        }
+
+        let quote_start = self.pos;
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(QueryError::new("Unterminated quote", quote_start, self.pos));
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('\\') if matches!(self.chars.get(self.pos + 1), Some('"') | Some('\\')) => {
+                    value.push(self.chars[self.pos + 1]);
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        if !self.at_term_end() {
+            return Err(QueryError::new("Expected a space after the closing quote", self.pos, self.pos + 1));
+        }
+        Ok(value)
+    }
+}
+
+/// 解析した項目を AND で結んだ SQL の条件に変換する（項目がなければすべての画像）
+pub fn build_query(clauses: &[Clause]) -> Result<ImageQuery, QueryError> {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+    for clause in clauses {
+        conditions.push(build_clause(clause, &mut params)?);
+    }
+
+    let condition = if conditions.is_empty() { "1".to_string() } else { conditions.join(" AND ") };
+    Ok(ImageQuery { condition, params })
+}
+
+/// 1項目を SQL の条件に変換する
+/// 否定（"-" と "!="）は NULL の行も含めるよう NOT IFNULL((条件), 0) にする
+fn build_clause(clause: &Clause, params: &mut Vec<Value>) -> Result<String, QueryError> {
+    let (condition, negated) = match &clause.term {
+        Term::Text(text) => {
+            let pattern = like_pattern(text);
+            params.extend(std::iter::repeat(Value::Text(pattern)).take(3));
+            (
+                "(images.file_name LIKE ? ESCAPE '\\' OR images.comment LIKE ? ESCAPE '\\' OR EXISTS (
+                    SELECT 1 FROM image_tags it JOIN tags t ON t.id = it.tag_id
+                    WHERE it.image_id = images.id AND t.name LIKE ? ESCAPE '\\'))"
+                    .to_string(),
+                clause.negated,
+            )
+        }
+        Term::Filter { field, op, value } => {
+            if !field.is_ordered() && !matches!(op, Op::Eq | Op::Ne) {
+                return Err(QueryError::new(
+                    format!("Operator \"{}\" cannot be used with {}", op.sql(), field.name()),
+                    clause.op_start,
+                    clause.op_end,
+                ));
+            }
+            // "!=" は "=" の否定として扱う
+            let (op, negated) = if *op == Op::Ne { (Op::Eq, !clause.negated) } else { (*op, clause.negated) };
+            (build_filter(*field, op, value, clause, params)?, negated)
+        }
+    };
+
+    if negated {
+        Ok(format!("NOT IFNULL({}, 0)", condition))
+    } else {
+        Ok(condition)
+    }
+}
+
+/// 項目の条件を SQL に変換する（op は Ne 以外）
+fn build_filter(field: Field, op: Op, value: &str, clause: &Clause, params: &mut Vec<Value>) -> Result<String, QueryError> {
+    let value_error = |message: String| QueryError::new(message, clause.value_start, clause.value_end);
+
+    let condition = match field {
+        Field::Rating => {
+            let rating = value
+                .parse::<i64>()
+                .ok()
+                .filter(|r| (0..=5).contains(r))
+                .ok_or_else(|| value_error(format!("Rating must be an integer between 0 and 5: {}", value)))?;
+            params.push(Value::Integer(rating));
+            format!("(images.rating {} ?)", op.sql())
+        }
+        Field::Duration => {
+            let seconds = value
+                .parse::<f64>()
+                .ok()
+                .filter(|d| d.is_finite() && *d >= 0.0)
+                .ok_or_else(|| value_error(format!("Duration must be a number of seconds: {}", value)))?;
+            params.push(Value::Real(seconds));
+            format!("(images.duration_seconds {} ?)", op.sql())
+        }
+        Field::Width | Field::Height => {
+            let pixels = value
+                .parse::<i64>()
+                .ok()
+                .filter(|p| *p >= 0)
+                .ok_or_else(|| value_error(format!("{} must be a number of pixels: {}", field.name(), value)))?;
+            params.push(Value::Integer(pixels));
+            format!("(images.{} {} ?)", field.name(), op.sql())
+        }
+        Field::Taken => {
+            let date = parse_date_prefix(value).ok_or_else(|| {
+                value_error(format!("Invalid date \"{}\" (expected YYYY, YYYY-MM or YYYY-MM-DD)", value))
+            })?;
+            params.push(Value::Text(date.to_string()));
+            // 期間の単位（年・月・日）で比較する: taken:2024-05 は5月中、taken>2024-05 は6月以降
+            format!("(substr(images.taken_at, 1, {}) {} ?)", date.len(), op.sql())
+        }
+        Field::Favorite => {
+            let favorite = match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => 1,
+                "false" | "no" | "0" => 0,
+                _ => return Err(value_error(format!("Favorite must be true or false: {}", value))),
+            };
+            params.push(Value::Integer(favorite));
+            "(COALESCE(images.is_favorite, 0) = ?)".to_string()
+        }
+        Field::Type => {
+            let file_type = value.to_ascii_lowercase();
+            if file_type != "image" && file_type != "video" {
+                return Err(value_error(format!("Type must be image or video: {}", value)));
+            }
+            params.push(Value::Text(file_type));
+            "(images.file_type = ?)".to_string()
+        }
+        Field::Tag => {
+            // 階層タグは子孫のタグも一致させる（tag:places は places/japan も含む）
+            let segments: Vec<&str> = value.split('/').map(str::trim).collect();
+            if segments.iter().any(|s| s.is_empty()) {
+                return Err(value_error(format!("Tag has an empty level: {}", value)));
+            }
+            let name = segments.join("/");
+            params.extend(std::iter::repeat(Value::Text(name)).take(3));
+            "EXISTS (
+                SELECT 1 FROM image_tags it JOIN tags t ON t.id = it.tag_id
+                WHERE it.image_id = images.id
+                    AND (t.name = ? COLLATE NOCASE OR substr(t.name, 1, length(?) + 1) = (? || '/') COLLATE NOCASE))"
+                .to_string()
+        }
+        Field::Dir => {
+            params.extend(std::iter::repeat(Value::Text(value.to_string())).take(2));
+            "(images.directory_id IN (SELECT d.id FROM directories d WHERE d.name = ? COLLATE NOCASE OR d.path = ?))".to_string()
+        }
+        Field::Group => {
+            // 手動で画像を追加したグループの所属のみ（スマートグループは含まない）
+            params.push(Value::Text(value.to_string()));
+            "(images.id IN (
+                SELECT ig.image_id FROM image_groups ig JOIN groups g ON g.id = ig.group_id
+                WHERE g.name = ? COLLATE NOCASE))"
+                .to_string()
+        }
+        Field::Name | Field::Comment => {
+            let column = if field == Field::Name { "file_name" } else { "comment" };
+            params.push(Value::Text(like_pattern(value)));
+            format!("(images.{} LIKE ? ESCAPE '\\')", column)
+        }
+        Field::Camera => {
+            params.extend(std::iter::repeat(Value::Text(like_pattern(value))).take(2));
+            "(images.camera_make LIKE ? ESCAPE '\\' OR images.camera_model LIKE ? ESCAPE '\\')".to_string()
+        }
+        Field::Place => {
+            params.extend(std::iter::repeat(Value::Text(value.to_string())).take(4));
+            "(images.city = ? COLLATE NOCASE OR images.region = ? COLLATE NOCASE
+                OR images.country = ? COLLATE NOCASE OR images.country_code = ? COLLATE NOCASE)"
+                .to_string()
+        }
+    };
+    Ok(condition)
+}
+
+/// "YYYY" / "YYYY-MM" / "YYYY-MM-DD" の形式で、存在する日付なら値を返す
+fn parse_date_prefix(value: &str) -> Option<&str> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    let valid = match parts.as_slice() {
+        [year] => digits(year, 4),
+        [year, month] => digits(year, 4) && digits(month, 2) && (1..=12).contains(&month.parse::<u32>().ok()?),
+        [_, _, _] => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() && value.len() == 10,
+        _ => false,
+    };
+    valid.then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(field: Field, op: Op, value: &str) -> Term {
+        Term::Filter { field, op, value: value.to_string() }
+    }
+
+    fn terms(input: &str) -> Vec<(bool, Term)> {
+        parse_query(input).unwrap().into_iter().map(|c| (c.negated, c.term)).collect()
+    }
+
+    #[test]
+    fn parses_fields_operators_and_negation() {
+        assert_eq!(
+            terms("rating>=4 tag:travel -tag:blurry type:video duration>30 taken:2024-05"),
+            vec![
+                (false, filter(Field::Rating, Op::Ge, "4")),
+                (false, filter(Field::Tag, Op::Eq, "travel")),
+                (true, filter(Field::Tag, Op::Eq, "blurry")),
+                (false, filter(Field::Type, Op::Eq, "video")),
+                (false, filter(Field::Duration, Op::Gt, "30")),
+                (false, filter(Field::Taken, Op::Eq, "2024-05")),
+            ]
+        );
+        assert_eq!(
+            terms("width<=1920 height<1080 rating!=0 Rating=5"),
+            vec![
+                (false, filter(Field::Width, Op::Le, "1920")),
+                (false, filter(Field::Height, Op::Lt, "1080")),
+                (false, filter(Field::Rating, Op::Ne, "0")),
+                (false, filter(Field::Rating, Op::Eq, "5")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values_and_text() {
+        assert_eq!(
+            terms(r#"dir:"Camera Roll" group:"Trip" "東京 タワー" sunset -"say \"hi\"""#),
+            vec![
+                (false, filter(Field::Dir, Op::Eq, "Camera Roll")),
+                (false, filter(Field::Group, Op::Eq, "Trip")),
+                (false, Term::Text("東京 タワー".to_string())),
+                (false, Term::Text("sunset".to_string())),
+                (true, Term::Text(r#"say "hi""#.to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_value_positions() {
+        let clauses = parse_query(r#"  -dir:"Camera Roll""#).unwrap();
+        assert_eq!((clauses[0].op_start, clauses[0].op_end), (6, 7));
+        assert_eq!((clauses[0].value_start, clauses[0].value_end), (7, 20));
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_positions() {
+        let err = parse_query("rating>=4 colour:red").unwrap_err();
+        assert_eq!(err, QueryError::new("Unknown field \"colour\"", 10, 16));
+        assert_eq!(err.to_string(), "Unknown field \"colour\" (column 11)");
+
+        assert_eq!(parse_query(r#"dir:"Camera Roll"#).unwrap_err(), QueryError::new("Unterminated quote", 4, 16));
+        assert_eq!(parse_query("tag: travel").unwrap_err(), QueryError::new("Missing value after \"tag:\"", 0, 4));
+        assert_eq!(parse_query("sunset -").unwrap_err(), QueryError::new("Expected a search term after \"-\"", 7, 8));
+        assert_eq!(
+            parse_query(r#"group:"Trip"2024"#).unwrap_err(),
+            QueryError::new("Expected a space after the closing quote", 12, 13)
+        );
+    }
+
+    #[test]
+    fn reports_invalid_values_with_positions() {
+        let err = compile_query("tag:travel rating>=six").unwrap_err();
+        assert_eq!((err.start, err.end), (19, 22));
+        assert!(err.message.starts_with("Rating must be an integer"));
+
+        let err = compile_query("taken:2024-13").unwrap_err();
+        assert_eq!((err.start, err.end), (6, 13));
+        assert!(compile_query("taken:2023-02-29").is_err());
+        assert!(compile_query("taken:2024-02-29").is_ok());
+        assert!(compile_query("type:audio").is_err());
+        assert!(compile_query("duration>-1").is_err());
+        assert!(compile_query("tag:places//tokyo").is_err());
+
+        let err = compile_query("tag>travel").unwrap_err();
+        assert_eq!(err, QueryError::new("Operator \">\" cannot be used with tag", 3, 4));
+    }
+
+    #[test]
+    fn builds_parameterized_sql() {
+        let query = compile_query("rating>=4 -type:video taken<=2024-05").unwrap();
+        assert_eq!(
+            query.condition,
+            "(images.rating >= ?) AND NOT IFNULL((images.file_type = ?), 0) AND (substr(images.taken_at, 1, 7) <= ?)"
+        );
+        assert_eq!(
+            query.params,
+            vec![Value::Integer(4), Value::Text("video".to_string()), Value::Text("2024-05".to_string())]
+        );
+
+        // "!=" は "=" の否定、"-" と重ねると肯定になる
+        assert_eq!(compile_query("rating!=0").unwrap().condition, "NOT IFNULL((images.rating = ?), 0)");
+        assert_eq!(compile_query("-rating!=0").unwrap().condition, "(images.rating = ?)");
+
+        let query = compile_query("").unwrap();
+        assert_eq!(query, ImageQuery { condition: "1".to_string(), params: vec![] });
+
+        let query = compile_query("name:100%_done").unwrap();
+        assert_eq!(query.params, vec![Value::Text("%100\\%\\_done%".to_string())]);
+    }
+
+    /// 変換した SQL を最小限のスキーマで実行し、一致した画像IDを返す
+    fn run(conn: &rusqlite::Connection, input: &str) -> Vec<i64> {
+        let query = compile_query(input).unwrap();
+        let mut stmt = conn
+            .prepare(&format!("SELECT id FROM images WHERE {} ORDER BY id", query.condition))
+            .unwrap();
+        let ids = stmt
+            .query_map(rusqlite::params_from_iter(query.params), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<i64>, _>>()
+            .unwrap();
+        ids
+    }
+
+    #[test]
+    fn executes_against_library_schema() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "
+            CREATE TABLE directories (id INTEGER PRIMARY KEY, path TEXT, name TEXT);
+            CREATE TABLE images (
+                id INTEGER PRIMARY KEY, file_name TEXT, file_type TEXT, comment TEXT, rating INTEGER DEFAULT 0,
+                is_favorite INTEGER DEFAULT 0, duration_seconds REAL, width INTEGER, height INTEGER, taken_at TEXT,
+                directory_id INTEGER, camera_make TEXT, camera_model TEXT, country_code TEXT, country TEXT, region TEXT, city TEXT
+            );
+            CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT UNIQUE);
+            CREATE TABLE image_tags (image_id INTEGER, tag_id INTEGER, position INTEGER);
+            CREATE TABLE groups (id INTEGER PRIMARY KEY, name TEXT);
+            CREATE TABLE image_groups (image_id INTEGER, group_id INTEGER);
+
+            INSERT INTO directories VALUES (1, '/photos/Camera Roll', 'Camera Roll'), (2, '/photos/Scans', 'Scans');
+            INSERT INTO images (id, file_name, file_type, comment, rating, is_favorite, duration_seconds, taken_at, directory_id, city)
+            VALUES
+                (1, 'beach.jpg', 'image', 'sunset at the beach', 5, 1, NULL, '2024-05-03 18:00:00', 1, 'Tokyo'),
+                (2, 'clip.mp4', 'video', NULL, 4, 0, 45.5, '2024-05-20 09:00:00', 1, NULL),
+                (3, 'short.mp4', 'video', NULL, 2, 0, 10.0, '2024-06-01 12:00:00', 2, NULL),
+                (4, 'scan.png', 'image', 'blurry', 0, 0, NULL, NULL, 2, NULL);
+            INSERT INTO tags VALUES (1, 'travel'), (2, 'travel/japan'), (3, 'blurry');
+            INSERT INTO image_tags VALUES (1, 2, 0), (2, 1, 0), (2, 3, 1), (4, 3, 0);
+            INSERT INTO groups VALUES (1, 'Trip');
+            INSERT INTO image_groups VALUES (1, 1), (3, 1);
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(run(&conn, "rating>=4 tag:travel -tag:blurry"), vec![1]);
+        assert_eq!(run(&conn, "tag:Travel"), vec![1, 2]);
+        assert_eq!(run(&conn, "type:video duration>30"), vec![2]);
+        assert_eq!(run(&conn, "-duration>30"), vec![1, 3, 4]);
+        assert_eq!(run(&conn, "taken:2024-05"), vec![1, 2]);
+        assert_eq!(run(&conn, "taken>2024-05"), vec![3]);
+        assert_eq!(run(&conn, "-taken:2024"), vec![4]);
+        assert_eq!(run(&conn, r#"dir:"Camera Roll" group:"trip""#), vec![1]);
+        assert_eq!(run(&conn, "favorite:true place:tokyo"), vec![1]);
+        assert_eq!(run(&conn, "blurry"), vec![2, 4]);
+        assert_eq!(run(&conn, "SUNSET"), vec![1]);
+        assert_eq!(run(&conn, ""), vec![1, 2, 3, 4]);
+    }
+}
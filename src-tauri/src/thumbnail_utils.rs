@@ -0,0 +1,348 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// サムネイル生成の定数
+const THUMBNAIL_JPEG_QUALITY: u8 = 85; // 0-100, 高いほど高品質
+
+/// 書き込み途中の一時ファイルの末尾（ffmpeg が出力形式を拡張子で判定するため .jpg で終える）
+const TEMP_FILE_SUFFIX: &str = ".tmp.jpg";
+
+/// 一時ファイル名を呼び出しごとに一意にするための連番
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// サムネイルのサイズ（長辺のピクセル数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailSize {
+    /// リスト表示用
+    Small,
+    /// グリッド表示用（既存の動画サムネイルと同じ大きさ）
+    Medium,
+    /// 詳細表示・プレビュー用
+    Large,
+}
+
+impl ThumbnailSize {
+    pub fn max_dimension(self) -> u32 {
+        match self {
+            ThumbnailSize::Small => 200,
+            ThumbnailSize::Medium => 400,
+            ThumbnailSize::Large => 800,
+        }
+    }
+}
+
+/// 元ファイルの識別キー（サイズと更新日時）
+/// 元ファイルが差し替えられるとキーが変わり、サムネイルが再生成される
+/// content_hash はスキャン時にサイズ・更新日時が変わったファイルでのみ再計算されるため、
+/// キーに含めても差し替えの検出精度は上がらず、生成のたびにDB参照が増えるだけなので使わない
+pub fn source_key(file_path: &str) -> Result<String, String> {
+    let metadata = fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read file metadata: {}: {}", file_path, e))?;
+    let mtime = crate::fs_utils::get_mtime_secs(&metadata).unwrap_or(0);
+    Ok(format!("{}-{}", metadata.len(), mtime))
+}
+
+/// 画像ごとのキャッシュディレクトリ（{サムネイルディレクトリ}/{画像ID}/）
+fn image_thumbnail_dir(thumbnail_dir: &Path, image_id: i64) -> PathBuf {
+    thumbnail_dir.join(image_id.to_string())
+}
+
+/// サムネイルのキャッシュファイル名（{長辺}_{識別キー}.jpg）
+fn thumbnail_file_name(size: ThumbnailSize, key: &str) -> String {
+    format!("{}_{}.jpg", size.max_dimension(), key)
+}
+
+/// キャッシュファイル名を (長辺, 識別キー) に分解
+fn parse_thumbnail_file_name(file_name: &str) -> Option<(u32, &str)> {
+    let (size, key) = file_name.strip_suffix(".jpg")?.split_once('_')?;
+    Some((size.parse().ok()?, key))
+}
+
+/// 画像ごとのディレクトリに分ける前の形式（{画像ID}.jpg、{画像ID}_{長辺}_{識別キー}.jpg）か
+fn is_legacy_thumbnail_file_name(file_name: &str) -> bool {
+    file_name
+        .strip_suffix(".jpg")
+        .and_then(|stem| stem.split('_').next())
+        .is_some_and(|id| id.parse::<i64>().is_ok())
+}
+
+/// 書き込み途中の一時ファイルか（旧形式の "*.jpg.tmp" を含む）
+fn is_temp_file_name(file_name: &str) -> bool {
+    file_name.ends_with(TEMP_FILE_SUFFIX) || file_name.ends_with(".jpg.tmp")
+}
+
+/// 一時ファイルに書き出してから出力先に置き換える（失敗時は一時ファイルを削除）
+/// 一時ファイル名は呼び出しごとに一意にし、同じサムネイルを同時に生成しても互いの書き込みを壊さない
+fn write_via_temp_file(
+    output_path: &Path,
+    write: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let file_name = output_path
+        .file_name()
+        .ok_or("Invalid thumbnail path")?
+        .to_string_lossy();
+    let temp_path = output_path.with_file_name(format!(
+        "{}.{}-{}{}",
+        file_name,
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed),
+        TEMP_FILE_SUFFIX
+    ));
+
+    let result = write(&temp_path).and_then(|()| {
+        fs::rename(&temp_path, output_path).map_err(|e| format!("Failed to save thumbnail: {}", e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 同じ画像・サイズの古いサムネイル（識別キーが異なるもの）を削除
+/// 画像ごとのディレクトリだけを見るため、キャッシュ全体の件数に依存しない
+/// 他の呼び出しが書き込み中の一時ファイルには触れない
+fn remove_stale_thumbnails(image_dir: &Path, size: ThumbnailSize, current: &str) {
+    let prefix = format!("{}_", size.max_dimension());
+    let Ok(entries) = fs::read_dir(image_dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix) && name != current && !is_temp_file_name(&name) {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                eprintln!("Warning: Failed to remove stale thumbnail {}: {}", name, e);
+            }
+        }
+    }
+}
+
+/// 画像を読み込み、EXIFの回転情報を適用
+fn open_oriented(image_path: &str) -> Result<DynamicImage, String> {
+    let mut decoder = ImageReader::open(image_path)
+        .map_err(|e| format!("Failed to open image: {}: {}", image_path, e))?
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to detect image format: {}: {}", image_path, e))?
+        .into_decoder()
+        .map_err(|e| format!("Failed to decode image: {}: {}", image_path, e))?;
+
+    let orientation = decoder
+        .orientation()
+        .map_err(|e| format!("Failed to read image orientation: {}: {}", image_path, e))?;
+
+    let mut img = DynamicImage::from_decoder(decoder)
+        .map_err(|e| format!("Failed to decode image: {}: {}", image_path, e))?;
+    img.apply_orientation(orientation);
+
+    Ok(img)
+}
+
+/// 画像（JPG/PNG/GIF/WebP）のサムネイルをJPEGで書き出し
+/// 元画像が指定サイズより小さい場合は拡大せずにそのまま変換する
+pub fn generate_image_thumbnail(
+    image_path: &str,
+    output_path: &Path,
+    max_dimension: u32,
+) -> Result<(), String> {
+    // ファイルパスの検証（セキュリティ対策）
+    let image_file = PathBuf::from(image_path);
+    if !image_file.exists() {
+        return Err(format!("Image file not found: {}", image_path));
+    }
+    if !image_file.is_file() {
+        return Err(format!("Path is not a file: {}", image_path));
+    }
+
+    let img = open_oriented(image_path)?;
+    let img = if img.width() > max_dimension || img.height() > max_dimension {
+        img.thumbnail(max_dimension, max_dimension)
+    } else {
+        img
+    };
+
+    // 書き込み途中のファイルを読まれないよう一時ファイル経由で保存
+    write_via_temp_file(output_path, |temp_path| {
+        let file = fs::File::create(temp_path)
+            .map_err(|e| format!("Failed to create thumbnail file: {}", e))?;
+
+        // JPEGはアルファチャンネル非対応のためRGBに変換
+        let encoder = JpegEncoder::new_with_quality(BufWriter::new(file), THUMBNAIL_JPEG_QUALITY);
+        img.to_rgb8()
+            .write_with_encoder(encoder)
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))
+    })
+}
+
+/// 指定した画像・動画のサムネイルを取得（キャッシュになければ生成）
+/// 元ファイルのサイズ・更新日時が変わっていれば再生成し、古いサムネイルは削除する
+pub fn ensure_thumbnail(
+    file_path: &str,
+    file_type: &str,
+    image_id: i64,
+    size: ThumbnailSize,
+) -> Result<String, String> {
+    let image_dir = image_thumbnail_dir(&crate::video_utils::get_thumbnail_dir()?, image_id);
+    let file_name = thumbnail_file_name(size, &source_key(file_path)?);
+    let output_path = image_dir.join(&file_name);
+
+    // 同じ元ファイルのサムネイルが既に存在する場合はスキップ
+    if !output_path.exists() {
+        fs::create_dir_all(&image_dir)
+            .map_err(|e| format!("Failed to create thumbnail directory: {}", e))?;
+        if file_type == "video" {
+            // ffmpeg がタイムアウト・失敗で途中までしか書けなかったファイルを残さないよう一時ファイル経由で保存
+            write_via_temp_file(&output_path, |temp_path| {
+                crate::video_utils::generate_thumbnail_to(
+                    file_path,
+                    temp_path,
+                    crate::video_utils::THUMBNAIL_TIMESTAMP_SECONDS,
+                    size.max_dimension(),
+                )
+            })?;
+        } else {
+            generate_image_thumbnail(file_path, &output_path, size.max_dimension())?;
+        }
+        remove_stale_thumbnails(&image_dir, size, &file_name);
+    }
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// 画像IDを指定してサムネイルのパスを取得（なければ生成）
+#[tauri::command]
+pub async fn get_thumbnail(image_id: i64, size: Option<ThumbnailSize>) -> Result<String, String> {
+    use rusqlite::Connection;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let (file_path, file_type): (String, Option<String>) = conn
+        .query_row(
+            "SELECT file_path, file_type FROM images WHERE id = ?",
+            rusqlite::params![image_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Image not found: {}", e))?;
+
+    ensure_thumbnail(
+        &file_path,
+        file_type.as_deref().unwrap_or("image"),
+        image_id,
+        size.unwrap_or(ThumbnailSize::Medium),
+    )
+}
+
+/**
+ * サムネイルキャッシュのクリーンアップ結果
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThumbnailGcSummary {
+    pub files_removed: i64,
+    pub bytes_reclaimed: u64,
+}
+
+/// キャッシュファイルを削除して集計に加える
+fn remove_cached_file(path: &Path, summary: &mut ThumbnailGcSummary) {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    match fs::remove_file(path) {
+        Ok(()) => {
+            summary.files_removed += 1;
+            summary.bytes_reclaimed += size;
+        }
+        Err(e) => eprintln!("Warning: Failed to remove thumbnail {}: {}", path.display(), e),
+    }
+}
+
+/// サムネイルキャッシュから不要なファイルを削除
+/// - DBに存在しない画像のサムネイル（画像ごとのディレクトリごと削除）
+/// - 元ファイルが差し替えられて古くなったサムネイル（元ファイルが見つからない場合は残す）
+/// - 旧形式（ディレクトリ直下の {画像ID}.jpg・{画像ID}_{長辺}_{識別キー}.jpg）のサムネイルと書き込み途中の一時ファイル
+#[tauri::command]
+pub async fn gc_thumbnails() -> Result<ThumbnailGcSummary, String> {
+    use rusqlite::Connection;
+    use std::collections::HashMap;
+
+    let db_path = crate::db::get_db_path()?;
+    let conn = Connection::open(&db_path)
+        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    let image_paths: HashMap<i64, String> = {
+        let mut stmt = conn
+            .prepare("SELECT id, file_path FROM images")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query images: {}", e))?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("Failed to collect images: {}", e))?;
+        rows
+    };
+
+    let thumbnail_dir = crate::video_utils::get_thumbnail_dir()?;
+    let entries = fs::read_dir(&thumbnail_dir)
+        .map_err(|e| format!("Failed to read thumbnail directory: {}", e))?;
+
+    let mut summary = ThumbnailGcSummary {
+        files_removed: 0,
+        bytes_reclaimed: 0,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+
+        if !path.is_dir() {
+            if is_temp_file_name(&name) || is_legacy_thumbnail_file_name(&name) {
+                remove_cached_file(&path, &mut summary);
+            }
+            continue;
+        }
+
+        // 認識できないディレクトリには触れない
+        let Ok(image_id) = name.parse::<i64>() else {
+            continue;
+        };
+        let Ok(files) = fs::read_dir(&path) else {
+            continue;
+        };
+
+        // 元ファイルの識別キーは画像ごとに1回だけ取得
+        let image_path = image_paths.get(&image_id);
+        let current_key = image_path.and_then(|p| source_key(p).ok());
+
+        for file in files.flatten() {
+            let file_name = file.file_name().to_string_lossy().to_string();
+            let remove = if is_temp_file_name(&file_name) || image_path.is_none() {
+                true
+            } else {
+                match parse_thumbnail_file_name(&file_name) {
+                    // 認識できないファイルには触れない
+                    None => false,
+                    Some((_, key)) => current_key.as_deref().is_some_and(|current| current != key),
+                }
+            };
+
+            if remove {
+                remove_cached_file(&file.path(), &mut summary);
+            }
+        }
+
+        // 空になったディレクトリは削除（ファイルが残っていれば失敗するだけ）
+        let _ = fs::remove_dir(&path);
+    }
+
+    println!(
+        "Thumbnail GC: removed {} files, reclaimed {} bytes",
+        summary.files_removed, summary.bytes_reclaimed
+    );
+
+    Ok(summary)
+}
@@ -1,12 +1,25 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::fs;
 use serde::{Deserialize, Serialize};
 
 // サムネイル生成の定数
-const THUMBNAIL_TIMESTAMP_SECONDS: f64 = 3.0; // 冒頭は黒画面が多いため3秒目から抽出
+pub const THUMBNAIL_TIMESTAMP_SECONDS: f64 = 3.0; // 冒頭は黒画面が多いため3秒目から抽出
 const THUMBNAIL_JPEG_QUALITY: &str = "2"; // 1-31, 低いほど高品質
 
+// ffprobe/ffmpegの1ファイルあたりのタイムアウト（破損ファイルでスキャンが止まらないように）
+const PROCESS_TIMEOUT: Duration = Duration::from_secs(30);
+
+// メタデータ抽出の並列数の上限
+const MAX_METADATA_WORKERS: usize = 16;
+
+// メタデータ抽出の並列数（0は自動 = CPUコア数）
+static METADATA_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VideoMetadata {
     pub duration_seconds: f64,
@@ -14,6 +27,10 @@ pub struct VideoMetadata {
     pub height: i32,
     pub video_codec: String,
     pub audio_codec: Option<String>,
+    /// 撮影日時（formatタグの creation_time をローカル時刻の "YYYY-MM-DD HH:MM:SS" に変換）
+    pub creation_time: Option<String>,
+    /// 撮影位置（location タグ、ISO 6709）
+    pub location: Option<crate::geo_utils::GeoLocation>,
 }
 
 /// ffmpegバイナリのパスを検出
@@ -41,6 +58,75 @@ pub fn find_ffmpeg() -> Option<PathBuf> {
     None
 }
 
+/// 外部プロセスを実行し、タイムアウトした場合は強制終了してエラーを返す
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output, String> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // 出力が多い場合にパイプが詰まってプロセスが止まらないよう、別スレッドで読み続ける
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let stdout_reader = thread::spawn(move || read_all(stdout));
+    let stderr_reader = thread::spawn(move || read_all(stderr));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {} seconds", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// パイプの内容をすべて読み込む
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buffer);
+    }
+    buffer
+}
+
+/// メタデータ抽出（ffprobe等）の並列数を取得
+pub fn metadata_worker_count() -> usize {
+    match METADATA_WORKERS.load(Ordering::Relaxed) {
+        0 => crate::jobs::worker_count(),
+        count => count,
+    }
+}
+
+/// メタデータ抽出の並列数を取得（0は自動）
+#[tauri::command]
+pub fn get_metadata_workers() -> usize {
+    METADATA_WORKERS.load(Ordering::Relaxed)
+}
+
+/// メタデータ抽出の並列数を設定（省略または0で自動）
+#[tauri::command]
+pub fn set_metadata_workers(count: Option<usize>) -> Result<usize, String> {
+    let count = count.unwrap_or(0);
+    if count > MAX_METADATA_WORKERS {
+        return Err(format!("Worker count must be between 0 and {}", MAX_METADATA_WORKERS));
+    }
+    METADATA_WORKERS.store(count, Ordering::Relaxed);
+    Ok(metadata_worker_count())
+}
+
 /// ffmpegが利用可能かチェック
 #[tauri::command]
 pub fn check_ffmpeg_available() -> Result<String, String> {
@@ -86,15 +172,17 @@ pub fn extract_video_metadata(video_path: &str) -> Result<VideoMetadata, String>
     }
 
     // ffprobeでJSON形式の情報取得
-    let output = Command::new(ffprobe_path)
-        .args(&[
+    let mut command = Command::new(ffprobe_path);
+    command
+        .args([
             "-v", "quiet",
             "-print_format", "json",
             "-show_format",
             "-show_streams",
             video_path,
         ])
-        .output()
+        .stdin(Stdio::null());
+    let output = output_with_timeout(&mut command, PROCESS_TIMEOUT)
         .map_err(|e| format!("Failed to execute ffprobe: {}", e))?;
 
     if !output.status.success() {
@@ -132,9 +220,30 @@ pub fn extract_video_metadata(video_path: &str) -> Result<VideoMetadata, String>
         height: video_stream["height"].as_i64().unwrap_or(0) as i32,
         video_codec: video_stream["codec_name"].as_str().unwrap_or("unknown").to_string(),
         audio_codec: audio_stream.map(|s| s["codec_name"].as_str().unwrap_or("unknown").to_string()),
+        creation_time: json["format"]["tags"]["creation_time"]
+            .as_str()
+            .or_else(|| video_stream["tags"]["creation_time"].as_str())
+            .and_then(parse_creation_time),
+        location: ["location", "com.apple.quicktime.location.ISO6709"]
+            .iter()
+            .find_map(|key| json["format"]["tags"][key].as_str())
+            .and_then(crate::geo_utils::parse_iso6709),
     })
 }
 
+/// ffprobeの creation_time（UTC、RFC 3339）をローカル時刻に変換
+/// 未設定のカメラが書き込む1970年・1904年などの値は無効として扱う
+fn parse_creation_time(value: &str) -> Option<String> {
+    use chrono::Datelike;
+
+    let utc = chrono::DateTime::parse_from_rfc3339(value).ok()?;
+    let local = utc.with_timezone(&chrono::Local);
+    if local.year() <= 1970 {
+        return None;
+    }
+    Some(local.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
 /// サムネイルディレクトリを取得（なければ作成）
 pub fn get_thumbnail_dir() -> Result<PathBuf, String> {
     let db_path = crate::db::get_db_path()?;
@@ -149,12 +258,13 @@ pub fn get_thumbnail_dir() -> Result<PathBuf, String> {
     Ok(thumbnail_dir)
 }
 
-/// 動画のサムネイルを生成
-pub fn generate_thumbnail(
+/// 動画の指定時刻のフレームを、長辺max_sizeのサムネイルとして書き出し
+pub fn generate_thumbnail_to(
     video_path: &str,
-    image_id: i64,
+    thumbnail_path: &Path,
     timestamp_seconds: f64,
-) -> Result<String, String> {
+    max_size: u32,
+) -> Result<(), String> {
     // ファイルパスの検証（セキュリティ対策）
     let video_file = PathBuf::from(video_path);
     if !video_file.exists() {
@@ -167,30 +277,24 @@ pub fn generate_thumbnail(
     let ffmpeg_path = find_ffmpeg()
         .ok_or("FFmpeg not found")?;
 
-    let thumbnail_dir = get_thumbnail_dir()?;
-    let thumbnail_path = thumbnail_dir.join(format!("{}.jpg", image_id));
-
-    // サムネイルが既に存在する場合はスキップ
-    if thumbnail_path.exists() {
-        return Ok(thumbnail_path.to_string_lossy().to_string());
-    }
-
     // unwrap()を使わずエラーハンドリング
     let thumbnail_path_str = thumbnail_path
         .to_str()
         .ok_or("Invalid thumbnail path: contains invalid UTF-8")?;
 
     // ffmpegコマンド実行
-    let output = Command::new(ffmpeg_path)
-        .args(&[
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args([
             "-ss", &timestamp_seconds.to_string(),
             "-i", video_path,
             "-vframes", "1",
-            "-vf", "scale=400:400:force_original_aspect_ratio=decrease",
+            "-vf", &format!("scale={0}:{0}:force_original_aspect_ratio=decrease", max_size),
             "-q:v", THUMBNAIL_JPEG_QUALITY,
             thumbnail_path_str,
         ])
-        .output()
+        .stdin(Stdio::null());
+    let output = output_with_timeout(&mut command, PROCESS_TIMEOUT)
         .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
 
     if !output.status.success() {
@@ -198,13 +302,72 @@ pub fn generate_thumbnail(
         return Err(format!("FFmpeg thumbnail generation failed: {}", error));
     }
 
-    Ok(thumbnail_path.to_string_lossy().to_string())
+    Ok(())
 }
 
+/// 動画のサムネイルを生成（キャッシュ済みで元ファイルに変更がなければ再利用）
 #[tauri::command]
 pub async fn generate_video_thumbnail(
     video_path: String,
     image_id: i64,
 ) -> Result<String, String> {
-    generate_thumbnail(&video_path, image_id, THUMBNAIL_TIMESTAMP_SECONDS)
+    crate::thumbnail_utils::ensure_thumbnail(
+        &video_path,
+        "video",
+        image_id,
+        crate::thumbnail_utils::ThumbnailSize::Medium,
+    )
+}
+
+/// 知覚ハッシュ用の代表フレームの時刻を決定
+/// 通常はサムネイルと同じ3秒目、短い動画では中間のフレームを使う
+pub fn representative_frame_timestamp(duration_seconds: Option<f64>) -> f64 {
+    match duration_seconds {
+        Some(duration) if duration > 0.0 => THUMBNAIL_TIMESTAMP_SECONDS.min(duration / 2.0),
+        _ => 0.0,
+    }
+}
+
+/// 指定時刻のフレームを縮小PNGとして標準出力から取得（ファイルは作らない）
+pub fn extract_frame_png(video_path: &str, timestamp_seconds: f64) -> Result<Vec<u8>, String> {
+    // ファイルパスの検証（セキュリティ対策）
+    let video_file = PathBuf::from(video_path);
+    if !video_file.exists() {
+        return Err(format!("Video file not found: {}", video_path));
+    }
+    if !video_file.is_file() {
+        return Err(format!("Path is not a file: {}", video_path));
+    }
+
+    let ffmpeg_path = find_ffmpeg()
+        .ok_or("FFmpeg not found")?;
+
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args([
+            "-v", "error",
+            "-ss", &timestamp_seconds.to_string(),
+            "-i", video_path,
+            "-vframes", "1",
+            "-vf", "scale=64:64:force_original_aspect_ratio=decrease",
+            "-f", "image2pipe",
+            "-vcodec", "png",
+            "-",
+        ])
+        .stdin(Stdio::null());
+    let output = output_with_timeout(&mut command, PROCESS_TIMEOUT)
+        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("FFmpeg frame extraction failed: {}", error));
+    }
+
+    Ok(output.stdout)
+}
+
+/// 動画の代表フレームから知覚ハッシュを計算
+pub fn compute_video_phash(video_path: &str, duration_seconds: Option<f64>) -> Result<String, String> {
+    let frame = extract_frame_png(video_path, representative_frame_timestamp(duration_seconds))?;
+    crate::phash_utils::compute_phash_from_bytes(&frame)
 }
@@ -0,0 +1,441 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+const NS_LR: &str = "http://ns.adobe.com/lightroom/1.0/";
+
+/// サイドカーに読み書きする項目
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SidecarMetadata {
+    /// xmp:Rating（0-5、-1 は「却下」として扱われるため 0 に丸める）
+    pub rating: Option<i64>,
+    /// タグ（階層タグは "/" 区切り）
+    /// dc:subject には末端の名前、lr:hierarchicalSubject には "|" 区切りのパスで書き出す
+    pub tags: Vec<String>,
+    /// dc:description（x-default）
+    pub description: Option<String>,
+}
+
+/// 読み込んだサイドカー
+#[derive(Debug, Clone)]
+pub struct XmpSidecar {
+    /// サイドカーの更新日時（UNIX秒）
+    pub mtime: i64,
+    pub metadata: SidecarMetadata,
+}
+
+/// 書き出すサイドカーのパス（darktable / digiKam 形式: "IMG_0001.JPG.xmp"）
+pub fn sidecar_path(file_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.xmp", file_path))
+}
+
+/// 取り込むサイドカーを探す
+/// "IMG_0001.JPG.xmp" を優先し、なければ Lightroom 形式の "IMG_0001.xmp" を探す
+/// "IMG_0001.xmp" は RAW+JPEG の組では RAW 側のものなので、書き出しには使わない
+pub fn find_sidecar(file_path: &str) -> Option<PathBuf> {
+    let primary = sidecar_path(file_path);
+    if primary.is_file() {
+        return Some(primary);
+    }
+    let alternate = Path::new(file_path).with_extension("xmp");
+    alternate.is_file().then_some(alternate)
+}
+
+/// サイドカーの更新日時（UNIX秒）
+pub fn sidecar_mtime(path: &Path) -> Option<i64> {
+    fs::metadata(path)
+        .ok()
+        .and_then(|m| crate::fs_utils::get_mtime_secs(&m))
+}
+
+/// ファイルのサイドカーを読み込む
+/// known_mtime と更新日時が同じ場合は前回から変わっていないため None を返す
+pub fn read_sidecar(file_path: &str, known_mtime: Option<i64>) -> Option<XmpSidecar> {
+    let path = find_sidecar(file_path)?;
+    let mtime = sidecar_mtime(&path)?;
+    if known_mtime == Some(mtime) {
+        return None;
+    }
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Warning: Failed to read XMP sidecar {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    match parse_sidecar(&content) {
+        Some(metadata) => Some(XmpSidecar { mtime, metadata }),
+        None => {
+            eprintln!("Warning: Failed to parse XMP sidecar {}", path.display());
+            None
+        }
+    }
+}
+
+/// XMP（RDF/XML）から評価・タグ・説明を取り出す
+pub fn parse_sidecar(xml: &str) -> Option<SidecarMetadata> {
+    let doc = roxmltree::Document::parse(xml).ok()?;
+    let mut metadata = SidecarMetadata::default();
+    let mut subjects: Vec<String> = Vec::new();
+    let mut hierarchical: Vec<String> = Vec::new();
+
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        // 属性形式（<rdf:Description xmp:Rating="3">）
+        if metadata.rating.is_none() {
+            metadata.rating = node
+                .attributes()
+                .find(|a| a.namespace() == Some(NS_XMP) && a.name() == "Rating")
+                .and_then(|a| parse_rating(a.value()));
+        }
+
+        let name = node.tag_name();
+        match (name.namespace(), name.name()) {
+            (Some(NS_XMP), "Rating") if metadata.rating.is_none() => {
+                metadata.rating = node.text().and_then(parse_rating);
+            }
+            (Some(NS_DC), "subject") => subjects.extend(list_items(node)),
+            (Some(NS_LR), "hierarchicalSubject") => {
+                hierarchical.extend(list_items(node).iter().map(|path| path.replace('|', "/")));
+            }
+            (Some(NS_DC), "description") if metadata.description.is_none() => {
+                // 言語別の値のうち x-default を優先
+                let items: Vec<_> = node
+                    .descendants()
+                    .filter(|d| d.is_element() && d.tag_name().namespace() == Some(NS_RDF) && d.tag_name().name() == "li")
+                    .collect();
+                metadata.description = items
+                    .iter()
+                    .find(|li| li.attribute(("http://www.w3.org/XML/1998/namespace", "lang")) == Some("x-default"))
+                    .or(items.first())
+                    .and_then(|li| li.text())
+                    .map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    // 階層タグを優先し、その各階層と同じ名前の dc:subject（Lightroom は親の名前も書き出す）は除く
+    let levels: Vec<&str> = hierarchical.iter().flat_map(|path| path.split('/')).map(str::trim).collect();
+    for tag in hierarchical.iter().chain(subjects.iter().filter(|s| !levels.contains(&s.as_str()))) {
+        if !metadata.tags.contains(tag) {
+            metadata.tags.push(tag.clone());
+        }
+    }
+
+    Some(metadata)
+}
+
+/// xmp:Rating を 0-5 に丸める（-1 = 却下は未評価扱い）
+fn parse_rating(value: &str) -> Option<i64> {
+    let rating: f64 = value.trim().parse().ok()?;
+    Some((rating.round() as i64).clamp(0, 5))
+}
+
+/// rdf:Bag / rdf:Seq の rdf:li を取り出す
+fn list_items(node: roxmltree::Node) -> Vec<String> {
+    node.descendants()
+        .filter(|d| d.is_element() && d.tag_name().namespace() == Some(NS_RDF) && d.tag_name().name() == "li")
+        .filter_map(|li| li.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// XMLのテキスト・属性値として書き出せるようエスケープ
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// サイドカーに書き出す要素（rdf_prefix は rdf 名前空間の接頭辞）
+fn render_properties(metadata: &SidecarMetadata, rdf_prefix: &str, indent: &str) -> String {
+    let mut out = String::new();
+
+    if let Some(rating) = metadata.rating {
+        out.push_str(&format!(
+            "\n{indent}<xmp:Rating xmlns:xmp=\"{NS_XMP}\">{rating}</xmp:Rating>"
+        ));
+    }
+
+    if !metadata.tags.is_empty() {
+        let mut leaves: Vec<&str> = Vec::new();
+        for leaf in metadata.tags.iter().filter_map(|tag| tag.rsplit('/').next()) {
+            if !leaves.contains(&leaf) {
+                leaves.push(leaf);
+            }
+        }
+        out.push_str(&render_bag("dc:subject", NS_DC, &leaves, rdf_prefix, indent));
+
+        let paths: Vec<String> = metadata.tags.iter().map(|tag| tag.replace('/', "|")).collect();
+        let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+        out.push_str(&render_bag("lr:hierarchicalSubject", NS_LR, &paths, rdf_prefix, indent));
+    }
+
+    if let Some(description) = &metadata.description {
+        out.push_str(&format!(
+            "\n{indent}<dc:description xmlns:dc=\"{NS_DC}\">\n{indent} <{rdf_prefix}:Alt>\n{indent}  <{rdf_prefix}:li xml:lang=\"x-default\">{}</{rdf_prefix}:li>\n{indent} </{rdf_prefix}:Alt>\n{indent}</dc:description>",
+            escape_xml(description)
+        ));
+    }
+
+    out
+}
+
+/// rdf:Bag の要素（qname の接頭辞を namespace で宣言する）
+fn render_bag(qname: &str, namespace: &str, items: &[&str], rdf_prefix: &str, indent: &str) -> String {
+    let prefix = qname.split_once(':').map(|(p, _)| p).unwrap_or(qname);
+    let mut out = format!("\n{indent}<{qname} xmlns:{prefix}=\"{namespace}\">\n{indent} <{rdf_prefix}:Bag>");
+    for item in items {
+        out.push_str(&format!("\n{indent}  <{rdf_prefix}:li>{}</{rdf_prefix}:li>", escape_xml(item)));
+    }
+    out.push_str(&format!("\n{indent} </{rdf_prefix}:Bag>\n{indent}</{qname}>"));
+    out
+}
+
+/// 新規サイドカーの内容
+fn render_new_sidecar(metadata: &SidecarMetadata) -> String {
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"{NS_RDF}\">\n  \
+         <rdf:Description rdf:about=\"\">{}\n  \
+         </rdf:Description>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>\n",
+        render_properties(metadata, "rdf", "   ")
+    )
+}
+
+/// 既存のサイドカーの評価・タグ・説明だけを置き換える
+/// 他のツールが書き込んだ内容（現像設定など）はそのまま残す
+fn merge_into_sidecar(existing: &str, metadata: &SidecarMetadata) -> Result<String, String> {
+    let doc = roxmltree::Document::parse(existing)
+        .map_err(|e| format!("Failed to parse existing sidecar: {}", e))?;
+
+    let description = doc
+        .descendants()
+        .find(|n| n.is_element() && n.tag_name().namespace() == Some(NS_RDF) && n.tag_name().name() == "Description")
+        .ok_or("Existing sidecar has no rdf:Description")?;
+
+    // 置き換える属性・要素の範囲（属性は直前の空白も含める）
+    let mut removals: Vec<Range<usize>> = Vec::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        let name = node.tag_name();
+        if name.namespace() == Some(NS_RDF) && name.name() == "Description" {
+            for attr in node.attributes() {
+                if attr.namespace() == Some(NS_XMP) && attr.name() == "Rating" {
+                    let range = attr.range();
+                    let start = existing[..range.start].trim_end().len();
+                    removals.push(start..range.end);
+                }
+            }
+        }
+        let replaced = matches!(
+            (name.namespace(), name.name()),
+            (Some(NS_XMP), "Rating")
+                | (Some(NS_DC), "subject")
+                | (Some(NS_DC), "description")
+                | (Some(NS_LR), "hierarchicalSubject")
+        );
+        if replaced && node.parent().is_some_and(|p| p.tag_name().namespace() == Some(NS_RDF)) {
+            let range = node.range();
+            let start = existing[..range.start].trim_end().len();
+            removals.push(start..range.end);
+        }
+    }
+
+    // rdf:Description の開始タグの終わり（自己終了タグなら閉じタグに展開する）
+    let element = &existing[description.range()];
+    let tag_end = start_tag_end(element).ok_or("Malformed rdf:Description start tag")?;
+    let self_closing = element[..tag_end].ends_with("/>");
+    let qname = element[1..]
+        .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .next()
+        .unwrap_or("rdf:Description");
+    let rdf_prefix = qname.split_once(':').map(|(p, _)| p).unwrap_or("rdf");
+    let insert_at = description.range().start + tag_end;
+
+    let properties = render_properties(metadata, rdf_prefix, "   ");
+    let insertion = if self_closing {
+        format!(">{}\n  </{}>", properties, qname)
+    } else {
+        properties
+    };
+
+    // 後ろから順に置き換える
+    let mut result = existing.to_string();
+    removals.sort_by_key(|r| std::cmp::Reverse(r.start));
+    let mut inserted = false;
+    for range in removals {
+        if !inserted && range.start < insert_at {
+            splice_insertion(&mut result, insert_at, self_closing, &insertion);
+            inserted = true;
+        }
+        result.replace_range(range, "");
+    }
+    if !inserted {
+        splice_insertion(&mut result, insert_at, self_closing, &insertion);
+    }
+
+    Ok(result)
+}
+
+/// 開始タグ直後に要素を挿入（自己終了タグの場合は "/>" を置き換える）
+fn splice_insertion(result: &mut String, insert_at: usize, self_closing: bool, insertion: &str) {
+    if self_closing {
+        result.replace_range(insert_at - 2..insert_at, insertion);
+    } else {
+        result.insert_str(insert_at, insertion);
+    }
+}
+
+/// 開始タグの終わり（'>' の直後）の位置。引用符内の '>' は無視する
+fn start_tag_end(element: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in element.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// サイドカー（常に "IMG_0001.JPG.xmp"）を書き出し、書き出したサイドカーの更新日時を返す
+/// 既存のサイドカーがあれば評価・タグ・説明のみ置き換え、なければ新規作成する
+pub fn write_sidecar(file_path: &str, metadata: &SidecarMetadata) -> Result<i64, String> {
+    let path = sidecar_path(file_path);
+
+    let content = match fs::read_to_string(&path) {
+        Ok(existing) => merge_into_sidecar(&existing, metadata)
+            .map_err(|e| format!("{}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => render_new_sidecar(metadata),
+        Err(e) => return Err(format!("Failed to read sidecar {}: {}", path.display(), e)),
+    };
+
+    // 書き込み途中のファイルを他のツールに読まれないよう一時ファイル経由で保存
+    let temp_path = path.with_extension("xmp.tmp");
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("Failed to write sidecar {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, &path)
+        .map_err(|e| format!("Failed to save sidecar {}: {}", path.display(), e))?;
+
+    sidecar_mtime(&path).ok_or_else(|| format!("Failed to read sidecar mtime: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n";
+    const FOOTER: &str = "\n </rdf:RDF>\n</x:xmpmeta>\n";
+
+    fn metadata(rating: Option<i64>, tags: &[&str], description: Option<&str>) -> SidecarMetadata {
+        SidecarMetadata {
+            rating,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            description: description.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn merge_replaces_attribute_rating() {
+        let existing = format!(
+            "{HEADER}  <rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmp:Rating=\"2\" xmp:Label=\"Red\">\n  </rdf:Description>{FOOTER}"
+        );
+        let merged = merge_into_sidecar(&existing, &metadata(Some(4), &[], None)).unwrap();
+
+        assert!(!merged.contains("xmp:Rating=\"2\""));
+        assert!(merged.contains("xmp:Label=\"Red\""));
+        assert_eq!(parse_sidecar(&merged).unwrap(), metadata(Some(4), &[], None));
+    }
+
+    #[test]
+    fn merge_expands_self_closing_description() {
+        let existing = format!(
+            "{HEADER}  <rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmp:Rating=\"1\"/>{FOOTER}"
+        );
+        let expected = metadata(Some(5), &["Travel"], Some("Sunset & sea"));
+        let merged = merge_into_sidecar(&existing, &expected).unwrap();
+
+        assert!(merged.contains("</rdf:Description>"));
+        assert!(!merged.contains("/>"));
+        assert_eq!(parse_sidecar(&merged).unwrap(), expected);
+    }
+
+    #[test]
+    fn merge_uses_the_existing_rdf_prefix() {
+        let existing = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n <r:RDF xmlns:r=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  <r:Description r:about=\"\">\n   <dc:subject xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><r:Bag><r:li>Old</r:li></r:Bag></dc:subject>\n  </r:Description>\n </r:RDF>\n</x:xmpmeta>\n";
+        let expected = metadata(None, &["Places/Japan"], None);
+        let merged = merge_into_sidecar(existing, &expected).unwrap();
+
+        assert!(merged.contains("<r:Bag>"));
+        assert!(!merged.contains("rdf:"));
+        assert!(!merged.contains("Old"));
+        assert_eq!(parse_sidecar(&merged).unwrap(), expected);
+    }
+
+    #[test]
+    fn merge_keeps_other_tools_content() {
+        let history = "   <darktable:history>\n    <rdf:Seq>\n     <rdf:li darktable:operation=\"exposure\" darktable:enabled=\"1\" darktable:params=\"0000&gt;00\"/>\n    </rdf:Seq>\n   </darktable:history>";
+        let existing = format!(
+            "{HEADER}  <rdf:Description rdf:about=\"\"\n    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n    xmlns:darktable=\"http://darktable.sf.net/\"\n    xmp:Rating=\"1\"\n    darktable:xmp_version=\"5\"\n    darktable:auto_presets_applied=\"1\">\n   <dc:subject>\n    <rdf:Seq>\n     <rdf:li>darktable|format|jpg</rdf:li>\n    </rdf:Seq>\n   </dc:subject>\n{history}\n  </rdf:Description>{FOOTER}"
+        );
+        let expected = metadata(Some(3), &["Travel"], None);
+        let merged = merge_into_sidecar(&existing, &expected).unwrap();
+
+        assert!(merged.contains(history));
+        assert!(merged.contains("darktable:xmp_version=\"5\""));
+        assert!(merged.contains("darktable:auto_presets_applied=\"1\">"));
+        assert!(merged.starts_with(HEADER));
+        assert!(merged.ends_with(FOOTER));
+        assert!(!merged.contains("darktable|format|jpg"));
+        assert_eq!(parse_sidecar(&merged).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_prefers_hierarchical_subjects() {
+        let xml = format!(
+            "{HEADER}  <rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:lr=\"http://ns.adobe.com/lightroom/1.0/\" xmp:Rating=\"-1\">\n   <dc:subject><rdf:Bag><rdf:li>Places</rdf:li><rdf:li>Japan</rdf:li><rdf:li>Tokyo</rdf:li><rdf:li>Travel</rdf:li></rdf:Bag></dc:subject>\n   <lr:hierarchicalSubject><rdf:Bag><rdf:li>Places|Japan|Tokyo</rdf:li></rdf:Bag></lr:hierarchicalSubject>\n   <dc:description><rdf:Alt><rdf:li xml:lang=\"ja\">夕日</rdf:li><rdf:li xml:lang=\"x-default\">Sunset</rdf:li></rdf:Alt></dc:description>\n  </rdf:Description>{FOOTER}"
+        );
+
+        assert_eq!(
+            parse_sidecar(&xml).unwrap(),
+            metadata(Some(0), &["Places/Japan/Tokyo", "Travel"], Some("Sunset"))
+        );
+    }
+
+    #[test]
+    fn write_sidecar_leaves_the_lightroom_sidecar_untouched() {
+        let dir = std::env::temp_dir().join(format!("xmp_utils_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("IMG_0001.JPG");
+        let raw_sidecar = dir.join("IMG_0001.xmp");
+        let raw_content = format!("{HEADER}  <rdf:Description rdf:about=\"\"/>{FOOTER}");
+        fs::write(&image, b"jpeg").unwrap();
+        fs::write(&raw_sidecar, &raw_content).unwrap();
+
+        let image_path = image.to_string_lossy().to_string();
+        let expected = metadata(Some(2), &["Travel"], None);
+        write_sidecar(&image_path, &expected).unwrap();
+
+        assert_eq!(fs::read_to_string(&raw_sidecar).unwrap(), raw_content);
+        let written = fs::read_to_string(sidecar_path(&image_path)).unwrap();
+        assert_eq!(parse_sidecar(&written).unwrap(), expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -63,6 +63,8 @@ function GroupAlbumView() {
         description: groupData.description,
         color: groupData.color,
         representative_image_id: groupData.representative_image_id,
+        image_count: groupData.image_count,
+        smart_query: groupData.smart_query,
       });
 
       // グループ内の画像IDを取得
@@ -89,8 +91,9 @@ function GroupAlbumView() {
     loadGroupData();
   }, [loadGroupData]);
 
-  // グループ内の画像のみをフィルタリング
-  const groupImages = allImages.filter((img) => groupImageIds.includes(img.id));
+  // グループ内の画像のみを、グループの並び順で取り出す
+  const imagesById = new Map(allImages.map((img) => [img.id, img]));
+  const groupImages = groupImageIds.flatMap((id) => imagesById.get(id) ?? []);
 
   // 代表画像選択モード開始
   const handleSetRepresentativeImage = () => {
@@ -1,7 +1,13 @@
 import { useEffect, useState } from 'react';
-import { Trash2, Send } from 'lucide-react';
-import { getGroupComments, addGroupComment, deleteGroupComment } from '../utils/tauri-commands';
-import type { GroupComment } from '../types/image';
+import { Trash2, Send, Pencil, Reply, History } from 'lucide-react';
+import {
+  getGroupComments,
+  addGroupComment,
+  updateGroupComment,
+  deleteGroupComment,
+  getGroupCommentHistory,
+} from '../utils/tauri-commands';
+import type { GroupComment, GroupCommentEdit } from '../types/image';
 import { useImageStore } from '../store/imageStore';
 
 type GroupCommentsProps = {
@@ -11,10 +17,19 @@ type GroupCommentsProps = {
 function GroupComments({ groupId }: GroupCommentsProps) {
   const showToast = useImageStore(s => s.showToast);
   const setError = useImageStore(s => s.setError);
+  const commentAuthor = useImageStore(s => s.commentAuthor);
   const [comments, setComments] = useState<GroupComment[]>([]);
   const [newComment, setNewComment] = useState('');
   const [isLoading, setIsLoading] = useState(true);
   const [isSubmitting, setIsSubmitting] = useState(false);
+  // 返信・編集中のコメントと入力中の本文
+  const [replyToId, setReplyToId] = useState<number | null>(null);
+  const [replyText, setReplyText] = useState('');
+  const [editingId, setEditingId] = useState<number | null>(null);
+  const [editText, setEditText] = useState('');
+  // 編集履歴を表示しているコメント
+  const [historyId, setHistoryId] = useState<number | null>(null);
+  const [history, setHistory] = useState<GroupCommentEdit[]>([]);
 
   // コメント読み込み
   const loadComments = async () => {
@@ -34,34 +49,100 @@ function GroupComments({ groupId }: GroupCommentsProps) {
     loadComments();
   }, [groupId]);
 
-  // コメント追加
+  // 本文のチェック（空なら null、長すぎる場合はトーストを出して null）
+  const checkCommentText = (text: string): string | null => {
+    const trimmed = text.trim();
+    if (!trimmed) return null;
+    if (trimmed.length > 500) {
+      showToast('Comment is too long (max 500 characters)', 'error');
+      return null;
+    }
+    return trimmed;
+  };
+
+  // コメント追加（parentCommentId を指定すると返信）
+  const submitComment = async (text: string, parentCommentId: number | null) => {
+    const trimmedComment = checkCommentText(text);
+    if (!trimmedComment) return false;
+
+    try {
+      setIsSubmitting(true);
+      await addGroupComment({
+        group_id: groupId,
+        comment: trimmedComment,
+        author: commentAuthor.trim() || null,
+        parent_comment_id: parentCommentId,
+      });
+      showToast(parentCommentId === null ? 'Comment added successfully' : 'Reply added successfully', 'success');
+      await loadComments();
+      return true;
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+      showToast('Failed to add comment', 'error');
+      return false;
+    } finally {
+      setIsSubmitting(false);
+    }
+  };
+
   const handleAddComment = async (e: React.FormEvent) => {
     e.preventDefault();
-    const trimmedComment = newComment.trim();
+    if (await submitComment(newComment, null)) {
+      setNewComment('');
+    }
+  };
+
+  const handleReply = async (e: React.FormEvent, parentCommentId: number) => {
+    e.preventDefault();
+    if (await submitComment(replyText, parentCommentId)) {
+      setReplyText('');
+      setReplyToId(null);
+    }
+  };
 
+  // コメント編集
+  const handleUpdateComment = async (e: React.FormEvent, commentId: number) => {
+    e.preventDefault();
+    const trimmedComment = checkCommentText(editText);
     if (!trimmedComment) return;
-    if (trimmedComment.length > 500) {
-      showToast('Comment is too long (max 500 characters)', 'error');
-      return;
-    }
 
     try {
       setIsSubmitting(true);
-      await addGroupComment({ group_id: groupId, comment: trimmedComment });
-      setNewComment('');
-      showToast('Comment added successfully', 'success');
+      await updateGroupComment(commentId, trimmedComment);
+      setEditingId(null);
+      if (historyId === commentId) setHistoryId(null);
+      showToast('Comment updated successfully', 'success');
       await loadComments();
     } catch (err) {
       setError(err instanceof Error ? err.message : String(err));
-      showToast('Failed to add comment', 'error');
+      showToast('Failed to update comment', 'error');
     } finally {
       setIsSubmitting(false);
     }
   };
 
+  // 編集履歴の表示切り替え
+  const handleToggleHistory = async (commentId: number) => {
+    if (historyId === commentId) {
+      setHistoryId(null);
+      return;
+    }
+    try {
+      setHistory(await getGroupCommentHistory(commentId));
+      setHistoryId(commentId);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+      showToast('Failed to load comment history', 'error');
+    }
+  };
+
   // コメント削除
   const handleDeleteComment = async (commentId: number) => {
-    if (!confirm('Are you sure you want to delete this comment?')) return;
+    const hasReplies = comments.some((c) => c.parent_comment_id === commentId);
+    const message = hasReplies
+      ? 'Are you sure you want to delete this comment and all of its replies?'
+      : 'Are you sure you want to delete this comment?';
+    if (!confirm(message)) return;
 
     try {
       await deleteGroupComment(commentId);
@@ -95,6 +176,141 @@ function GroupComments({ groupId }: GroupCommentsProps) {
     });
   };
 
+  // スレッド構造（最上位は新しい順、返信は古い順で会話の流れに合わせる）
+  const commentIds = new Set(comments.map((c) => c.id));
+  const rootComments = comments.filter(
+    (c) => c.parent_comment_id === null || !commentIds.has(c.parent_comment_id)
+  );
+  const repliesOf = (commentId: number) =>
+    comments.filter((c) => c.parent_comment_id === commentId).reverse();
+
+  const textareaClassName =
+    'flex-1 px-4 py-2 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500 dark:focus:ring-blue-400 resize-none disabled:opacity-50';
+  const submitButtonClassName =
+    'px-4 py-2 bg-blue-500 hover:bg-blue-600 dark:bg-blue-600 dark:hover:bg-blue-700 text-white rounded-lg transition-colors disabled:opacity-50 disabled:cursor-not-allowed flex items-center gap-2';
+  const actionButtonClassName =
+    'opacity-0 group-hover:opacity-100 p-2 text-gray-500 hover:text-gray-700 dark:text-gray-400 dark:hover:text-gray-200 transition-opacity rounded-lg hover:bg-gray-200 dark:hover:bg-gray-500';
+
+  const renderComment = (comment: GroupComment, depth: number) => {
+    const replies = repliesOf(comment.id);
+
+    return (
+      <div key={comment.id} className={depth > 0 ? 'ml-6 pl-4 border-l-2 border-gray-200 dark:border-gray-600' : ''}>
+        <div className="group flex items-start gap-3 p-4 bg-gray-50 dark:bg-gray-700 rounded-lg hover:bg-gray-100 dark:hover:bg-gray-600 transition-colors">
+          <div className="flex-1 min-w-0">
+            {editingId === comment.id ? (
+              <form onSubmit={(e) => handleUpdateComment(e, comment.id)} className="flex gap-3">
+                <textarea
+                  value={editText}
+                  onChange={(e) => setEditText(e.target.value)}
+                  maxLength={500}
+                  rows={2}
+                  disabled={isSubmitting}
+                  className={textareaClassName}
+                  aria-label="Edit comment"
+                />
+                <div className="flex flex-col gap-2">
+                  <button type="submit" disabled={isSubmitting || !editText.trim()} className={submitButtonClassName}>
+                    Save
+                  </button>
+                  <button
+                    type="button"
+                    onClick={() => setEditingId(null)}
+                    className="px-4 py-2 text-gray-700 dark:text-gray-300 hover:bg-gray-200 dark:hover:bg-gray-500 rounded-lg transition-colors"
+                  >
+                    Cancel
+                  </button>
+                </div>
+              </form>
+            ) : (
+              <p className="text-gray-900 dark:text-gray-100 whitespace-pre-wrap break-words">
+                {comment.comment}
+              </p>
+            )}
+            <p className="text-sm text-gray-500 dark:text-gray-400 mt-2">
+              {comment.author && (
+                <span className="font-medium text-gray-700 dark:text-gray-300">{comment.author} · </span>
+              )}
+              {formatTimestamp(comment.created_at)}
+              {comment.updated_at && (
+                <button
+                  onClick={() => handleToggleHistory(comment.id)}
+                  className="ml-2 inline-flex items-center gap-1 hover:underline"
+                  title={`Edited ${formatTimestamp(comment.updated_at)}`}
+                >
+                  <History size={12} />
+                  edited
+                </button>
+              )}
+            </p>
+            {historyId === comment.id && (
+              <ul className="mt-2 space-y-1 text-sm text-gray-500 dark:text-gray-400 border-t border-gray-200 dark:border-gray-600 pt-2">
+                {history.map((edit) => (
+                  <li key={edit.id} className="whitespace-pre-wrap break-words">
+                    <span className="text-xs">{formatTimestamp(edit.edited_at)}: </span>
+                    {edit.comment}
+                  </li>
+                ))}
+              </ul>
+            )}
+          </div>
+          <button
+            onClick={() => {
+              setReplyToId(replyToId === comment.id ? null : comment.id);
+              setReplyText('');
+            }}
+            className={actionButtonClassName}
+            title="Reply"
+          >
+            <Reply size={16} />
+          </button>
+          <button
+            onClick={() => {
+              setEditingId(comment.id);
+              setEditText(comment.comment);
+            }}
+            className={actionButtonClassName}
+            title="Edit comment"
+          >
+            <Pencil size={16} />
+          </button>
+          <button
+            onClick={() => handleDeleteComment(comment.id)}
+            className="opacity-0 group-hover:opacity-100 p-2 text-red-500 hover:text-red-600 dark:text-red-400 dark:hover:text-red-300 transition-opacity rounded-lg hover:bg-red-50 dark:hover:bg-red-900/20"
+            title="Delete comment"
+          >
+            <Trash2 size={16} />
+          </button>
+        </div>
+
+        {/* 返信フォーム */}
+        {replyToId === comment.id && (
+          <form onSubmit={(e) => handleReply(e, comment.id)} className="ml-6 mt-2 flex gap-3">
+            <textarea
+              value={replyText}
+              onChange={(e) => setReplyText(e.target.value)}
+              placeholder="Write a reply..."
+              maxLength={500}
+              rows={2}
+              disabled={isSubmitting}
+              className={textareaClassName}
+            />
+            <button type="submit" disabled={isSubmitting || !replyText.trim()} className={submitButtonClassName}>
+              <Send size={16} />
+              <span className="hidden sm:inline">Reply</span>
+            </button>
+          </form>
+        )}
+
+        {replies.length > 0 && (
+          <div className="mt-2 space-y-2">
+            {replies.map((reply) => renderComment(reply, depth + 1))}
+          </div>
+        )}
+      </div>
+    );
+  };
+
   return (
     <div className="bg-white dark:bg-gray-800 rounded-lg border border-gray-200 dark:border-gray-700 overflow-hidden">
       {/* ヘッダー */}
@@ -112,12 +328,12 @@ function GroupComments({ groupId }: GroupCommentsProps) {
             maxLength={500}
             rows={2}
             disabled={isSubmitting}
-            className="flex-1 px-4 py-2 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500 dark:focus:ring-blue-400 resize-none disabled:opacity-50"
+            className={textareaClassName}
           />
           <button
             type="submit"
             disabled={isSubmitting || !newComment.trim()}
-            className="px-4 py-2 bg-blue-500 hover:bg-blue-600 dark:bg-blue-600 dark:hover:bg-blue-700 text-white rounded-lg transition-colors disabled:opacity-50 disabled:cursor-not-allowed flex items-center gap-2"
+            className={submitButtonClassName}
           >
             <Send size={16} />
             <span className="hidden sm:inline">Send</span>
@@ -142,28 +358,7 @@ function GroupComments({ groupId }: GroupCommentsProps) {
           </div>
         ) : (
           <div className="space-y-4">
-            {comments.map((comment) => (
-              <div
-                key={comment.id}
-                className="group flex items-start gap-3 p-4 bg-gray-50 dark:bg-gray-700 rounded-lg hover:bg-gray-100 dark:hover:bg-gray-600 transition-colors"
-              >
-                <div className="flex-1 min-w-0">
-                  <p className="text-gray-900 dark:text-gray-100 whitespace-pre-wrap break-words">
-                    {comment.comment}
-                  </p>
-                  <p className="text-sm text-gray-500 dark:text-gray-400 mt-2">
-                    {formatTimestamp(comment.created_at)}
-                  </p>
-                </div>
-                <button
-                  onClick={() => handleDeleteComment(comment.id)}
-                  className="opacity-0 group-hover:opacity-100 p-2 text-red-500 hover:text-red-600 dark:text-red-400 dark:hover:text-red-300 transition-opacity rounded-lg hover:bg-red-50 dark:hover:bg-red-900/20"
-                  title="Delete comment"
-                >
-                  <Trash2 size={16} />
-                </button>
-              </div>
-            ))}
+            {rootComments.map((comment) => renderComment(comment, 0))}
           </div>
         )}
       </div>
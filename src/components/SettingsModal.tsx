@@ -1,5 +1,5 @@
 import { useState, useRef } from 'react';
-import { X, Database, AlertTriangle } from 'lucide-react';
+import { X, Database, AlertTriangle, User } from 'lucide-react';
 import { backupDatabase, resetDatabase } from '../utils/tauri-commands';
 import { useImageStore } from '../store/imageStore';
 import ExportSection from './settings/ExportSection';
@@ -12,7 +12,7 @@ interface SettingsModalProps {
 
 /**
  * 設定モーダルコンポーネント
- * データベース管理機能、エクスポート/インポート機能を提供します
+ * コメントの投稿者名、データベース管理機能、エクスポート/インポート機能を提供します
  */
 export default function SettingsModal({ onClose }: SettingsModalProps) {
   const [isResetting, setIsResetting] = useState(false);
@@ -20,6 +20,8 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
   const [createBackup, setCreateBackup] = useState(true);
   const [message, setMessage] = useState<{ type: 'success' | 'error'; text: string } | null>(null);
   const { setImages } = useImageStore();
+  const commentAuthor = useImageStore(s => s.commentAuthor);
+  const setCommentAuthor = useImageStore(s => s.setCommentAuthor);
   const dialogRef = useRef<HTMLDivElement>(null);
   useFocusTrap(dialogRef, true);
 
@@ -103,6 +105,28 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
             </div>
           )}
 
+          {/* コメントの投稿者名 */}
+          <div>
+            <div className="flex items-center gap-2 mb-4">
+              <User size={20} className="text-gray-700 dark:text-gray-300" />
+              <h3 className="text-lg font-semibold text-gray-900 dark:text-gray-100">コメント</h3>
+            </div>
+            <label htmlFor="comment-author" className="block text-sm text-gray-600 dark:text-gray-400 mb-2">
+              グループコメントの投稿者名（空欄の場合は名前なしで投稿）
+            </label>
+            <input
+              id="comment-author"
+              type="text"
+              value={commentAuthor}
+              onChange={(e) => setCommentAuthor(e.target.value)}
+              maxLength={100}
+              placeholder="名前"
+              className="w-full px-3 py-2 border border-gray-300 dark:border-gray-600 rounded bg-white dark:bg-gray-700 text-gray-900 dark:text-gray-100 placeholder-gray-400 dark:placeholder-gray-500 focus:outline-none focus:ring-2 focus:ring-blue-500 dark:focus:ring-blue-400"
+            />
+          </div>
+
+          <hr className="border-gray-200 dark:border-gray-700" />
+
           {/* エクスポートセクション */}
           <ExportSection onMessage={setMessage} />
 
@@ -70,9 +70,15 @@ export default function Sidebar({ isOpen }: SidebarProps) {
       return;
     }
 
+    // サブグループがある場合は、まとめて削除するか一つ上の階層に移すかを選ぶ
+    const hasSubgroups = groups.some((group) => group.parent_id === groupId);
+    const cascade =
+      hasSubgroups &&
+      confirm('Also delete its subgroups? Choose Cancel to move them up one level instead.');
+
     try {
       setIsDeleting(true);
-      await deleteGroup(groupId);
+      await deleteGroup(groupId, cascade);
       const updatedGroups = await getAllGroups();
       setGroups(updatedGroups);
 
@@ -1,6 +1,6 @@
 import { useRef, useMemo, useCallback } from 'react';
 import { useVirtualizer } from '@tanstack/react-virtual';
-import { useImageStore } from '../../store/imageStore';
+import { useImageStore, getImageDate } from '../../store/imageStore';
 import MediaCard from '../MediaCard';
 import type { ImageData, DateGroup } from '../../types/image';
 
@@ -46,7 +46,7 @@ export default function TimelineView({ images, onImageClick }: TimelineViewProps
     const groupMap = new Map<string, ImageData[]>();
 
     for (const img of images) {
-      const date = new Date(img.created_at);
+      const date = getImageDate(img);
       const key = `${date.getFullYear()}-${String(date.getMonth() + 1).padStart(2, '0')}`;
       const group = groupMap.get(key);
       if (group) {
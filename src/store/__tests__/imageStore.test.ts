@@ -121,6 +121,17 @@ describe('imageStore', () => {
       expect(filtered[0].file_name).toBe('apple.jpg');
     });
 
+    it('filters by place name in search query', () => {
+      const store = useImageStore.getState();
+      store.setImages([
+        ...sampleImages,
+        makeImage({ id: 5, file_name: 'temple.jpg', country: 'Japan', region: 'Kyoto', city: 'Kyoto' }),
+      ]);
+      store.setSearchQuery('kyoto');
+      const filtered = store.getSortedImages();
+      expect(filtered.map((i) => i.id)).toEqual([5]);
+    });
+
     it('filters by tags ANY mode', () => {
       const store = useImageStore.getState();
       store.setFilterSettings({ selectedTags: ['yellow', 'red'], tagFilterMode: 'any' });
@@ -136,6 +147,20 @@ describe('imageStore', () => {
       expect(filtered).toHaveLength(1);
       expect(filtered[0].id).toBe(1);
     });
+
+    it('includes descendant tags when filtering by a parent tag', () => {
+      const store = useImageStore.getState();
+      store.setImages([
+        makeImage({ id: 5, tags: ['people/family/alice'] }),
+        makeImage({ id: 6, tags: ['people/friends'] }),
+        makeImage({ id: 7, tags: ['peoplewatching'] }),
+      ]);
+      store.setFilterSettings({ selectedTags: ['people'], tagFilterMode: 'any' });
+      expect(store.getSortedImages().map((i) => i.id).sort()).toEqual([5, 6]);
+
+      store.setFilterSettings({ selectedTags: ['people/family'] });
+      expect(store.getSortedImages().map((i) => i.id)).toEqual([5]);
+    });
   });
 
   describe('getAllTags', () => {
@@ -153,6 +178,22 @@ describe('imageStore', () => {
       expect(tagsWithCount[0]).toEqual({ tag: 'fruit', count: 3 });
       expect(tagsWithCount).toHaveLength(4);
     });
+
+    it('counts parent tags once per image with descendant tags', () => {
+      const store = useImageStore.getState();
+      store.setImages([
+        makeImage({ id: 5, tags: ['people/family/alice', 'people/family/bob'] }),
+        makeImage({ id: 6, tags: ['people/friends'] }),
+      ]);
+      const counts = Object.fromEntries(store.getTagsWithCount().map(({ tag, count }) => [tag, count]));
+      expect(counts).toEqual({
+        people: 2,
+        'people/family': 1,
+        'people/family/alice': 1,
+        'people/family/bob': 1,
+        'people/friends': 1,
+      });
+    });
   });
 
   describe('selection mode', () => {
@@ -7,6 +7,29 @@ import type { ImageData, FileType, GroupData } from '../types/image';
  */
 export type SortBy = 'name' | 'created_at' | 'rating';
 
+/**
+ * 画像の日付（撮影日時、未取得の場合は登録日時）
+ * taken_at はローカル時刻の "YYYY-MM-DD HH:MM:SS" のため、ISO形式に直してローカル時刻として解釈する
+ */
+export function getImageDate(image: ImageData): Date {
+  return image.taken_at ? new Date(image.taken_at.replace(' ', 'T')) : new Date(image.created_at);
+}
+
+/**
+ * 階層タグとその親タグ（"people/family/alice" → ["people", "people/family", "people/family/alice"]）
+ */
+export function getTagWithAncestors(tag: string): string[] {
+  const levels = tag.split('/');
+  return levels.map((_, i) => levels.slice(0, i + 1).join('/'));
+}
+
+/**
+ * 画像のタグが指定タグ、またはその子孫のタグを含むか
+ */
+export function hasTagOrDescendant(image: ImageData, tag: string): boolean {
+  return image.tags.some((t) => t === tag || t.startsWith(`${tag}/`));
+}
+
 /**
  * ソート順序の型
  */
@@ -96,6 +119,8 @@ interface ImageStore {
   gridDensity: GridDensity;
   /** サイドバーが折りたたまれているかどうか */
   isSidebarCollapsed: boolean;
+  /** グループコメントの投稿者名（空文字列は未設定） */
+  commentAuthor: string;
 
   // Phase 4: グループ管理
   /** 読み込まれたグループの配列 */
@@ -174,6 +199,8 @@ interface ImageStore {
   setGridDensity: (density: GridDensity) => void;
   /** サイドバーの折りたたみ状態を切り替えます */
   toggleSidebarCollapsed: () => void;
+  /** グループコメントの投稿者名を設定します */
+  setCommentAuthor: (author: string) => void;
 
   // Phase 4: グループ関連アクション
   /** グループの配列を設定します */
@@ -245,6 +272,7 @@ export const useImageStore = create<ImageStore>()(
       viewMode: 'grid',
       gridDensity: 'normal',
       isSidebarCollapsed: false,
+      commentAuthor: '',
 
       // Phase 4: グループ管理
       groups: [],
@@ -292,7 +320,7 @@ export const useImageStore = create<ImageStore>()(
         const { images } = get();
         const tagsSet = new Set<string>();
         images.forEach((img) => {
-          img.tags.forEach((tag) => tagsSet.add(tag));
+          img.tags.forEach((tag) => getTagWithAncestors(tag).forEach((t) => tagsSet.add(t)));
         });
         return Array.from(tagsSet).sort();
       },
@@ -300,7 +328,9 @@ export const useImageStore = create<ImageStore>()(
         const { images } = get();
         const tagCounts = new Map<string, number>();
         images.forEach((img) => {
-          img.tags.forEach((tag) => {
+          // 親タグは子孫のタグが付いた画像も数える（1画像につき1回）
+          const tags = new Set(img.tags.flatMap(getTagWithAncestors));
+          tags.forEach((tag) => {
             tagCounts.set(tag, (tagCounts.get(tag) || 0) + 1);
           });
         });
@@ -318,11 +348,11 @@ export const useImageStore = create<ImageStore>()(
             return false;
           }
 
-          // 検索クエリでフィルター
+          // 検索クエリでフィルター（ファイル名・撮影地）
           if (searchQuery.trim()) {
             const query = searchQuery.toLowerCase();
-            const fileName = img.file_name.toLowerCase();
-            if (!fileName.includes(query)) {
+            const targets = [img.file_name, img.city, img.region, img.country];
+            if (!targets.some((target) => target?.toLowerCase().includes(query))) {
               return false;
             }
           }
@@ -341,12 +371,12 @@ export const useImageStore = create<ImageStore>()(
             return false;
           }
 
-          // タグでフィルター（選択されたタグがある場合のみ）
+          // タグでフィルター（選択されたタグがある場合のみ、親タグは子孫のタグも含む）
           if (filterSettings.selectedTags.length > 0) {
             if (filterSettings.tagFilterMode === 'all') {
               // AND検索: 選択されたタグを全て含む
               const hasAllTags = filterSettings.selectedTags.every((tag) =>
-                img.tags.includes(tag)
+                hasTagOrDescendant(img, tag)
               );
               if (!hasAllTags) {
                 return false;
@@ -354,7 +384,7 @@ export const useImageStore = create<ImageStore>()(
             } else {
               // OR検索: 選択されたタグのいずれかを含む
               const hasAnyTag = filterSettings.selectedTags.some((tag) =>
-                img.tags.includes(tag)
+                hasTagOrDescendant(img, tag)
               );
               if (!hasAnyTag) {
                 return false;
@@ -374,7 +404,7 @@ export const useImageStore = create<ImageStore>()(
               comparison = a.file_name.localeCompare(b.file_name);
               break;
             case 'created_at':
-              comparison = new Date(a.created_at).getTime() - new Date(b.created_at).getTime();
+              comparison = getImageDate(a).getTime() - getImageDate(b).getTime();
               break;
             case 'rating':
               comparison = a.rating - b.rating;
@@ -403,6 +433,7 @@ export const useImageStore = create<ImageStore>()(
       // Phase 6: 表示モードアクション
       setViewMode: (mode) => set({ viewMode: mode }),
       setGridDensity: (density) => set({ gridDensity: density }),
+      setCommentAuthor: (author) => set({ commentAuthor: author }),
       toggleSidebarCollapsed: () => set((state) => ({ isSidebarCollapsed: !state.isSidebarCollapsed })),
 
       // Phase 4: グループ関連アクション
@@ -512,6 +543,7 @@ export const useImageStore = create<ImageStore>()(
         viewMode: state.viewMode,
         gridDensity: state.gridDensity,
         isSidebarCollapsed: state.isSidebarCollapsed,
+        commentAuthor: state.commentAuthor,
       }),
     }
   )
@@ -25,6 +25,28 @@ export const makeImage = (overrides: Partial<ImageData> = {}): ImageData => ({
   audio_codec: null,
   thumbnail_path: null,
   directory_id: null,
+  file_size: null,
+  file_mtime: null,
+  is_missing: 0,
+  content_hash: null,
+  perceptual_hash: null,
+  captured_at: null,
+  camera_make: null,
+  camera_model: null,
+  lens_model: null,
+  iso: null,
+  exposure_time: null,
+  f_number: null,
+  orientation: null,
+  taken_at: null,
+  taken_at_source: null,
+  latitude: null,
+  longitude: null,
+  altitude: null,
+  country_code: null,
+  country: null,
+  region: null,
+  city: null,
   ...overrides,
 });
 
@@ -40,6 +62,9 @@ export const makeGroup = (overrides: Partial<GroupData> = {}): GroupData => ({
   created_at: '2026-01-15T10:00:00Z',
   updated_at: '2026-01-15T10:00:00Z',
   image_count: 0,
+  smart_query: null,
+  parent_id: null,
+  sort_order: 0,
   ...overrides,
 });
 
@@ -44,6 +44,80 @@ export interface ImageData {
   thumbnail_path: string | null;
   /** 所属ディレクトリID */
   directory_id: number | null;
+  /** ファイルサイズ（バイト）- 差分スキャン用 */
+  file_size: number | null;
+  /** ファイルの最終更新日時（UNIX秒）- 差分スキャン用 */
+  file_mtime: number | null;
+  /** ディスク上にファイルが見つからないかどうか（1: 欠損, 0: 正常） */
+  is_missing: number;
+  /** ファイル内容のハッシュ（重複検出用、未計算ならnull） */
+  content_hash: string | null;
+  /** 知覚ハッシュ（類似画像検出用、16進文字列、未計算ならnull） */
+  perceptual_hash: string | null;
+  /** 撮影日時（EXIF/XMP、"YYYY-MM-DD HH:MM:SS"） */
+  captured_at: string | null;
+  /** カメラメーカー */
+  camera_make: string | null;
+  /** カメラ機種 */
+  camera_model: string | null;
+  /** レンズ */
+  lens_model: string | null;
+  /** ISO感度 */
+  iso: number | null;
+  /** シャッタースピード（秒） */
+  exposure_time: number | null;
+  /** 絞り値（F値） */
+  f_number: number | null;
+  /** EXIFの回転情報（1-8） */
+  orientation: number | null;
+  /** 撮影日時（"YYYY-MM-DD HH:MM:SS"、ローカル時刻。並び替え・タイムライン用） */
+  taken_at: string | null;
+  /** 撮影日時の取得元 */
+  taken_at_source: TakenAtSource | null;
+  /** 撮影位置の緯度（GPS、南緯は負） */
+  latitude: number | null;
+  /** 撮影位置の経度（GPS、西経は負） */
+  longitude: number | null;
+  /** 撮影位置の標高（m） */
+  altitude: number | null;
+  /** 撮影地の国コード（ISO 3166-1 alpha-2） */
+  country_code: string | null;
+  /** 撮影地の国名 */
+  country: string | null;
+  /** 撮影地の地域（都道府県・州など） */
+  region: string | null;
+  /** 撮影地の都市 */
+  city: string | null;
+}
+
+/**
+ * 撮影日時の取得元
+ * - exif: EXIF/XMPの撮影日時
+ * - video: 動画のcreation_time
+ * - mtime: ファイルの更新日時（撮影日時が取得できなかった場合）
+ */
+export type TakenAtSource = 'exif' | 'video' | 'mtime';
+
+/**
+ * 撮影日ごとの件数
+ */
+export interface TakenDateCount {
+  /** 撮影日（"YYYY-MM-DD"） */
+  date: string;
+  count: number;
+}
+
+/**
+ * タグと付いている画像数
+ */
+export interface TagCount {
+  id: number;
+  /** 階層タグは "/" 区切りのパス（"people/family/alice"） */
+  name: string;
+  /** 親タグのID（最上位のタグは null） */
+  parent_id: number | null;
+  /** このタグまたは子孫のタグが付いている画像の数 */
+  count: number;
 }
 
 /**
@@ -61,6 +135,30 @@ export interface ImageMetadataUpdate {
   rating?: number;
   /** 更新するお気に入り状態（オプション） */
   is_favorite?: number;
+  /** trueの場合、変更内容をaction_logに記録する（オプション、デフォルト: false） */
+  record_action?: boolean;
+}
+
+/**
+ * 複数画像のメタデータを一括更新するためのインターフェース
+ */
+export interface BulkUpdateImagesInput {
+  /** 更新対象の画像IDの配列 */
+  image_ids: number[];
+  /** 更新するコメント（オプション） */
+  comment?: string;
+  /** 置き換えるタグの配列（オプション） */
+  tags?: string[];
+  /** 追加するタグの配列（オプション） */
+  add_tags?: string[];
+  /** 削除するタグの配列（オプション） */
+  remove_tags?: string[];
+  /** 更新する評価値（オプション） */
+  rating?: number;
+  /** 更新するお気に入り状態（オプション） */
+  is_favorite?: number;
+  /** trueの場合、変更内容をaction_logに記録する（オプション、デフォルト: false） */
+  record_action?: boolean;
 }
 
 /**
@@ -95,6 +193,172 @@ export type DirectoryData = {
   file_count: number;
   /** 作成日時 */
   created_at: string;
+  /** 評価・タグ・コメントの変更をXMPサイドカーに書き出すか（1: true, 0: false） */
+  write_xmp_sidecars: number;
+};
+
+/**
+ * スキャン結果をDBに同期した結果のサマリー
+ */
+export type SyncSummary = {
+  /** ディレクトリID */
+  directory_id: number;
+  /** ディレクトリの絶対パス */
+  directory_path: string;
+  /** スキャンしたファイル数 */
+  scanned: number;
+  /** 新規登録したファイル数 */
+  inserted: number;
+  /** 更新したファイル数 */
+  updated: number;
+  /** 変更のなかったファイル数 */
+  unchanged: number;
+  /** 移動・リネームを検出して既存データに再リンクしたファイル数 */
+  relinked: number;
+  /** 欠損としてマークしたファイル数 */
+  missing: number;
+  /** XMPサイドカーから評価・タグ・コメントを取り込んだファイル数 */
+  sidecars_imported: number;
+};
+
+/**
+ * XMPサイドカー書き出しの結果
+ */
+export type XmpSidecarSummary = {
+  /** 書き出したファイル数 */
+  written: number;
+  /** 失敗したファイル数 */
+  failed: number;
+};
+
+/**
+ * ライブラリ整合性チェックの結果サマリー
+ */
+export type ReconcileSummary = {
+  /** チェックしたディレクトリ数 */
+  directories_checked: number;
+  /** 見つからずスキップしたディレクトリ数 */
+  directories_skipped: number;
+  /** 再リンクしたファイル数 */
+  relinked: number;
+  /** 欠損としてマークしたファイル数 */
+  missing: number;
+  /** 欠損マークを解除したファイル数 */
+  restored: number;
+};
+
+/**
+ * 重複クラスタ内の画像情報
+ */
+export type DuplicateImage = {
+  /** 画像ID */
+  id: number;
+  /** ファイルパス */
+  file_path: string;
+  /** ファイル名 */
+  file_name: string;
+  /** ファイルサイズ（バイト） */
+  file_size: number | null;
+  /** 所属ディレクトリID */
+  directory_id: number | null;
+  /** 所属ディレクトリのパス */
+  directory_path: string | null;
+  /** 評価 */
+  rating: number;
+  /** お気に入り */
+  is_favorite: number;
+};
+
+/**
+ * 内容ハッシュが一致する重複ファイルのまとまり
+ */
+export type DuplicateCluster = {
+  /** 共通の内容ハッシュ */
+  content_hash: string;
+  /** ファイルサイズ（バイト） */
+  file_size: number | null;
+  /** 重複している画像 */
+  images: DuplicateImage[];
+};
+
+/**
+ * サムネイルのサイズ（長辺 small: 200px, medium: 400px, large: 800px）
+ */
+export type ThumbnailSize = 'small' | 'medium' | 'large';
+
+/**
+ * サムネイルキャッシュのクリーンアップ結果
+ */
+export type ThumbnailGcSummary = {
+  /** 削除したファイル数 */
+  files_removed: number;
+  /** 解放したバイト数 */
+  bytes_reclaimed: number;
+};
+
+/**
+ * バックグラウンドジョブの状態
+ */
+export type JobStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+
+/**
+ * バックグラウンドジョブの情報（job-progress イベントのペイロード）
+ */
+export type JobInfo = {
+  /** ジョブID */
+  id: number;
+  /** ジョブの種類（'scan' | 'thumbnails'） */
+  kind: string;
+  /** 状態 */
+  status: JobStatus;
+  /** 処理対象の総数 */
+  total: number;
+  /** 処理済みの数 */
+  processed: number;
+  /** 処理中のファイル */
+  current_file: string | null;
+  /** 失敗時のエラーメッセージ */
+  error: string | null;
+  /** 完了時の結果（scan: SyncSummary[], thumbnails: { generated, failed }） */
+  result: unknown;
+};
+
+/**
+ * 類似画像の検索結果
+ */
+export type SimilarImage = {
+  /** 類似画像 */
+  image: ImageData;
+  /** 知覚ハッシュのハミング距離（0で見た目がほぼ同一） */
+  distance: number;
+};
+
+/**
+ * 指定地点からの距離付きの画像
+ */
+export type NearbyImage = {
+  image: ImageData;
+  /** 指定地点からの距離（km） */
+  distance_km: number;
+};
+
+/**
+ * 地名ごとの件数
+ */
+export interface PlaceCount {
+  country_code: string | null;
+  country: string | null;
+  region: string | null;
+  city: string | null;
+  count: number;
+}
+
+/**
+ * 見た目が似ている画像のまとまり
+ */
+export type SimilarCluster = {
+  /** クラスタに含まれる画像 */
+  images: ImageData[];
 };
 
 /**
@@ -155,8 +419,29 @@ export interface GroupData {
   created_at: string;
   /** 最終更新日時（ISO 8601形式） */
   updated_at: string;
-  /** グループ内の画像数 */
+  /** グループ内の画像数（スマートグループは検索クエリに一致する件数） */
   image_count: number;
+  /** スマートグループの検索クエリ（手動で画像を追加するグループは null） */
+  smart_query: string | null;
+  /** 親グループのID（最上位のグループは null） */
+  parent_id: number | null;
+  /** 手動で並べ替えた順番（小さいほど前） */
+  sort_order: number;
+}
+
+/**
+ * グループの並び順
+ * manual: reorderGroups で並べ替えた順 / name: 名前順 / created: 作成日時の新しい順
+ * updated: 更新日時の新しい順 / image_count: 画像数の多い順
+ */
+export type GroupSort = 'manual' | 'name' | 'created' | 'updated' | 'image_count';
+
+/**
+ * グループの階層（get_group_tree の戻り値）
+ */
+export interface GroupTreeNode extends GroupData {
+  /** サブグループ */
+  children: GroupTreeNode[];
 }
 
 /**
@@ -171,6 +456,10 @@ export interface CreateGroupInput {
   color?: string;
   /** 代表画像ID（任意） */
   representative_image_id?: number;
+  /** 指定するとスマートグループとして作成（例: "rating>=4 tag:travel"） */
+  smart_query?: string;
+  /** 親グループID（任意、省略時は最上位） */
+  parent_id?: number;
 }
 
 /**
@@ -187,6 +476,10 @@ export interface UpdateGroupInput {
   color?: string;
   /** 代表画像ID（任意） */
   representative_image_id?: number;
+  /** 検索クエリ（任意、スマートグループのみ） */
+  smart_query?: string;
+  /** 親グループID（任意、null で最上位に移動） */
+  parent_id?: number | null;
 }
 
 // ============================================================
@@ -205,6 +498,26 @@ export type GroupComment = {
   comment: string;
   /** 作成日時（ISO 8601形式） */
   created_at: string;
+  /** 最後に編集した日時（未編集は null） */
+  updated_at: string | null;
+  /** 投稿者名（未設定は null） */
+  author: string | null;
+  /** 返信先のコメントID（グループへの直接のコメントは null） */
+  parent_comment_id: number | null;
+};
+
+/**
+ * グループコメントの編集履歴（編集前の本文）
+ */
+export type GroupCommentEdit = {
+  /** 履歴ID */
+  id: number;
+  /** コメントID */
+  comment_id: number;
+  /** 編集前の本文 */
+  comment: string;
+  /** 編集した日時 */
+  edited_at: string;
 };
 
 /**
@@ -215,4 +528,38 @@ export type AddCommentInput = {
   group_id: number;
   /** コメント本文 */
   comment: string;
+  /** 投稿者名（任意） */
+  author?: string | null;
+  /** 返信先のコメントID（任意） */
+  parent_comment_id?: number | null;
+};
+
+/**
+ * グループ内の画像のキャプション（同じ画像でもグループごとに異なる）
+ */
+export type GroupImageCaption = {
+  /** 画像ID */
+  image_id: number;
+  /** キャプション */
+  caption: string;
+};
+
+/**
+ * 全文検索の結果
+ */
+export type SearchResult = {
+  /** 一致した対象の種類 */
+  kind: 'image' | 'group' | 'group_comment';
+  /** 画像ID・グループID・グループコメントID（kind による） */
+  id: number;
+  /** コメントが属するグループのID（group_comment のみ） */
+  group_id: number | null;
+  /** 画像のファイル名、またはグループ名 */
+  title: string;
+  /** スニペットを取り出した項目 */
+  field: 'file_name' | 'comment' | 'tags' | 'name' | 'description';
+  /** 一致箇所を <mark>…</mark> で囲んだ抜粋（HTMLエスケープされていない） */
+  snippet: string;
+  /** 一致度（FTS5 の bm25）。小さいほど上位 */
+  rank: number;
 };
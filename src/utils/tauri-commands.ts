@@ -1,22 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import Database from '@tauri-apps/plugin-sql';
-import type { ImageData, ImageMetadataUpdate, GroupData, CreateGroupInput, UpdateGroupInput, GroupComment, AddCommentInput, DirectoryData, ActionLogEntry } from '../types/image';
-
-/**
- * ファイルシステムからスキャンされたメディアファイル情報
- */
-interface ImageFileInfo {
-  file_path: string;
-  file_name: string;
-  file_type: string;
-
-  // Phase 3追加
-  duration_seconds?: number;
-  width?: number;
-  height?: number;
-  video_codec?: string;
-  audio_codec?: string;
-}
+import type { ImageData, ImageMetadataUpdate, BulkUpdateImagesInput, SyncSummary, ReconcileSummary, TakenDateCount, TagCount, DuplicateCluster, SimilarImage, SimilarCluster, NearbyImage, PlaceCount, ThumbnailSize, ThumbnailGcSummary, JobInfo, GroupData, GroupSort, GroupTreeNode, CreateGroupInput, UpdateGroupInput, GroupComment, GroupCommentEdit, AddCommentInput, GroupImageCaption, SearchResult, DirectoryData, XmpSidecarSummary, ActionLogEntry } from '../types/image';
 
 /**
  * データベースを初期化します
@@ -56,27 +39,10 @@ export async function backupDatabase(): Promise<string> {
  * @throws リセットに失敗した場合
  */
 export async function resetDatabase(): Promise<string> {
-  dbInstance = null;
   dbInitialized = false;
   return await invoke<string>('reset_database');
 }
 
-/**
- * データベース接続を取得します（シングルトン）
- * Database.load() はURLベースでキャッシュされた共有プールを返すため、
- * close() すると他の呼び出し元でも "closed pool" エラーが発生する。
- * そのため接続は閉じずに再利用する。
- */
-let dbInstance: Database | null = null;
-
-async function getDatabase(): Promise<Database> {
-  if (dbInstance) return dbInstance;
-  const dbPath = await getDatabasePath();
-  const dbUrl = `sqlite:${dbPath}`;
-  dbInstance = await Database.load(dbUrl);
-  return dbInstance;
-}
-
 // 後のステップで追加する関数のプレースホルダー
 
 /**
@@ -93,144 +59,19 @@ export async function selectDirectory(): Promise<string | null> {
  * @returns スキャンされたメディアファイルデータの配列
  */
 export async function scanDirectory(path: string): Promise<ImageData[]> {
-  // ファイルシステムからメディアファイルをスキャン
-  const fileInfos = await invoke<ImageFileInfo[]>('scan_directory', { path });
-
-  // データベースに接続
-  const db = await getDatabase();
-
-  // ディレクトリレコードが存在するか確認、なければ自動作成
-  let dirResult = await db.select<{ id: number }[]>(
-    'SELECT id FROM directories WHERE path = $1',
-    [path]
-  );
-
-  let directoryId: number | null = null;
-  if (dirResult.length > 0) {
-    directoryId = dirResult[0].id;
-  } else {
-    // ディレクトリ名を抽出
-    const dirName = path.split('/').filter(Boolean).pop() || path;
-    await db.execute(
-      'INSERT INTO directories (path, name) VALUES ($1, $2)',
-      [path, dirName]
-    );
-    dirResult = await db.select<{ id: number }[]>(
-      'SELECT id FROM directories WHERE path = $1',
-      [path]
-    );
-    if (dirResult.length > 0) {
-      directoryId = dirResult[0].id;
-    }
-  }
-
-  // データベースにファイルを挿入
-  for (const fileInfo of fileInfos) {
-    // 既に存在するかチェック
-    const existing = await db.select<{ count: number }[]>(
-      'SELECT COUNT(*) as count FROM images WHERE file_path = $1',
-      [fileInfo.file_path]
-    );
-
-    if (existing[0].count === 0) {
-      await db.execute(
-        'INSERT INTO images (file_path, file_name, file_type, duration_seconds, width, height, video_codec, audio_codec, directory_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)',
-        [
-          fileInfo.file_path,
-          fileInfo.file_name,
-          fileInfo.file_type,
-          fileInfo.duration_seconds ?? null,
-          fileInfo.width ?? null,
-          fileInfo.height ?? null,
-          fileInfo.video_codec ?? null,
-          fileInfo.audio_codec ?? null,
-          directoryId,
-        ]
-      );
-    } else if (directoryId !== null) {
-      // 既存画像のdirectory_idを更新（NULLの場合のみ）
-      await db.execute(
-        'UPDATE images SET directory_id = $1 WHERE file_path = $2 AND directory_id IS NULL',
-        [directoryId, fileInfo.file_path]
-      );
-    }
-  }
-
-  // ディレクトリのファイル数とスキャン日時を更新
-  if (directoryId !== null) {
-    await db.execute(
-      'UPDATE directories SET file_count = (SELECT COUNT(*) FROM images WHERE directory_id = $1), last_scanned_at = CURRENT_TIMESTAMP WHERE id = $1',
-      [directoryId]
-    );
-  }
+  // スキャン結果をバックエンドでDBに同期（ディレクトリ未登録なら自動作成）
+  await invoke<SyncSummary>('sync_directory', { path });
 
   // すべてのメディアファイルを取得して返す
   return await getAllImages();
 }
 
-/**
- * DB行データから ImageData に変換します
- */
-function rowToImageData(row: {
-  id: number;
-  file_path: string;
-  file_name: string;
-  file_type: string;
-  comment: string | null;
-  tags: string | null;
-  rating: number;
-  is_favorite: number;
-  created_at: string;
-  updated_at: string;
-  duration_seconds: number | null;
-  width: number | null;
-  height: number | null;
-  video_codec: string | null;
-  audio_codec: string | null;
-  thumbnail_path: string | null;
-  directory_id: number | null;
-}): ImageData {
-  return {
-    id: row.id,
-    file_path: row.file_path,
-    file_name: row.file_name,
-    file_type: (row.file_type as 'image' | 'video') || 'image',
-    comment: row.comment,
-    tags: row.tags ? (() => {
-      try {
-        return JSON.parse(row.tags);
-      } catch (e) {
-        console.error('Failed to parse tags for image:', row.id, e);
-        return [];
-      }
-    })() : [],
-    rating: row.rating,
-    is_favorite: row.is_favorite,
-    created_at: row.created_at,
-    updated_at: row.updated_at,
-    duration_seconds: row.duration_seconds,
-    width: row.width,
-    height: row.height,
-    video_codec: row.video_codec,
-    audio_codec: row.audio_codec,
-    thumbnail_path: row.thumbnail_path,
-    directory_id: row.directory_id,
-  };
-}
-
-/** SELECT対象のカラム */
-const IMAGE_COLUMNS = 'id, file_path, file_name, file_type, comment, tags, rating, is_favorite, created_at, updated_at, duration_seconds, width, height, video_codec, audio_codec, thumbnail_path, directory_id';
-
 /**
  * データベースからすべての画像を取得します
  * @returns すべての画像データの配列
  */
 export async function getAllImages(): Promise<ImageData[]> {
-  const db = await getDatabase();
-  const results = await db.select<Array<Parameters<typeof rowToImageData>[0]>>(
-    `SELECT ${IMAGE_COLUMNS} FROM images ORDER BY created_at DESC, id DESC`
-  );
-  return results.map(rowToImageData);
+  return await invoke<ImageData[]>('get_images', { directoryId: null });
 }
 
 /**
@@ -239,57 +80,196 @@ export async function getAllImages(): Promise<ImageData[]> {
  * @returns ディレクトリに紐づく画像データの配列
  */
 export async function getImagesByDirectoryId(directoryId: number): Promise<ImageData[]> {
-  const db = await getDatabase();
-  const results = await db.select<Array<Parameters<typeof rowToImageData>[0]>>(
-    `SELECT ${IMAGE_COLUMNS} FROM images WHERE directory_id = $1 ORDER BY created_at DESC, id DESC`,
-    [directoryId]
-  );
-  return results.map(rowToImageData);
+  return await invoke<ImageData[]>('get_images', { directoryId });
+}
+
+/**
+ * 検索クエリに一致する画像をDBから取得します
+ * 例: `rating>=4 tag:travel -tag:blurry type:video duration>30 taken:2024-05 dir:"Camera Roll" group:"Trip"`
+ * @param query 空白区切りの条件（すべてを満たす画像を返す）
+ * @returns 一致した画像データの配列（撮影日時の新しい順）
+ * @throws 構文エラーの場合（メッセージにクエリ中の位置 column を含む）
+ */
+export async function queryImages(query: string): Promise<ImageData[]> {
+  return await invoke<ImageData[]>('query_images', { query });
 }
 
 /**
  * 画像のメタデータを更新します
  * @param data 更新するメタデータ
+ * @returns 更新後の画像データ
+ * @throws バリデーションエラー、または画像が存在しない場合
  */
 export async function updateImageMetadata(
   data: ImageMetadataUpdate
-): Promise<void> {
-  const db = await getDatabase();
+): Promise<ImageData> {
+  return await invoke<ImageData>('update_image_metadata', { input: data });
+}
 
-  const updates: string[] = [];
-  const values: any[] = [];
-  let paramIndex = 1;
+/**
+ * 複数画像のメタデータを一括更新します
+ * @param input 一括更新入力データ
+ * @returns 実際に変更された画像数
+ * @throws バリデーションエラー、または画像が存在しない場合
+ */
+export async function bulkUpdateImages(input: BulkUpdateImagesInput): Promise<number> {
+  return await invoke<number>('bulk_update_images', { input });
+}
 
-  // 更新するフィールドを動的に構築
-  if (data.rating !== undefined) {
-    updates.push(`rating = $${paramIndex++}`);
-    values.push(data.rating);
-  }
+/**
+ * 撮影日時が指定範囲内の画像・動画を取得します（撮影日時の古い順）
+ * @param start 開始日（"YYYY-MM-DD" または "YYYY-MM-DD HH:MM:SS"、省略時は制限なし）
+ * @param end 終了日（日付のみの場合はその日の終わりまで、省略時は制限なし）
+ * @param directoryId 指定時はそのディレクトリの画像のみ
+ * @returns 画像データの配列
+ * @throws 日付の形式が不正な場合
+ */
+export async function getImagesByDateRange(
+  start?: string,
+  end?: string,
+  directoryId?: number
+): Promise<ImageData[]> {
+  return await invoke<ImageData[]>('get_images_by_date_range', { start, end, directoryId });
+}
 
-  if (data.comment !== undefined) {
-    updates.push(`comment = $${paramIndex++}`);
-    values.push(data.comment);
-  }
+/**
+ * 撮影日ごとの画像・動画の件数を取得します（日付の新しい順）
+ * @param directoryId 指定時はそのディレクトリの画像のみ
+ * @returns 撮影日ごとの件数
+ */
+export async function getTakenDateCounts(directoryId?: number): Promise<TakenDateCount[]> {
+  return await invoke<TakenDateCount[]>('get_taken_date_counts', { directoryId });
+}
 
-  if (data.tags !== undefined) {
-    updates.push(`tags = $${paramIndex++}`);
-    values.push(JSON.stringify(data.tags));
-  }
+/**
+ * ライブラリ内のタグ一覧を取得します（付いている画像数の多い順）
+ * 件数には子孫のタグが付いた画像も含みます
+ * @returns タグと画像数の配列
+ */
+export async function listTags(): Promise<TagCount[]> {
+  return await invoke<TagCount[]>('list_tags');
+}
 
-  if (data.is_favorite !== undefined) {
-    updates.push(`is_favorite = $${paramIndex++}`);
-    values.push(data.is_favorite);
-  }
+/**
+ * タグで画像・動画を絞り込みます（子孫のタグが付いた画像も含む）
+ * @param name タグ名（"people" で "people/family/alice" の画像も返す）
+ * @returns 画像データの配列
+ */
+export async function getImagesByTag(name: string): Promise<ImageData[]> {
+  return await invoke<ImageData[]>('get_images_by_tag', { name });
+}
 
-  // updated_atを現在時刻に更新
-  updates.push(`updated_at = CURRENT_TIMESTAMP`);
+/**
+ * タグの名前をライブラリ全体で変更します（子孫のタグも付け替え）
+ * @param name 変更するタグ名
+ * @param newName 新しいタグ名（"/" 区切りにすると別の親の下に移動）
+ * @returns タグ（子孫を含む）が付いている画像の数
+ * @throws タグが存在しない場合、新しい名前のタグが既にある場合
+ */
+export async function renameTag(name: string, newName: string): Promise<number> {
+  return await invoke<number>('rename_tag', { name, newName });
+}
 
-  // WHERE句のためのIDを追加
-  values.push(data.id);
+/**
+ * タグを子孫ごと別の親タグの下に移動します
+ * @param name 移動するタグ名
+ * @param newParent 移動先の親タグ（省略時は最上位に移動）
+ * @returns タグ（子孫を含む）が付いている画像の数
+ * @throws タグが存在しない場合、移動先に同名のタグがある場合
+ */
+export async function moveTag(name: string, newParent?: string): Promise<number> {
+  return await invoke<number>('move_tag', { name, newParent: newParent ?? null });
+}
 
-  const query = `UPDATE images SET ${updates.join(', ')} WHERE id = $${paramIndex}`;
+/**
+ * 複数のタグを1つのタグに統合します（統合元のタグは削除されます）
+ * @param sourceNames 統合元のタグ名
+ * @param targetName 統合先のタグ名（なければ作成）
+ * @returns タグが変わった画像の数
+ * @throws 統合元のタグが存在しない場合、子タグを持つ場合
+ */
+export async function mergeTags(sourceNames: string[], targetName: string): Promise<number> {
+  return await invoke<number>('merge_tags', { sourceNames, targetName });
+}
 
-  await db.execute(query, values);
+/**
+ * タグを子孫のタグごとライブラリ全体から削除します
+ * @param name 削除するタグ名
+ * @returns タグが外れた画像の数
+ * @throws タグが存在しない場合
+ */
+export async function deleteTag(name: string): Promise<number> {
+  return await invoke<number>('delete_tag', { name });
+}
+
+/**
+ * 撮影位置が指定範囲内の画像・動画を取得します（撮影日時の新しい順）
+ * minLongitude > maxLongitude の場合は日付変更線をまたぐ範囲として扱います
+ * @returns 画像データの配列
+ * @throws 緯度・経度が範囲外の場合
+ */
+export async function findImagesInBounds(
+  minLatitude: number,
+  minLongitude: number,
+  maxLatitude: number,
+  maxLongitude: number
+): Promise<ImageData[]> {
+  return await invoke<ImageData[]>('find_images_in_bounds', {
+    minLatitude,
+    minLongitude,
+    maxLatitude,
+    maxLongitude,
+  });
+}
+
+/**
+ * 指定地点から半径 radiusKm 以内で撮影された画像・動画を取得します（近い順）
+ * @param latitude 中心の緯度
+ * @param longitude 中心の経度
+ * @param radiusKm 半径（km）
+ * @returns 距離付きの画像のリスト
+ * @throws 緯度・経度が範囲外、または半径が0以下の場合
+ */
+export async function findImagesNear(
+  latitude: number,
+  longitude: number,
+  radiusKm: number
+): Promise<NearbyImage[]> {
+  return await invoke<NearbyImage[]>('find_images_near', { latitude, longitude, radiusKm });
+}
+
+/**
+ * 画像・動画に記録された地名の一覧を件数付きで取得します（件数の多い順）
+ * @returns 地名ごとの件数
+ */
+export async function listPlaces(): Promise<PlaceCount[]> {
+  return await invoke<PlaceCount[]>('list_places');
+}
+
+/**
+ * 地名で画像・動画を絞り込みます（指定した項目をすべて満たすもの、大文字小文字は区別しない）
+ * @param place country は国名または国コード、region は都道府県・州など、city は都市名
+ * @returns 画像データの配列
+ * @throws いずれの項目も指定されていない場合
+ */
+export async function getImagesByPlace(place: {
+  country?: string;
+  region?: string;
+  city?: string;
+}): Promise<ImageData[]> {
+  return await invoke<ImageData[]>('get_images_by_place', {
+    country: place.country ?? null,
+    region: place.region ?? null,
+    city: place.city ?? null,
+  });
+}
+
+/**
+ * 撮影位置を持つ全画像の地名を同梱の地名辞書で求め直します
+ * @returns 地名が変わった画像の数
+ */
+export async function reverseGeocodeImages(): Promise<number> {
+  return await invoke<number>('reverse_geocode_images');
 }
 
 /**
@@ -318,6 +298,42 @@ export async function generateVideoThumbnail(
   });
 }
 
+/**
+ * メタデータ抽出（ffprobe等）の並列数を取得します
+ * @returns 設定値（0は自動 = CPUコア数）
+ */
+export async function getMetadataWorkers(): Promise<number> {
+  return await invoke<number>('get_metadata_workers');
+}
+
+/**
+ * メタデータ抽出（ffprobe等）の並列数を設定します
+ * @param count 並列数（1〜16、省略または0で自動）
+ * @returns 実際に使用される並列数
+ */
+export async function setMetadataWorkers(count?: number): Promise<number> {
+  return await invoke<number>('set_metadata_workers', { count: count ?? null });
+}
+
+/**
+ * 画像・動画のサムネイルを取得します（キャッシュになければ生成）
+ * @param imageId 画像ID
+ * @param size サムネイルのサイズ（省略時は medium）
+ * @returns サムネイル画像のパス
+ * @throws 画像が見つからない、またはサムネイル生成に失敗した場合
+ */
+export async function getThumbnail(imageId: number, size?: ThumbnailSize): Promise<string> {
+  return await invoke<string>('get_thumbnail', { imageId, size: size ?? null });
+}
+
+/**
+ * サムネイルキャッシュから削除済み画像・古いサムネイルを削除します
+ * @returns 削除したファイル数と解放したバイト数
+ */
+export async function gcThumbnails(): Promise<ThumbnailGcSummary> {
+  return await invoke<ThumbnailGcSummary>('gc_thumbnails');
+}
+
 // ============================================================
 // Phase 4: グループ管理API
 // ============================================================
@@ -334,11 +350,31 @@ export async function createGroup(input: CreateGroupInput): Promise<number> {
 
 /**
  * すべてのグループを取得します
+ * @param sort 並び順（省略時は作成日時の新しい順）
  * @returns グループデータの配列（画像数を含む）
  * @throws グループ取得に失敗した場合
  */
-export async function getAllGroups(): Promise<GroupData[]> {
-  return await invoke<GroupData[]>('get_all_groups');
+export async function getAllGroups(sort?: GroupSort): Promise<GroupData[]> {
+  return await invoke<GroupData[]>('get_all_groups', { sort });
+}
+
+/**
+ * グループを階層構造で取得します
+ * @param sort 同じ階層の並び順（省略時は名前順）
+ * @returns 最上位のグループの配列（children にサブグループ）
+ * @throws グループ取得に失敗した場合
+ */
+export async function getGroupTree(sort?: GroupSort): Promise<GroupTreeNode[]> {
+  return await invoke<GroupTreeNode[]>('get_group_tree', { sort });
+}
+
+/**
+ * グループの並び順（sort が manual のときの順番）を変更します
+ * @param orderedIds グループIDの配列（指定しなかったグループは後ろに元の順番で続く）
+ * @throws 存在しないグループや重複がある場合
+ */
+export async function reorderGroups(orderedIds: number[]): Promise<void> {
+  return await invoke<void>('reorder_groups', { orderedIds });
 }
 
 /**
@@ -353,20 +389,32 @@ export async function updateGroup(input: UpdateGroupInput): Promise<void> {
 /**
  * グループを削除します
  * @param groupId グループID
+ * @param cascade true ならサブグループもまとめて削除、false ならサブグループを一つ上の階層に移動
  * @throws グループ削除に失敗した場合
  */
-export async function deleteGroup(groupId: number): Promise<void> {
-  return await invoke<void>('delete_group', { groupId });
+export async function deleteGroup(groupId: number, cascade = false): Promise<void> {
+  return await invoke<void>('delete_group', { groupId, cascade });
 }
 
 /**
  * 画像をグループに追加します
  * @param imageIds 画像IDの配列
  * @param groupId グループID
+ * @param position 挿入する位置（0始まり、省略時は末尾）
  * @throws 画像追加に失敗した場合
  */
-export async function addImagesToGroup(imageIds: number[], groupId: number): Promise<void> {
-  return await invoke<void>('add_images_to_group', { imageIds, groupId });
+export async function addImagesToGroup(imageIds: number[], groupId: number, position?: number): Promise<void> {
+  return await invoke<void>('add_images_to_group', { imageIds, groupId, position });
+}
+
+/**
+ * グループ内の画像の並び順を変更します
+ * @param groupId グループID
+ * @param orderedIds グループに所属する全画像のID（新しい順番）
+ * @throws スマートグループの場合、または画像の指定が所属と一致しない場合
+ */
+export async function reorderGroupImages(groupId: number, orderedIds: number[]): Promise<void> {
+  return await invoke<void>('reorder_group_images', { groupId, orderedIds });
 }
 
 /**
@@ -382,11 +430,12 @@ export async function removeImagesFromGroup(imageIds: number[], groupId: number)
 /**
  * グループに所属する画像IDの配列を取得します
  * @param groupId グループID
+ * @param includeSubgroups true ならサブグループの画像も含める
  * @returns 画像IDの配列
  * @throws 画像ID取得に失敗した場合
  */
-export async function getGroupImages(groupId: number): Promise<number[]> {
-  return await invoke<number[]>('get_group_images', { groupId });
+export async function getGroupImages(groupId: number, includeSubgroups = false): Promise<number[]> {
+  return await invoke<number[]>('get_group_images', { groupId, includeSubgroups });
 }
 
 /**
@@ -444,7 +493,27 @@ export async function getGroupComments(groupId: number): Promise<GroupComment[]>
 }
 
 /**
- * グループコメントを削除します
+ * グループコメントを編集します（編集前の本文は履歴に残ります）
+ * @param commentId コメントID
+ * @param comment 新しい本文
+ * @throws コメントが存在しない場合、または本文が不正な場合
+ */
+export async function updateGroupComment(commentId: number, comment: string): Promise<void> {
+  return await invoke<void>('update_group_comment', { commentId, comment });
+}
+
+/**
+ * グループコメントの編集履歴を取得します（新しい順）
+ * @param commentId コメントID
+ * @returns 編集前の本文の配列
+ * @throws コメントが存在しない場合
+ */
+export async function getGroupCommentHistory(commentId: number): Promise<GroupCommentEdit[]> {
+  return await invoke<GroupCommentEdit[]>('get_group_comment_history', { commentId });
+}
+
+/**
+ * グループコメントを削除します（返信と編集履歴も削除されます）
  * @param commentId コメントID
  * @throws コメント削除に失敗した場合
  */
@@ -452,6 +521,42 @@ export async function deleteGroupComment(commentId: number): Promise<void> {
   return await invoke<void>('delete_group_comment', { commentId });
 }
 
+/**
+ * グループ内の画像のキャプションを設定します
+ * @param groupId グループID
+ * @param imageId 画像ID
+ * @param caption キャプション（null または空文字列で削除）
+ * @throws 画像がグループに所属していない場合、またはスマートグループの場合
+ */
+export async function setGroupImageCaption(groupId: number, imageId: number, caption: string | null): Promise<void> {
+  return await invoke<void>('set_group_image_caption', { groupId, imageId, caption });
+}
+
+/**
+ * グループ内の画像のキャプションを取得します（キャプションのある画像のみ、グループ内の並び順）
+ * @param groupId グループID
+ * @returns キャプションの配列
+ * @throws キャプション取得に失敗した場合
+ */
+export async function getGroupImageCaptions(groupId: number): Promise<GroupImageCaption[]> {
+  return await invoke<GroupImageCaption[]>('get_group_image_captions', { groupId });
+}
+
+// ============================================================
+// 全文検索API
+// ============================================================
+
+/**
+ * 画像のファイル名・コメント・タグ、グループ名・説明、グループコメントを全文検索します
+ * 空白区切りの語をすべて含むものを一致度順に返します
+ * @param query 検索語（空白区切り）
+ * @param limit 最大件数（省略時は50、最大500）
+ * @throws 検索語が空の場合
+ */
+export async function search(query: string, limit?: number): Promise<SearchResult[]> {
+  return await invoke<SearchResult[]>('search', { query, limit });
+}
+
 // ============================================================
 // Phase 6: マルチディレクトリ管理API
 // ============================================================
@@ -493,36 +598,10 @@ export async function setDirectoryActive(directoryId: number, isActive: boolean)
 /**
  * 指定ディレクトリを再スキャンしてDBに登録します
  * @param directoryId ディレクトリID
- * @returns スキャンされたファイル情報
+ * @returns 全画像データ
  */
 export async function scanSingleDirectory(directoryId: number): Promise<ImageData[]> {
-  const fileInfos = await invoke<ImageFileInfo[]>('scan_single_directory', { directoryId });
-  const db = await getDatabase();
-
-  for (const fileInfo of fileInfos) {
-    const existing = await db.select<{ count: number }[]>(
-      'SELECT COUNT(*) as count FROM images WHERE file_path = $1',
-      [fileInfo.file_path]
-    );
-
-    if (existing[0].count === 0) {
-      await db.execute(
-        'INSERT INTO images (file_path, file_name, file_type, duration_seconds, width, height, video_codec, audio_codec, directory_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, (SELECT id FROM directories WHERE path = (SELECT path FROM directories WHERE id = $9)))',
-        [
-          fileInfo.file_path,
-          fileInfo.file_name,
-          fileInfo.file_type,
-          fileInfo.duration_seconds ?? null,
-          fileInfo.width ?? null,
-          fileInfo.height ?? null,
-          fileInfo.video_codec ?? null,
-          fileInfo.audio_codec ?? null,
-          directoryId,
-        ]
-      );
-    }
-  }
-
+  await invoke<SyncSummary>('sync_single_directory', { directoryId });
   return await getAllImages();
 }
 
@@ -531,35 +610,84 @@ export async function scanSingleDirectory(directoryId: number): Promise<ImageDat
  * @returns 全画像データ
  */
 export async function scanAllActiveDirectories(): Promise<ImageData[]> {
-  const fileInfos = await invoke<ImageFileInfo[]>('scan_all_active_directories');
-  const db = await getDatabase();
-
-  for (const fileInfo of fileInfos) {
-    const existing = await db.select<{ count: number }[]>(
-      'SELECT COUNT(*) as count FROM images WHERE file_path = $1',
-      [fileInfo.file_path]
-    );
-
-    if (existing[0].count === 0) {
-      await db.execute(
-        'INSERT INTO images (file_path, file_name, file_type, duration_seconds, width, height, video_codec, audio_codec) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)',
-        [
-          fileInfo.file_path,
-          fileInfo.file_name,
-          fileInfo.file_type,
-          fileInfo.duration_seconds ?? null,
-          fileInfo.width ?? null,
-          fileInfo.height ?? null,
-          fileInfo.video_codec ?? null,
-          fileInfo.audio_codec ?? null,
-        ]
-      );
-    }
-  }
-
+  await invoke<SyncSummary[]>('sync_all_active_directories');
   return await getAllImages();
 }
 
+/**
+ * 全登録ディレクトリの整合性チェックを行います
+ * 消えたファイルを欠損としてマークし、移動・リネームされたファイルを既存データに再リンクします
+ * @returns チェック結果サマリー
+ */
+export async function reconcileLibrary(): Promise<ReconcileSummary> {
+  return await invoke<ReconcileSummary>('reconcile_library');
+}
+
+/**
+ * 欠損としてマークされた画像をDBから削除します
+ * @param imageIds 削除対象の画像ID（省略時は全欠損画像）
+ * @returns 削除した件数
+ */
+export async function purgeMissingImages(imageIds?: number[]): Promise<number> {
+  return await invoke<number>('purge_missing_images', { imageIds: imageIds ?? null });
+}
+
+/**
+ * 内容ハッシュが一致する重複ファイルをクラスタとして取得します
+ * @returns 重複クラスタのリスト
+ */
+export async function findDuplicates(): Promise<DuplicateCluster[]> {
+  return await invoke<DuplicateCluster[]>('find_duplicates');
+}
+
+/**
+ * 重複画像のメタデータ・グループ所属を残す画像に統合します
+ * 重複側の行とファイルは削除しません
+ * @param keeperId 残す画像のID
+ * @param duplicateIds 統合元の重複画像ID
+ * @returns 統合した件数
+ */
+export async function mergeDuplicates(keeperId: number, duplicateIds: number[]): Promise<number> {
+  return await invoke<number>('merge_duplicates', { keeperId, duplicateIds });
+}
+
+/**
+ * 指定画像と見た目が似ている画像を取得します（距離の近い順）
+ * @param imageId 基準となる画像ID
+ * @param maxDistance 許容するハミング距離（0〜64、省略時は10）
+ * @returns 類似画像のリスト
+ */
+export async function findSimilar(imageId: number, maxDistance?: number): Promise<SimilarImage[]> {
+  return await invoke<SimilarImage[]>('find_similar', { imageId, maxDistance: maxDistance ?? null });
+}
+
+/**
+ * ライブラリ全体で見た目が似ている画像をクラスタにまとめます
+ * @param maxDistance 許容するハミング距離（0〜64、省略時は10）
+ * @returns 類似クラスタのリスト（大きい順）
+ */
+export async function clusterSimilarImages(maxDistance?: number): Promise<SimilarCluster[]> {
+  return await invoke<SimilarCluster[]>('cluster_similar_images', { maxDistance: maxDistance ?? null });
+}
+
+/**
+ * ディレクトリのXMPサイドカー書き出しを有効/無効にします
+ * @param directoryId ディレクトリID
+ * @param enabled 書き出すかどうか
+ */
+export async function setDirectoryXmpSidecars(directoryId: number, enabled: boolean): Promise<void> {
+  return await invoke<void>('set_directory_xmp_sidecars', { directoryId, enabled });
+}
+
+/**
+ * 画像の評価・タグ・コメントをXMPサイドカー（画像ファイル名 + .xmp）に書き出します
+ * @param imageIds 書き出す画像ID（省略時は書き出しが有効なディレクトリの全画像）
+ * @returns 書き出し件数と失敗件数
+ */
+export async function writeXmpSidecars(imageIds?: number[]): Promise<XmpSidecarSummary> {
+  return await invoke<XmpSidecarSummary>('write_xmp_sidecars', { imageIds: imageIds ?? null });
+}
+
 /**
  * ディレクトリ選択ダイアログを表示してディレクトリを追加します
  * @returns 追加されたディレクトリ情報、またはキャンセル時null
@@ -643,6 +771,15 @@ export async function exportMetadataCsv(outputPath: string): Promise<string> {
   return await invoke<string>('export_metadata_csv', { outputPath });
 }
 
+/**
+ * 撮影位置を持つ画像・動画をGeoJSON形式でエクスポートします
+ * @param outputPath 出力ファイルパス
+ * @returns 出力ファイルパス
+ */
+export async function exportLocationsGeojson(outputPath: string): Promise<string> {
+  return await invoke<string>('export_locations_geojson', { outputPath });
+}
+
 /**
  * JSONファイルからメタデータをインポートします
  * @param inputPath 入力ファイルパス
@@ -677,3 +814,43 @@ export async function stopFileWatcher(): Promise<void> {
 export async function getWatchedDirectories(): Promise<string[]> {
   return await invoke<string[]>('get_watched_directories');
 }
+
+// ============================================================
+// バックグラウンドジョブAPI
+// ============================================================
+
+/**
+ * ディレクトリのスキャン・同期をバックグラウンドジョブとして開始します
+ * 進捗は 'job-progress' イベント（JobInfo）で通知されます
+ * @param directoryId 対象ディレクトリID（省略時は全アクティブディレクトリ）
+ * @returns ジョブID
+ */
+export async function startScanJob(directoryId?: number): Promise<number> {
+  return await invoke<number>('start_scan_job', { directoryId: directoryId ?? null });
+}
+
+/**
+ * サムネイルの一括生成をバックグラウンドジョブとして開始します
+ * @param imageIds 対象画像ID（省略時は欠損していない全画像）
+ * @param size サムネイルのサイズ（省略時は medium）
+ * @returns ジョブID
+ */
+export async function startThumbnailJob(imageIds?: number[], size?: ThumbnailSize): Promise<number> {
+  return await invoke<number>('start_thumbnail_job', { imageIds: imageIds ?? null, size: size ?? null });
+}
+
+/**
+ * ジョブのキャンセルを要求します
+ * @param jobId ジョブID
+ * @returns キャンセルを要求できた場合 true（既に終了していた場合 false）
+ */
+export async function cancelJob(jobId: number): Promise<boolean> {
+  return await invoke<boolean>('cancel_job', { jobId });
+}
+
+/**
+ * 実行中・待機中・最近終了したジョブの一覧を取得します
+ */
+export async function listJobs(): Promise<JobInfo[]> {
+  return await invoke<JobInfo[]>('list_jobs');
+}